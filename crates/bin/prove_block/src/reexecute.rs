@@ -22,7 +22,7 @@ use starknet_os::starkware_utils::commitment_tree::errors::TreeError;
 use starknet_os::starkware_utils::commitment_tree::inner_node_fact::InnerNodeFact;
 use starknet_os::starkware_utils::commitment_tree::patricia_tree::nodes::{BinaryNodeFact, EdgeNodeFact};
 use starknet_os::storage::dict_storage::DictStorage;
-use starknet_os::storage::storage::{Fact, HashFunctionType};
+use starknet_os::storage::storage::{Fact, HashFunctionType, StorageError};
 
 /// Retrieves the transaction hash from a Blockifier `Transaction` object.
 fn get_tx_hash(tx: &Transaction) -> TransactionHash {
@@ -171,24 +171,26 @@ impl PerContractStorage for ProverPerContractStorage {
     }
 
     async fn read(&mut self, key: TreeIndex) -> Option<Felt252> {
+        self.try_read(key).await.expect("failed to read storage from the RPC node")
+    }
+
+    async fn try_read(&mut self, key: TreeIndex) -> Result<Option<Felt252>, StorageError> {
         if let Some(value) = self.ongoing_storage_changes.get(&key) {
-            Some(*value)
+            Ok(Some(*value))
         } else {
             let key_felt = Felt252::from(key.clone());
-            // TODO: this should be fallible
             let value = match self
                 .rpc_client
                 .starknet_rpc()
                 .get_storage_at(self.contract_address, key_felt, self.block_id)
                 .await
             {
-                Ok(value) => Ok(value),
-                Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => Ok(Felt252::ZERO),
-                Err(e) => Err(e),
-            }
-            .unwrap();
+                Ok(value) => value,
+                Err(ProviderError::StarknetError(StarknetError::ContractNotFound)) => Felt252::ZERO,
+                Err(e) => return Err(StorageError::Backend(e.to_string())),
+            };
             self.ongoing_storage_changes.insert(key, value);
-            Some(value)
+            Ok(Some(value))
         }
     }
 
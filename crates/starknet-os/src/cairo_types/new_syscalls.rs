@@ -60,6 +60,12 @@ pub struct GetBlockHashRequest {
     pub block_number: Felt252,
 }
 
+#[allow(unused)]
+#[derive(FieldOffsetGetters)]
+pub struct GetClassHashAtRequest {
+    pub contract_address: Felt252,
+}
+
 #[derive(FieldOffsetGetters)]
 pub struct LibraryCallRequest {
     /// The hash of the class to run.
@@ -118,6 +124,44 @@ pub struct ExecutionInfo {
     selector: Felt252,
 }
 
+#[allow(unused)]
+#[derive(FieldOffsetGetters)]
+pub struct TxInfo {
+    // The version of the transaction.
+    version: Felt252,
+    // The account contract from which this transaction originates.
+    account_contract_address: Felt252,
+    // The max_fee field of the transaction.
+    max_fee: Felt252,
+    // The signature of the transaction.
+    signature_start: Relocatable,
+    signature_end: Relocatable,
+    // The hash of the transaction.
+    transaction_hash: Felt252,
+    // The identifier of the chain.
+    chain_id: Felt252,
+    // The transaction's nonce.
+    nonce: Felt252,
+    // Fields introduced by v3 transactions, zeroed/empty for older versions.
+    resource_bounds_start: Relocatable,
+    resource_bounds_end: Relocatable,
+    tip: Felt252,
+    paymaster_data_start: Relocatable,
+    paymaster_data_end: Relocatable,
+    nonce_data_availability_mode: Felt252,
+    fee_data_availability_mode: Felt252,
+    account_deployment_data_start: Relocatable,
+    account_deployment_data_end: Relocatable,
+}
+
+#[allow(unused)]
+#[derive(FieldOffsetGetters)]
+pub struct ResourceBounds {
+    resource: Felt252,
+    max_amount: Felt252,
+    max_price_per_unit: Felt252,
+}
+
 #[allow(unused)]
 #[derive(FieldOffsetGetters)]
 pub struct BlockInfo {
@@ -18,6 +18,14 @@ pub struct ExecutionContext {
     pub deprecated_tx_info: Relocatable,
 }
 
+#[allow(unused)]
+#[derive(FieldOffsetGetters)]
+pub struct StateEntry {
+    pub class_hash: Felt252,
+    pub storage_ptr: Relocatable,
+    pub nonce: Felt252,
+}
+
 #[allow(unused)]
 #[derive(FieldOffsetGetters)]
 pub struct CompiledClassFact {
@@ -1,5 +1,7 @@
 use cairo_type_derive::FieldOffsetGetters;
 use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 
 #[allow(unused)]
@@ -318,3 +320,153 @@ pub struct EcCoordinate {
     #[allow(unused)]
     pub y: EcPoint,
 }
+
+/// Typed access to the deprecated syscall located at `syscall_ptr`, e.g.
+/// `SyscallRequestReader::new(syscall_ptr).storage_read_request().address(vm)`. The offsets of
+/// the fields are computed from the structs above, in a single place.
+#[derive(Clone, Copy, Debug)]
+pub struct SyscallRequestReader {
+    syscall_ptr: Relocatable,
+}
+
+/// Counterpart of `SyscallRequestReader` to write the responses of deprecated syscalls.
+#[derive(Clone, Copy, Debug)]
+pub struct SyscallResponseWriter {
+    syscall_ptr: Relocatable,
+}
+
+/// Declares a view over one syscall struct, with one getter or setter per felt field.
+macro_rules! syscall_view {
+    ($view:ident, reader, $($getter:ident => $offset:expr),+) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $view {
+            syscall_ptr: Relocatable,
+        }
+
+        impl $view {
+            $(
+                pub fn $getter(&self, vm: &VirtualMachine) -> Result<Felt252, HintError> {
+                    Ok(vm.get_integer((self.syscall_ptr + $offset)?)?.into_owned())
+                }
+            )+
+        }
+    };
+    ($view:ident, writer, $($setter:ident => $offset:expr),+) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $view {
+            syscall_ptr: Relocatable,
+        }
+
+        impl $view {
+            $(
+                pub fn $setter(&self, vm: &mut VirtualMachine, value: Felt252) -> Result<(), HintError> {
+                    vm.insert_value((self.syscall_ptr + $offset)?, value)?;
+                    Ok(())
+                }
+            )+
+        }
+    };
+}
+
+syscall_view!(StorageReadRequestReader, reader, address => StorageRead::request_offset() + StorageReadRequest::address_offset());
+syscall_view!(
+    StorageWriteRequestReader,
+    reader,
+    address => StorageWrite::address_offset(),
+    value => StorageWrite::value_offset()
+);
+syscall_view!(StorageReadResponseWriter, writer, set_value => StorageRead::response_offset() + StorageReadResponse::value_offset());
+syscall_view!(
+    GetBlockNumberResponseWriter,
+    writer,
+    set_block_number => GetBlockNumber::response_offset() + GetBlockNumberResponse::block_number_offset()
+);
+syscall_view!(
+    GetBlockTimestampResponseWriter,
+    writer,
+    set_block_timestamp => GetBlockTimestamp::response_offset() + GetBlockTimestampResponse::block_timestamp_offset()
+);
+syscall_view!(
+    GetContractAddressResponseWriter,
+    writer,
+    set_contract_address => GetContractAddress::response_offset() + GetContractAddressResponse::contract_address_offset()
+);
+
+impl SyscallRequestReader {
+    pub fn new(syscall_ptr: Relocatable) -> Self {
+        Self { syscall_ptr }
+    }
+
+    pub fn storage_read_request(&self) -> StorageReadRequestReader {
+        StorageReadRequestReader { syscall_ptr: self.syscall_ptr }
+    }
+
+    pub fn storage_write_request(&self) -> StorageWriteRequestReader {
+        StorageWriteRequestReader { syscall_ptr: self.syscall_ptr }
+    }
+}
+
+impl SyscallResponseWriter {
+    pub fn new(syscall_ptr: Relocatable) -> Self {
+        Self { syscall_ptr }
+    }
+
+    pub fn storage_read_response(&self) -> StorageReadResponseWriter {
+        StorageReadResponseWriter { syscall_ptr: self.syscall_ptr }
+    }
+
+    pub fn get_block_number_response(&self) -> GetBlockNumberResponseWriter {
+        GetBlockNumberResponseWriter { syscall_ptr: self.syscall_ptr }
+    }
+
+    pub fn get_block_timestamp_response(&self) -> GetBlockTimestampResponseWriter {
+        GetBlockTimestampResponseWriter { syscall_ptr: self.syscall_ptr }
+    }
+
+    pub fn get_contract_address_response(&self) -> GetContractAddressResponseWriter {
+        GetContractAddressResponseWriter { syscall_ptr: self.syscall_ptr }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fills a segment with distinct values so that any offset mistake is visible.
+    fn syscall_segment(vm: &mut VirtualMachine, size: usize) -> Relocatable {
+        let syscall_ptr = vm.add_memory_segment();
+        for i in 0..size {
+            vm.insert_value((syscall_ptr + i).unwrap(), Felt252::from(100 + i)).unwrap();
+        }
+        syscall_ptr
+    }
+
+    #[test]
+    fn test_syscall_request_reader() {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_ptr = syscall_segment(&mut vm, StorageRead::cairo_size());
+        let reader = SyscallRequestReader::new(syscall_ptr);
+
+        let offset = StorageRead::request_offset() + StorageReadRequest::address_offset();
+        let expected = vm.get_integer((syscall_ptr + offset).unwrap()).unwrap().into_owned();
+        assert_eq!(reader.storage_read_request().address(&vm).unwrap(), expected);
+
+        let expected_address =
+            vm.get_integer((syscall_ptr + StorageWrite::address_offset()).unwrap()).unwrap().into_owned();
+        let expected_value =
+            vm.get_integer((syscall_ptr + StorageWrite::value_offset()).unwrap()).unwrap().into_owned();
+        assert_eq!(reader.storage_write_request().address(&vm).unwrap(), expected_address);
+        assert_eq!(reader.storage_write_request().value(&vm).unwrap(), expected_value);
+    }
+
+    #[test]
+    fn test_syscall_response_writer() {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_ptr = vm.add_memory_segment();
+        let writer = SyscallResponseWriter::new(syscall_ptr);
+
+        writer.storage_read_response().set_value(&mut vm, Felt252::from(42)).unwrap();
+        let offset = StorageRead::response_offset() + StorageReadResponse::value_offset();
+        assert_eq!(vm.get_integer((syscall_ptr + offset).unwrap()).unwrap().into_owned(), Felt252::from(42));
+    }
+}
@@ -0,0 +1,23 @@
+use cairo_vm::Felt252;
+
+use crate::crypto::pedersen::PedersenHash;
+use crate::storage::storage::HashFunctionType;
+
+/// Hash function used by the syscall handlers, e.g. to derive the address of deployed contracts.
+///
+/// Defaults to Pedersen, see `ExecutionHelperWrapper::with_hasher` to replace it.
+pub trait Hasher {
+    fn hash(&self, a: Felt252, b: Felt252) -> Felt252;
+
+    /// Hashes `elements` as `h(h(h(h(0, e0), e1), ...), len)`, like `compute_hash_on_elements`.
+    fn hash_many(&self, elements: &[Felt252]) -> Felt252 {
+        let hash = elements.iter().fold(Felt252::ZERO, |hash, element| self.hash(hash, *element));
+        self.hash(hash, Felt252::from(elements.len()))
+    }
+}
+
+impl Hasher for PedersenHash {
+    fn hash(&self, a: Felt252, b: Felt252) -> Felt252 {
+        <PedersenHash as HashFunctionType>::hash_felts(a, b)
+    }
+}
@@ -1,2 +1,3 @@
+pub mod hasher;
 pub mod pedersen;
 pub mod poseidon;
@@ -30,6 +30,7 @@ pub const CALL_CONTRACT_GAS_COST: u64 = SYSCALL_BASE_GAS_COST + 10 * STEP_GAS_CO
 pub const DEPLOY_GAS_COST: u64 = SYSCALL_BASE_GAS_COST + 200 * STEP_GAS_COST + ENTRY_POINT_GAS_COST;
 pub const EMIT_EVENT_GAS_COST: u64 = SYSCALL_BASE_GAS_COST + 10 * STEP_GAS_COST;
 pub const GET_BLOCK_HASH_GAS_COST: u64 = SYSCALL_BASE_GAS_COST + 50 * STEP_GAS_COST;
+pub const GET_CLASS_HASH_AT_GAS_COST: u64 = SYSCALL_BASE_GAS_COST + 50 * STEP_GAS_COST;
 pub const GET_EXECUTION_INFO_GAS_COST: u64 = SYSCALL_BASE_GAS_COST + 10 * STEP_GAS_COST;
 #[allow(unused)]
 pub const KECCAK_GAS_COST: u64 = SYSCALL_BASE_GAS_COST;
@@ -77,3 +78,10 @@ pub const KECCAK_FULL_RATE_IN_U64S: u64 = 17;
 // The hexadecimal string "0x000000000000000000000000496e76616c696420696e707574206c656e677468"
 // decodes to "Invalid input length".
 pub const INVALID_INPUT_LENGTH_ERROR: &str = "0x000000000000000000000000496e76616c696420696e707574206c656e677468";
+
+// Maximal depth of nested contract calls, same as the default `max_recursion_depth` of Starknet.
+pub const MAX_CALL_DEPTH: usize = 50;
+
+// Maximal length of the constructor calldata of the deploy syscall, same as the maximal calldata
+// length accepted by the Starknet gateway.
+pub const MAX_CONSTRUCTOR_CALLDATA_LEN: usize = 4000;
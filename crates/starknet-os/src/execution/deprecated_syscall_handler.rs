@@ -1,21 +1,24 @@
 use std::rc::Rc;
 
 use blockifier::blockifier::block::BlockInfo;
+use cairo_vm::hint_processor::hint_processor_utils::felt_to_usize;
 use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
 use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 use tokio::sync::RwLock;
 
-use super::helper::ExecutionHelperWrapper;
+use super::helper::{ExecutionHelperWrapper, L1ToL2Message};
 use crate::cairo_types::new_syscalls::{BlockInfo as BlockInfoStruct, ExecutionInfo};
 use crate::cairo_types::syscalls::{
-    CallContract, CallContractResponse, Deploy, DeployResponse, GetBlockNumber, GetBlockNumberResponse,
-    GetBlockTimestamp, GetBlockTimestampResponse, GetContractAddress, GetContractAddressResponse, GetSequencerAddress,
+    CallContract, CallContractRequest, CallContractResponse, Deploy, DeployResponse, GetSequencerAddress,
     GetSequencerAddressResponse, GetTxInfo, GetTxInfoResponse, GetTxSignature, GetTxSignatureResponse, LibraryCall,
-    TxInfo,
+    SyscallResponseWriter, TxInfo,
 };
+use crate::execution::syscall_handler_backend::SyscallHandlerBackend;
+use crate::execution::syscall_handler_utils::{unimplemented_syscall, SyscallExecutionError, SyscallSelector};
 use crate::starknet::starknet_storage::PerContractStorage;
+use crate::utils::{execute_coroutine, read_felt_span};
 
 /// DeprecatedSyscallHandler implementation for execution of system calls in the StarkNet OS
 #[derive(Debug)]
@@ -26,6 +29,7 @@ where
     pub exec_wrapper: ExecutionHelperWrapper<PCS>,
     pub syscall_ptr: Relocatable,
     block_info: BlockInfo,
+    strict_syscalls: bool,
 }
 
 /// DeprecatedOsSyscallHandler is wrapped in Rc<RefCell<_>> in order
@@ -47,6 +51,20 @@ where
     }
 }
 
+/// Reads the message from L1 handled by a `delegate_l1_handler` request.
+fn read_l1_to_l2_message(vm: &VirtualMachine, syscall_ptr: Relocatable) -> Result<L1ToL2Message, HintError> {
+    let to_address = vm.get_integer((syscall_ptr + CallContractRequest::contract_address_offset())?)?.into_owned();
+    let selector = vm.get_integer((syscall_ptr + CallContractRequest::function_selector_offset())?)?.into_owned();
+    let calldata_size = felt_to_usize(&vm.get_integer((syscall_ptr + CallContractRequest::calldata_size_offset())?)?)?;
+    let calldata_ptr = vm.get_relocatable((syscall_ptr + CallContractRequest::calldata_offset())?)?;
+    let calldata = read_felt_span(vm, calldata_ptr, calldata_size)?;
+
+    let (from_address, payload) = calldata.split_first().ok_or(HintError::SyscallError(
+        "L1 handler calldata must start with the L1 sender address".to_string().into_boxed_str(),
+    ))?;
+    Ok(L1ToL2Message { from_address: *from_address, to_address, selector, payload: payload.to_vec() })
+}
+
 impl<PCS> DeprecatedOsSyscallHandlerWrapper<PCS>
 where
     PCS: PerContractStorage,
@@ -58,10 +76,16 @@ where
                 exec_wrapper,
                 syscall_ptr,
                 block_info,
+                strict_syscalls: false,
             })),
         }
     }
 
+    /// See `OsSyscallHandlerWrapper::set_strict_syscalls`.
+    pub async fn set_strict_syscalls(&self, strict_syscalls: bool) {
+        self.deprecated_syscall_handler.write().await.strict_syscalls = strict_syscalls;
+    }
+
     async fn call_contract_and_write_response(
         &self,
         syscall_ptr: Relocatable,
@@ -91,15 +115,28 @@ where
     pub async fn call_contract(&self, syscall_ptr: Relocatable, vm: &mut VirtualMachine) -> Result<(), HintError> {
         self.call_contract_and_write_response(syscall_ptr, CallContract::response_offset(), vm).await
     }
-    #[allow(unused)]
+    /// Replays a delegate call. The request has the same layout as `call_contract`, the only
+    /// difference being that the callee code runs in the context of the caller's storage.
     pub async fn delegate_call(&self, syscall_ptr: Relocatable, vm: &mut VirtualMachine) -> Result<(), HintError> {
+        let delegated_address =
+            vm.get_integer((syscall_ptr + CallContractRequest::contract_address_offset())?)?.into_owned();
+        log::debug!("delegate_call() syscall, syscall_ptr = {}, delegated to {}", syscall_ptr, delegated_address);
+
         self.call_contract_and_write_response(syscall_ptr, CallContract::response_offset(), vm).await
     }
+    /// Replays a call to an L1 handler. The first calldata element is the L1 sender and the rest is
+    /// the message payload, see `ExecutionHelper::consume_l1_handler_message`.
     pub async fn delegate_l1_handler(
         &self,
         syscall_ptr: Relocatable,
         vm: &mut VirtualMachine,
     ) -> Result<(), HintError> {
+        let message = read_l1_to_l2_message(vm, syscall_ptr)?;
+        {
+            let syscall_handler = self.deprecated_syscall_handler.read().await;
+            syscall_handler.exec_wrapper.execution_helper.write().await.consume_l1_handler_message(&message)?;
+        }
+
         self.call_contract_and_write_response(syscall_ptr, CallContract::response_offset(), vm).await
     }
     pub async fn deploy(&self, syscall_ptr: Relocatable, vm: &mut VirtualMachine) -> Result<(), HintError> {
@@ -129,8 +166,9 @@ where
         Ok(())
     }
 
-    pub fn emit_event(&self) {
+    pub async fn emit_event(&self) -> Result<(), HintError> {
         // Nothing to do
+        Ok(())
     }
 
     pub async fn get_block_number(&self, syscall_ptr: Relocatable, vm: &mut VirtualMachine) -> Result<(), HintError> {
@@ -143,8 +181,7 @@ where
         let block_info_ptr = vm.get_relocatable((execution_info_ptr + ExecutionInfo::block_info_offset())?)?;
         let block_number = vm.get_integer((block_info_ptr + BlockInfoStruct::block_number_offset())?)?.into_owned();
 
-        let response_offset = GetBlockNumber::response_offset() + GetBlockNumberResponse::block_number_offset();
-        vm.insert_value((syscall_ptr + response_offset)?, block_number)?;
+        SyscallResponseWriter::new(syscall_ptr).get_block_number_response().set_block_number(vm, block_number)?;
 
         Ok(())
     }
@@ -164,9 +201,9 @@ where
         let block_timestamp =
             vm.get_integer((block_info_ptr + BlockInfoStruct::block_timestamp_offset())?)?.into_owned();
 
-        let response_offset =
-            GetBlockTimestamp::response_offset() + GetBlockTimestampResponse::block_timestamp_offset();
-        vm.insert_value((syscall_ptr + response_offset)?, block_timestamp)?;
+        SyscallResponseWriter::new(syscall_ptr)
+            .get_block_timestamp_response()
+            .set_block_timestamp(vm, block_timestamp)?;
 
         Ok(())
     }
@@ -198,9 +235,9 @@ where
             ))?;
         let contract_address_felt = *contract_address.0.key();
 
-        let response_offset =
-            GetContractAddress::response_offset() + GetContractAddressResponse::contract_address_offset();
-        vm.insert_value((syscall_ptr + response_offset)?, contract_address_felt)?;
+        SyscallResponseWriter::new(syscall_ptr)
+            .get_contract_address_response()
+            .set_contract_address(vm, contract_address_felt)?;
 
         Ok(())
     }
@@ -210,8 +247,11 @@ where
         vm: &mut VirtualMachine,
     ) -> Result<(), HintError> {
         let syscall_handler = self.deprecated_syscall_handler.read().await;
+        let execution_helper = syscall_handler.exec_wrapper.execution_helper.read().await;
 
-        let sequencer_address = *syscall_handler.block_info.sequencer_address.0.key();
+        let sequencer_address = execution_helper
+            .sequencer_address_override
+            .unwrap_or(*syscall_handler.block_info.sequencer_address.0.key());
 
         let response_offset =
             GetSequencerAddress::response_offset() + GetSequencerAddressResponse::sequencer_address_offset();
@@ -225,7 +265,7 @@ where
 
         let tx_info_ptr = execution_helper
             .tx_info_ptr
-            .ok_or(HintError::SyscallError("Tx info pointer not set".to_string().into_boxed_str()))?;
+            .ok_or(SyscallExecutionError::NoActiveTransaction { syscall: "get_tx_info" })?;
 
         let response_offset = GetTxInfo::response_offset() + GetTxInfoResponse::tx_info_offset();
         vm.insert_value((syscall_ptr + response_offset)?, tx_info_ptr)?;
@@ -239,7 +279,7 @@ where
 
         let tx_info_ptr = execution_helper
             .tx_info_ptr
-            .ok_or(HintError::SyscallError("Tx info pointer not set".to_string().into_boxed_str()))?;
+            .ok_or(SyscallExecutionError::NoActiveTransaction { syscall: "get_tx_signature" })?;
 
         let signature_len = vm.get_integer((tx_info_ptr + TxInfo::signature_len_offset())?)?.into_owned();
         let signature = vm.get_relocatable((tx_info_ptr + TxInfo::signature_offset())?)?;
@@ -262,11 +302,13 @@ where
     ) -> Result<(), HintError> {
         self.call_contract_and_write_response(syscall_ptr, LibraryCall::response_offset(), vm).await
     }
-    pub fn replace_class(&self) {
+    pub async fn replace_class(&self) -> Result<(), HintError> {
         // Nothing to do.
+        Ok(())
     }
-    pub fn send_message_to_l1(&self) {
+    pub async fn send_message_to_l1(&self) -> Result<(), HintError> {
         // Nothing to do
+        Ok(())
     }
     pub async fn storage_read(&self, syscall_ptr: Relocatable, vm: &mut VirtualMachine) -> Result<(), HintError> {
         let sys_hand = self.deprecated_syscall_handler.write().await;
@@ -275,7 +317,7 @@ where
                 HintError::SyscallError("No more storage reads available to replay".to_string().into_boxed_str()),
             )?;
 
-        vm.insert_value((syscall_ptr + 2usize)?, value)?;
+        SyscallResponseWriter::new(syscall_ptr).storage_read_response().set_value(vm, value)?;
 
         Ok(())
     }
@@ -289,12 +331,63 @@ where
         syscall_handler.syscall_ptr = syscall_ptr;
     }
 
-    #[allow(unused)]
     pub async fn syscall_ptr(&self) -> Relocatable {
         self.deprecated_syscall_handler.read().await.syscall_ptr
     }
 }
 
+impl<PCS> SyscallHandlerBackend for DeprecatedOsSyscallHandlerWrapper<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    fn set_syscall_ptr(&self, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        execute_coroutine(DeprecatedOsSyscallHandlerWrapper::set_syscall_ptr(self, syscall_ptr))
+    }
+
+    fn syscall_ptr(&self) -> Result<Option<Relocatable>, HintError> {
+        execute_coroutine(DeprecatedOsSyscallHandlerWrapper::syscall_ptr(self)).map(Some)
+    }
+
+    fn execute(
+        &self,
+        selector: SyscallSelector,
+        syscall_ptr: Relocatable,
+        vm: &mut VirtualMachine,
+    ) -> Result<(), HintError> {
+        execute_coroutine(async {
+            let strict_syscalls = self.deprecated_syscall_handler.read().await.strict_syscalls;
+            match selector {
+                SyscallSelector::CallContract => self.call_contract(syscall_ptr, vm).await,
+                SyscallSelector::DelegateCall => self.delegate_call(syscall_ptr, vm).await,
+                SyscallSelector::DelegateL1Handler => self.delegate_l1_handler(syscall_ptr, vm).await,
+                SyscallSelector::Deploy => self.deploy(syscall_ptr, vm).await,
+                SyscallSelector::EmitEvent => self.emit_event().await,
+                SyscallSelector::GetBlockNumber => self.get_block_number(syscall_ptr, vm).await,
+                SyscallSelector::GetBlockTimestamp => self.get_block_timestamp(syscall_ptr, vm).await,
+                SyscallSelector::GetCallerAddress => {
+                    self.get_caller_address(syscall_ptr, vm).await;
+                    Ok(())
+                }
+                SyscallSelector::GetContractAddress => self.get_contract_address(syscall_ptr, vm).await,
+                SyscallSelector::GetSequencerAddress => self.get_sequencer_address(syscall_ptr, vm).await,
+                SyscallSelector::GetTxInfo => self.get_tx_info(syscall_ptr, vm).await,
+                SyscallSelector::GetTxSignature => self.get_tx_signature(syscall_ptr, vm).await,
+                SyscallSelector::LibraryCall => self.library_call(syscall_ptr, vm).await,
+                SyscallSelector::LibraryCallL1Handler => self.library_call_l1_handler(syscall_ptr, vm).await,
+                SyscallSelector::ReplaceClass => self.replace_class().await,
+                SyscallSelector::SendMessageToL1 => self.send_message_to_l1().await,
+                SyscallSelector::StorageRead => self.storage_read(syscall_ptr, vm).await,
+                SyscallSelector::StorageWrite => self.storage_write(syscall_ptr).await,
+                _ if strict_syscalls => Err(unimplemented_syscall(selector)),
+                _ => Err(HintError::CustomHint(
+                    format!("Syscall {:?} is not supported by the deprecated syscall handler", selector)
+                        .into_boxed_str(),
+                )),
+            }
+        })?
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::borrow::Cow;
@@ -307,6 +400,7 @@ mod test {
     use blockifier::versioned_constants::VersionedConstants;
     use cairo_vm::types::exec_scope::ExecutionScopes;
     use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
+    use cairo_vm::vm::errors::hint_errors::HintError;
     use cairo_vm::vm::vm_core::VirtualMachine;
     use cairo_vm::Felt252;
     use rstest::{fixture, rstest};
@@ -318,6 +412,8 @@ mod test {
     use crate::crypto::pedersen::PedersenHash;
     use crate::execution::deprecated_syscall_handler::DeprecatedOsSyscallHandlerWrapper;
     use crate::execution::helper::{ContractStorageMap, ExecutionHelperWrapper};
+    use crate::execution::syscall_handler_backend::SyscallHandlerBackend;
+    use crate::execution::syscall_handler_utils::SyscallSelector;
     use crate::hints::vars;
     use crate::starknet::starknet_storage::OsSingleStarknetStorage;
     use crate::storage::dict_storage::DictStorage;
@@ -418,4 +514,37 @@ mod test {
             ]
         );
     }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_strict_syscalls(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_ptr = vm.add_memory_segment();
+
+        let exec_helper = ExecutionHelperWrapper::<OsSingleStarknetStorage<DictStorage, PedersenHash>>::new(
+            ContractStorageMap::default(),
+            Default::default(),
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let syscall_handler =
+            DeprecatedOsSyscallHandlerWrapper::new(exec_helper, syscall_ptr, block_context.block_info().clone());
+
+        let error = SyscallHandlerBackend::execute(&syscall_handler, SyscallSelector::Keccak, syscall_ptr, &mut vm)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("is not supported by the deprecated syscall handler"), "{error}");
+
+        syscall_handler.set_strict_syscalls(true).await;
+        let error = SyscallHandlerBackend::execute(&syscall_handler, SyscallSelector::Keccak, syscall_ptr, &mut vm)
+            .unwrap_err();
+        assert!(matches!(error, HintError::CustomHint(msg) if msg.as_ref() == "unimplemented syscall: Keccak"));
+
+        // The syscalls applied by the OS itself are no-ops, not unimplemented
+        syscall_handler.emit_event().await.unwrap();
+        syscall_handler.replace_class().await.unwrap();
+        syscall_handler.send_message_to_l1().await.unwrap();
+        syscall_handler.storage_write(syscall_ptr).await.unwrap();
+    }
 }
@@ -1,40 +1,377 @@
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::rc::Rc;
 use std::vec::IntoIter;
 
+use blockifier::blockifier::block::BlockInfo;
 use blockifier::context::BlockContext;
 use blockifier::execution::call_info::CallInfo;
+use blockifier::execution::contract_class::ContractClass;
+use blockifier::execution::entry_point::CallType;
 use blockifier::execution::entry_point_execution::CallResult;
-use blockifier::transaction::objects::TransactionExecutionInfo;
+use blockifier::transaction::objects::{TransactionExecutionInfo, TransactionInfo};
 use cairo_vm::types::relocatable::Relocatable;
 use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
+use futures::future::LocalBoxFuture;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockNumber, BlockTimestamp};
+use starknet_api::core::{ContractAddress, PatriciaKey};
 use starknet_api::deprecated_contract_class::EntryPointType;
+use starknet_os_types::chain_id::chain_id_to_felt;
 use tokio::sync::RwLock;
 
 use super::secp_handler::SecpSyscallProcessor;
-use crate::config::STORED_BLOCK_HASH_BUFFER;
+use super::syscall_handler::DeployedAddressCache;
+use super::syscall_handler_utils::SyscallSelector;
+use crate::config::{StarknetGeneralConfig, DEFAULT_STORAGE_TREE_HEIGHT, STORED_BLOCK_HASH_BUFFER};
+use crate::crypto::hasher::Hasher;
+use crate::crypto::pedersen::PedersenHash;
+use crate::error::SnOsError;
+use crate::execution::constants::MAX_CONSTRUCTOR_CALLDATA_LEN;
+use crate::execution::run_input::OsRunInput;
+use crate::execution::segment_pool::SegmentPool;
 use crate::io::input::StarknetOsInput;
+use crate::io::InternalTransaction;
 use crate::starknet::core::os::kzg_manager::KzgManager;
+use crate::starknet::core::os::os_logger::{OsLogger, SyscallProfile, SyscallUsage};
 use crate::starknet::starknet_storage::{CommitmentInfo, CommitmentInfoError, PerContractStorage};
 use crate::storage::storage::StorageError;
 
 // TODO: make the execution helper generic over the storage and hash function types.
 pub type ContractStorageMap<PCS> = HashMap<Felt252, PCS>;
 
+/// An event emitted by `contract_address` in the current transaction. `order` is the position of
+/// the event among all the events of the transaction, nested calls included.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderedEvent {
+    pub order: usize,
+    #[serde(default)]
+    pub contract_address: Felt252,
+    /// The first key of the event, set apart from `keys` when the event selector is split, see
+    /// `ExecutionHelperWrapper::with_event_selector_split`.
+    #[serde(default)]
+    pub selector: Option<Felt252>,
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
+/// A storage write, applied to the state or recorded in dry-run mode, with the value of the slot
+/// before the write.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedStorageWrite {
+    pub contract_address: Felt252,
+    pub key: Felt252,
+    pub old_value: Felt252,
+    pub new_value: Felt252,
+}
+
+impl RecordedStorageWrite {
+    /// Whether the write sets a nonzero slot to zero, which deletes it from the state.
+    pub fn clears_slot(&self) -> bool {
+        self.old_value != Felt252::ZERO && self.new_value == Felt252::ZERO
+    }
+
+    /// Whether the write sets an empty slot to a nonzero value.
+    pub fn fills_slot(&self) -> bool {
+        self.old_value == Felt252::ZERO && self.new_value != Felt252::ZERO
+    }
+}
+
+/// Reduces `key` modulo 2**251. The storage tree has 251 levels, so a felt of 252 bits addresses
+/// the same slot as its reduction: reads and writes normalize their keys the same way, and the
+/// keys of the recorded writes and accesses are normalized.
+pub fn normalize_storage_key(key: Felt252) -> Felt252 {
+    Felt252::from(&(key.to_biguint() % (BigUint::from(1u8) << DEFAULT_STORAGE_TREE_HEIGHT)))
+}
+
+/// Merges the writes to the same slot into one, which holds the value of the slot before the
+/// first write and after the last one. Slots are listed in the order of their first write.
+pub fn coalesce_storage_writes(writes: &[RecordedStorageWrite]) -> Vec<RecordedStorageWrite> {
+    let mut coalesced_writes: Vec<RecordedStorageWrite> = vec![];
+    let mut slot_indices = HashMap::new();
+    for write in writes {
+        match slot_indices.entry((write.contract_address, write.key)) {
+            Entry::Occupied(entry) => coalesced_writes[*entry.get()].new_value = write.new_value,
+            Entry::Vacant(entry) => {
+                entry.insert(coalesced_writes.len());
+                coalesced_writes.push(write.clone());
+            }
+        }
+    }
+    coalesced_writes
+}
+
+/// An event recorded in dry-run mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedEvent {
+    pub contract_address: Felt252,
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
+/// A message sent to L1 by `from_address`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct L2ToL1Message {
+    pub from_address: Felt252,
+    pub to_address: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
+/// A storage access of a `storage_read` or `storage_write` syscall, see `replay_transcript`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageAccess {
+    Read { contract_address: Felt252, key: Felt252, value: Felt252 },
+    Write { contract_address: Felt252, key: Felt252 },
+}
+
+/// A syscall dispatched by the OS. `index` is its position in the transcript.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallTranscriptEntry {
+    pub index: usize,
+    pub selector: SyscallSelector,
+    pub summary: String,
+    /// Number of keccak-f permutations, for `keccak` syscalls that went through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keccak_rounds: Option<usize>,
+    /// The storage slot accessed, for storage syscalls executed during a call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_access: Option<StorageAccess>,
+}
+
+/// The syscalls dispatched by the OS, in execution order, with the salient arguments of their
+/// requests, e.g. the key of storage reads or the class hash of deploys.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallTranscript {
+    entries: Vec<SyscallTranscriptEntry>,
+}
+
+impl SyscallTranscript {
+    pub fn record(&mut self, selector: SyscallSelector, summary: String) {
+        let index = self.entries.len();
+        self.entries.push(SyscallTranscriptEntry {
+            index,
+            selector,
+            summary,
+            keccak_rounds: None,
+            storage_access: None,
+        });
+    }
+
+    /// Attaches the number of keccak-f permutations to the syscall recorded last.
+    pub fn record_keccak_rounds(&mut self, n_rounds: usize) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.keccak_rounds = Some(n_rounds);
+        }
+    }
+
+    /// Attaches the storage slot accessed to the syscall recorded last.
+    pub fn record_storage_access(&mut self, storage_access: StorageAccess) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.storage_access = Some(storage_access);
+        }
+    }
+
+    pub fn entries(&self) -> &[SyscallTranscriptEntry] {
+        &self.entries
+    }
+}
+
+/// Version of the execution info layout that `get_execution_info` hands out to contracts.
+///
+/// The OS always builds the latest layout, the handler serializes an older one for the blocks
+/// produced by Starknet versions that predate it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StarknetVersion {
+    /// Starknet 0.11, the tx info ends with the nonce.
+    V1,
+    /// Starknet 0.12, same tx info as `V1`.
+    V2,
+    /// Starknet 0.13 onwards, the tx info includes the fields of v3 transactions: resource bounds,
+    /// tip, paymaster data, data availability modes and account deployment data.
+    #[default]
+    V3,
+}
+
+/// The side effects of the current transaction, see `ExecutionHelperWrapper::export_effects`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallEffects {
+    pub events: Vec<OrderedEvent>,
+    pub l2_to_l1_messages: Vec<L2ToL1Message>,
+    pub storage_writes: Vec<RecordedStorageWrite>,
+}
+
+/// Compact binary encoding of the side effects, to persist them and replay them later.
+macro_rules! impl_bincode_encoding {
+    ($($effect:ty),*) => {
+        $(
+            impl $effect {
+                pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+                    bincode::serialize(self)
+                }
+
+                pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+                    bincode::deserialize(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_bincode_encoding!(OrderedEvent, L2ToL1Message, RecordedStorageWrite, SyscallEffects);
+
+/// Summary of a run, see `ExecutionHelperWrapper::run_report`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunReport {
+    pub syscall_profile: SyscallProfile,
+    /// Gas charged by the syscall handlers, see `ExecutionHelper::syscall_gas_consumed`.
+    pub gas_consumed: u64,
+    /// Events, messages to L1 and storage diff of the current transaction.
+    pub effects: SyscallEffects,
+    pub warnings: Vec<String>,
+}
+
+/// A message sent from L1 to the L1 handler `selector` of `to_address`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct L1ToL2Message {
+    pub from_address: Felt252,
+    pub to_address: Felt252,
+    pub selector: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
+impl L1ToL2Message {
+    /// The message consumed by an L1 handler transaction, `None` for the other transactions.
+    pub fn from_l1_handler(tx: &InternalTransaction) -> Option<Self> {
+        if tx.r#type != "L1_HANDLER" {
+            return None;
+        }
+        let (from_address, payload) = tx.calldata.as_ref()?.split_first()?;
+        Some(Self {
+            from_address: *from_address,
+            to_address: tx.contract_address?,
+            selector: tx.entry_point_selector?,
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+/// The block seen by the syscalls, see [`ExecutionHelperWrapper::block_info`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockInfoView {
+    block_number: BlockNumber,
+    block_timestamp: BlockTimestamp,
+    sequencer_address: Felt252,
+    chain_id: Felt252,
+}
+
+impl BlockInfoView {
+    pub fn block_number(&self) -> BlockNumber {
+        self.block_number
+    }
+
+    pub fn block_timestamp(&self) -> BlockTimestamp {
+        self.block_timestamp
+    }
+
+    /// The sequencer address of the block, unless overridden with `set_sequencer_address`.
+    pub fn sequencer_address(&self) -> Felt252 {
+        self.sequencer_address
+    }
+
+    pub fn chain_id(&self) -> Felt252 {
+        self.chain_id
+    }
+}
+
+/// Identifies a checkpoint taken by [`ExecutionHelperWrapper::snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotId(usize);
+
+/// Lengths of the logs rolled back by [`ExecutionHelperWrapper::restore`].
+#[derive(Debug)]
+struct ExecutionSnapshot {
+    n_storage_writes: usize,
+    n_applied_storage_writes: usize,
+    n_events: usize,
+    n_messages: usize,
+}
+
+/// A call dispatched by a syscall, with the lengths of the effect logs when it was entered.
+#[derive(Debug)]
+struct CallContext {
+    calldata: Vec<Felt252>,
+    n_storage_writes: usize,
+    n_events: usize,
+    n_messages: usize,
+    failed: bool,
+}
+
+/// A storage write made while a snapshot is active, with the value it overwrote.
+#[derive(Debug)]
+struct StorageWriteJournalEntry {
+    contract_address: Felt252,
+    key: Felt252,
+    previous_value: Felt252,
+}
+
+/// What `read_storage_for_address` returns for keys that the contract storage does not hold.
+/// Failures of the storage backend are reported as errors in any case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StorageMissPolicy {
+    /// Absent keys read as zero, like on Starknet.
+    #[default]
+    ReturnZero,
+    /// Absent keys are reported as `StorageError::ContentNotFound`, e.g. to check that the
+    /// storage provided to the OS is complete.
+    Error,
+}
+
+/// Resolves the compiled class of a class hash, see `ExecutionHelperWrapper::with_class_provider`.
+pub type ClassProvider = Box<dyn Fn(Felt252) -> Option<ContractClass>>;
+
+/// Resolves the hash of a block from its number, see `ExecutionHelperWrapper::with_block_hash_provider`.
+pub type BlockHashProvider = Box<dyn Fn(u64) -> Option<Felt252>>;
+
+/// Reads the storage of contracts that the OS was not given, see
+/// `ExecutionHelperWrapper::with_storage_fallback`. Resolves to `None` for absent keys.
+pub type StorageFallback =
+    Box<dyn Fn(Felt252, Felt252) -> LocalBoxFuture<'static, Result<Option<Felt252>, StorageError>>>;
+
+/// Observes the storage writes applied by the OS, see `ExecutionHelperWrapper::with_on_storage_write`.
+pub type StorageWriteCallback = Box<dyn FnMut(ContractAddress, Felt252, Felt252)>;
+
 /// Maintains the info for executing txns in the OS
 pub struct ExecutionHelper<PCS>
 where
     PCS: PerContractStorage,
 {
     pub _prev_block_context: Option<BlockContext>,
+    // Block context the helper was created for, see `export_input`
+    pub block_context: BlockContext,
     pub os_input: Option<Rc<StarknetOsInput>>,
     pub kzg_manager: KzgManager,
+    // Tracks the resources used by syscalls
+    pub os_logger: OsLogger,
+    // Syscalls dispatched so far, in execution order
+    pub syscall_transcript: SyscallTranscript,
+    // Gas charged by the syscall handlers so far, on top of the base cost pre-charged by the OS
+    pub syscall_gas_consumed: u64,
     // Pointer tx execution info
     pub tx_execution_info_iter: IntoIter<TransactionExecutionInfo>,
     // Tx info for transaction currently being executed
     pub tx_execution_info: Option<TransactionExecutionInfo>,
+    // Fields of the transaction currently being executed, served by the new `get_tx_info` path
+    pub current_tx_info: Option<TransactionInfo>,
+    // Chain id of the block, as written in the tx info
+    pub chain_id: Felt252,
+    // Info of the block being executed, possibly overridden e.g. to estimate fees in the future
+    pub block_info: BlockInfo,
+    // Sequencer address returned by `get_sequencer_address` instead of the one of `block_info`
+    pub sequencer_address_override: Option<Felt252>,
     // Pointer to the Cairo (deprecated) TxInfo struct
     // Must match the DeprecatedTxInfo pointer for system call validation in 'enter_tx'
     pub tx_info_ptr: Option<Relocatable>,
@@ -49,6 +386,11 @@ where
     pub call_iter: IntoIter<CallInfo>,
     // CallInfo for the call currently being executed
     pub call_info: Option<CallInfo>,
+    // Calls dispatched by call_contract and library_call syscalls that did not return yet,
+    // innermost last
+    call_context_stack: Vec<CallContext>,
+    // Check that the calls that failed left no effects behind, see `with_reverted_call_validation`
+    pub validate_reverted_calls: bool,
     // Iter to the results of the current call's internal calls
     pub result_iter: IntoIter<CallResult>,
     // Iter over contract addresses that were deployed during that call
@@ -57,6 +399,84 @@ where
     pub execute_code_read_iter: IntoIter<Felt252>,
     // Per-contract storage
     pub storage_by_address: ContractStorageMap<PCS>,
+    // Storage keys read through `read_storage_for_address`, per contract address
+    pub accessed_storage_keys: HashMap<Felt252, HashSet<Felt252>>,
+    // Storage values fetched ahead of time by `prefetch_storage`, by (contract address, key)
+    pub storage_read_cache: HashMap<(Felt252, Felt252), Felt252>,
+    // Storage values installed by `with_storage_snapshot`, by (contract address, key)
+    pub storage_snapshot: Option<HashMap<(Felt252, Felt252), Felt252>>,
+    pub storage_miss_policy: StorageMissPolicy,
+    pub storage_fallback: Option<StorageFallback>,
+    // Whether storage accesses must target the called contract, see `expected_self_address`
+    pub validate_storage_address: bool,
+    // Inclusive `(low, high)` ranges of storage keys that reads and writes may not target
+    pub reserved_storage_keys: Vec<(Felt252, Felt252)>,
+    // Compiled classes resolved by `get_compiled_class`, by class hash
+    pub class_provider: Option<ClassProvider>,
+    pub block_hash_provider: Option<BlockHashProvider>,
+    // `get_block_hash` returns zero for the blocks less than this many blocks before the current one
+    pub block_hash_lower_bound: u64,
+    // Layout of the execution info returned by `get_execution_info`
+    pub starknet_version: StarknetVersion,
+    pub compiled_class_cache: HashMap<Felt252, ContractClass>,
+    // Classes registered with their precomputed class hash, by class hash, see `register_class_hash`
+    pub registered_classes: HashMap<Felt252, ContractClass>,
+    // Whether registered classes are checked against the class resolved for their hash
+    pub verify_registered_classes: bool,
+
+    // Events emitted by the current transaction, in emission order
+    pub emitted_events: Vec<OrderedEvent>,
+    // Whether the first key of emitted events is stored as their selector
+    pub split_event_selector: bool,
+    // Whether emitting the same event twice in a call records a warning
+    pub warn_duplicate_events: bool,
+    // Keys and data of the events emitted by the current call, to detect duplicates
+    pub call_events: HashSet<(Vec<Felt252>, Vec<Felt252>)>,
+    // Suspicious but valid behaviors noticed during the run, see `warnings`
+    pub warnings: Vec<String>,
+    // Whether `end_tx` checks the order of the events, see `validate_event_ordering`
+    pub validate_event_ordering: bool,
+    // Messages sent to L1 by the current transaction, in sending order
+    pub l2_to_l1_messages: Vec<L2ToL1Message>,
+    // Storage writes applied by the current transaction, in execution order
+    pub storage_writes: Vec<RecordedStorageWrite>,
+    // Called with (contract address, key, new value) for every applied storage write
+    pub on_storage_write: Option<StorageWriteCallback>,
+    // Hash function of the syscall handlers, Pedersen by default
+    pub hasher: Box<dyn Hasher>,
+    // (contract address, new state entry) pairs, in the order the OS allocated the state entries
+    pub state_entry_access_order: Vec<(Felt252, Relocatable)>,
+    // Segments allocated ahead of time for the new state entries, handed out in allocation order
+    pub state_entry_segment_pool: SegmentPool,
+    // Segments of the arrays written by the syscall handler, empty ones are recycled
+    pub scratch_segment_pool: SegmentPool,
+    // Class hashes of the contracts deployed by the `deploy` syscall, by contract address
+    pub deployed_contracts: HashMap<Felt252, Felt252>,
+    // Nonces of the accounts incremented during this run, by contract address
+    pub nonces: HashMap<Felt252, Felt252>,
+    // Addresses computed by the `deploy` syscall, by deploy parameters
+    pub deployed_address_cache: DeployedAddressCache,
+    // Deploys with a longer constructor calldata are rejected before hashing it
+    pub max_constructor_calldata_len: usize,
+    // Class hashes set by `replace_class`, by contract address
+    pub class_replacements: HashMap<Felt252, Felt252>,
+    // Messages from L1 that can still be consumed by an L1 handler, and the ones already consumed
+    pub pending_l1_messages: Vec<L1ToL2Message>,
+    pub consumed_l1_messages: Vec<L1ToL2Message>,
+    // Check that the L1 handlers consume a pending message, see `with_l1_message_validation`
+    pub validate_l1_messages: bool,
+    // Nonces of the pending messages registered with one, and the next nonce expected from each
+    // L1 sender once one of its messages was consumed
+    pending_l1_message_nonces: Vec<(L1ToL2Message, Felt252)>,
+    next_l1_nonces: HashMap<Felt252, Felt252>,
+    // Active snapshots, innermost last, and the storage writes made since the first one
+    snapshots: Vec<ExecutionSnapshot>,
+    storage_write_journal: Vec<StorageWriteJournalEntry>,
+    // In dry-run mode, side effects are recorded instead of being applied
+    pub dry_run: bool,
+    pub recorded_writes: Vec<RecordedStorageWrite>,
+    pub recorded_events: Vec<RecordedEvent>,
+    pub recorded_messages: Vec<L2ToL1Message>,
 
     // Secp syscall processors.
     pub secp256k1_syscall_processor: SecpSyscallProcessor<ark_secp256k1::Config>,
@@ -88,24 +508,343 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ExecutionHelper")
             .field("_prev_block_context", &self._prev_block_context)
+            .field("block_context", &self.block_context)
             .field("kzg_manager", &self.kzg_manager)
+            .field("os_logger", &self.os_logger)
+            .field("syscall_transcript", &self.syscall_transcript)
+            .field("syscall_gas_consumed", &self.syscall_gas_consumed)
             .field("tx_execution_info_iter", &self.tx_execution_info_iter)
             .field("tx_execution_info", &self.tx_execution_info)
+            .field("current_tx_info", &self.current_tx_info)
+            .field("chain_id", &self.chain_id)
+            .field("block_info", &self.block_info)
+            .field("sequencer_address_override", &self.sequencer_address_override)
             .field("tx_info_ptr", &self.tx_info_ptr)
             .field("call_execution_info_ptr", &self.call_execution_info_ptr)
             .field("old_block_number_and_hash", &self.old_block_number_and_hash)
             .field("call_iter", &self.call_iter)
             .field("call_info", &self.call_info)
+            .field("call_context_stack", &self.call_context_stack)
+            .field("validate_reverted_calls", &self.validate_reverted_calls)
+            .field("validate_l1_messages", &self.validate_l1_messages)
             .field("result_iter", &self.result_iter)
             .field("deployed_contracts_iter", &self.deployed_contracts_iter)
             .field("execute_code_read_iter", &self.execute_code_read_iter)
             .field("storage_by_address", &self.storage_by_address)
+            .field("accessed_storage_keys", &self.accessed_storage_keys)
+            .field("storage_read_cache", &self.storage_read_cache)
+            .field("storage_snapshot", &self.storage_snapshot)
+            .field("storage_miss_policy", &self.storage_miss_policy)
+            .field("storage_fallback", &self.storage_fallback.as_ref().map(|_| "StorageFallback"))
+            .field("validate_storage_address", &self.validate_storage_address)
+            .field("reserved_storage_keys", &self.reserved_storage_keys)
+            .field("class_provider", &self.class_provider.as_ref().map(|_| "ClassProvider"))
+            .field("block_hash_provider", &self.block_hash_provider.as_ref().map(|_| "BlockHashProvider"))
+            .field("block_hash_lower_bound", &self.block_hash_lower_bound)
+            .field("starknet_version", &self.starknet_version)
+            .field("compiled_class_cache", &self.compiled_class_cache)
+            .field("registered_classes", &self.registered_classes)
+            .field("verify_registered_classes", &self.verify_registered_classes)
+            .field("emitted_events", &self.emitted_events)
+            .field("split_event_selector", &self.split_event_selector)
+            .field("warn_duplicate_events", &self.warn_duplicate_events)
+            .field("call_events", &self.call_events)
+            .field("warnings", &self.warnings)
+            .field("validate_event_ordering", &self.validate_event_ordering)
+            .field("l2_to_l1_messages", &self.l2_to_l1_messages)
+            .field("storage_writes", &self.storage_writes)
+            .field("on_storage_write", &self.on_storage_write.as_ref().map(|_| "StorageWriteCallback"))
+            .field("hasher", &"Hasher")
+            .field("state_entry_access_order", &self.state_entry_access_order)
+            .field("state_entry_segment_pool", &self.state_entry_segment_pool)
+            .field("scratch_segment_pool", &self.scratch_segment_pool)
+            .field("deployed_contracts", &self.deployed_contracts)
+            .field("nonces", &self.nonces)
+            .field("deployed_address_cache", &self.deployed_address_cache)
+            .field("max_constructor_calldata_len", &self.max_constructor_calldata_len)
+            .field("class_replacements", &self.class_replacements)
+            .field("pending_l1_messages", &self.pending_l1_messages)
+            .field("consumed_l1_messages", &self.consumed_l1_messages)
+            .field("pending_l1_message_nonces", &self.pending_l1_message_nonces)
+            .field("next_l1_nonces", &self.next_l1_nonces)
+            .field("snapshots", &self.snapshots)
+            .field("storage_write_journal", &self.storage_write_journal)
+            .field("dry_run", &self.dry_run)
+            .field("recorded_writes", &self.recorded_writes)
+            .field("recorded_events", &self.recorded_events)
+            .field("recorded_messages", &self.recorded_messages)
             .field("secp256k1_syscall_processor", &"SecpHintProcessor<ark_secp256k1::Config>")
             .field("secp256r1_syscall_processor", &"SecpHintProcessor<ark_secp256r1::Config>")
             .finish()
     }
 }
 
+impl<PCS> ExecutionHelper<PCS>
+where
+    PCS: PerContractStorage,
+{
+    /// Returns the sequencer address of the block, unless overridden with `set_sequencer_address`.
+    pub fn sequencer_address(&self) -> Felt252 {
+        self.sequencer_address_override.unwrap_or(*self.block_info.sequencer_address.0.key())
+    }
+
+    /// Returns the block seen by the syscalls, with the overrides of `set_block_info` and
+    /// `set_sequencer_address`.
+    pub fn block_info_view(&self) -> BlockInfoView {
+        BlockInfoView {
+            block_number: self.block_info.block_number,
+            block_timestamp: self.block_info.block_timestamp,
+            sequencer_address: self.sequencer_address(),
+            chain_id: self.chain_id,
+        }
+    }
+
+    /// Returns the address whose storage the current call may access, if storage addresses are
+    /// validated. Library calls run in the storage of their caller and are not checked.
+    pub fn expected_self_address(&self) -> Option<Felt252> {
+        if !self.validate_storage_address {
+            return None;
+        }
+        self.call_info
+            .as_ref()
+            .filter(|call_info| call_info.call.call_type == CallType::Call)
+            .map(|call_info| Felt252::from(call_info.call.storage_address))
+    }
+
+    /// Accounts for a call dispatched by a `call_contract` or `library_call` syscall, whose
+    /// calldata becomes the current calldata until `exit_call_context`.
+    pub fn enter_call_context(&mut self, calldata: Vec<Felt252>) {
+        let context = CallContext {
+            calldata,
+            n_storage_writes: self.storage_writes.len(),
+            n_events: self.emitted_events.len(),
+            n_messages: self.l2_to_l1_messages.len(),
+            failed: false,
+        };
+        self.call_context_stack.push(context);
+    }
+
+    /// Records that the innermost call entered with `enter_call_context` failed.
+    pub fn mark_call_failed(&mut self) {
+        if let Some(context) = self.call_context_stack.last_mut() {
+            context.failed = true;
+        }
+    }
+
+    /// Accounts for the return of a call entered with `enter_call_context` and returns its
+    /// calldata. With `validate_reverted_calls`, fails if the call failed but the storage writes,
+    /// events or messages made since it was entered were not rolled back.
+    pub fn exit_call_context(&mut self) -> Result<Option<Vec<Felt252>>, HintError> {
+        let context = match self.call_context_stack.pop() {
+            Some(context) => context,
+            None => return Ok(None),
+        };
+        if self.validate_reverted_calls && context.failed {
+            let n_storage_writes = self.storage_writes.len().saturating_sub(context.n_storage_writes);
+            let n_events = self.emitted_events.len().saturating_sub(context.n_events);
+            let n_messages = self.l2_to_l1_messages.len().saturating_sub(context.n_messages);
+            if n_storage_writes + n_events + n_messages > 0 {
+                return Err(HintError::AssertionFailed(
+                    format!(
+                        "A reverted call left its effects behind: {} storage writes, {} events and {} messages",
+                        n_storage_writes, n_events, n_messages
+                    )
+                    .into_boxed_str(),
+                ));
+            }
+        }
+        Ok(Some(context.calldata))
+    }
+
+    /// Returns the calldata of the innermost call dispatched by a syscall, or the calldata of the
+    /// entry point of the transaction at the top level.
+    pub fn current_calldata(&self) -> &[Felt252] {
+        if let Some(context) = self.call_context_stack.last() {
+            return &context.calldata;
+        }
+        self.tx_execution_info
+            .as_ref()
+            .and_then(|tx_execution_info| tx_execution_info.execute_call_info.as_ref())
+            .map(|call_info| call_info.call.calldata.0.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn check_storage_address(&self, address: Felt252) -> Result<(), StorageError> {
+        match self.expected_self_address() {
+            Some(expected) if expected != address => {
+                Err(StorageError::UnexpectedContractAddress { expected, actual: address })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_storage_key(&self, address: Felt252, key: Felt252) -> Result<(), StorageError> {
+        if self.reserved_storage_keys.iter().any(|(low, high)| (low..=high).contains(&&key)) {
+            return Err(StorageError::ReservedStorageKey { contract_address: address, key });
+        }
+        Ok(())
+    }
+
+    /// Returns the class hash of `contract_address`, taking the contracts deployed and the
+    /// classes replaced during this run into account.
+    pub fn class_hash_of(&self, contract_address: &Felt252) -> Option<Felt252> {
+        let cached_class_hash = self
+            .class_replacements
+            .get(contract_address)
+            .or_else(|| self.deployed_contracts.get(contract_address))
+            .copied();
+        cached_class_hash.or_else(|| {
+            self.os_input
+                .as_ref()
+                .and_then(|os_input| os_input.contract_address_to_class_hash.get(contract_address).copied())
+        })
+    }
+
+    /// Returns the nonce of the account at `contract_address`, taking the nonces incremented during
+    /// this run into account. Unknown accounts have a zero nonce.
+    pub fn nonce_at(&self, contract_address: &Felt252) -> Felt252 {
+        self.nonces.get(contract_address).copied().unwrap_or_else(|| {
+            self.os_input
+                .as_ref()
+                .and_then(|os_input| os_input.contracts.get(contract_address))
+                .map(|contract_state| contract_state.nonce)
+                .unwrap_or(Felt252::ZERO)
+        })
+    }
+
+    /// Looks up the compiled class of `class_hash` in the OS input, Cairo 1 classes first.
+    fn os_input_compiled_class(&self, class_hash: Felt252) -> Result<Option<ContractClass>, HintError> {
+        let os_input = match &self.os_input {
+            Some(os_input) => os_input,
+            None => return Ok(None),
+        };
+        let to_hint_error =
+            |e| HintError::CustomHint(format!("Could not convert class {}: {}", class_hash, e).into_boxed_str());
+
+        let compiled_class = os_input
+            .class_hash_to_compiled_class_hash
+            .get(&class_hash)
+            .and_then(|compiled_class_hash| os_input.compiled_classes.get(compiled_class_hash));
+        if let Some(compiled_class) = compiled_class {
+            let blockifier_class = compiled_class.get_blockifier_contract_class().map_err(to_hint_error)?;
+            return Ok(Some(blockifier_class.clone().into()));
+        }
+        if let Some(deprecated_class) = os_input.deprecated_compiled_classes.get(&class_hash) {
+            let blockifier_class = deprecated_class.get_blockifier_contract_class().map_err(to_hint_error)?;
+            return Ok(Some(blockifier_class.clone().into()));
+        }
+        Ok(None)
+    }
+
+    /// Returns the class registered for `class_hash` with `register_class_hash`, if any. When
+    /// verification is enabled, the class resolved for `class_hash` through the class provider or
+    /// the OS input, if any, must be the registered one.
+    pub fn registered_class(&self, class_hash: Felt252) -> Result<Option<ContractClass>, HintError> {
+        let registered_class = match self.registered_classes.get(&class_hash) {
+            Some(registered_class) => registered_class,
+            None => return Ok(None),
+        };
+        if self.verify_registered_classes {
+            let resolved_class =
+                match self.class_provider.as_ref().and_then(|class_provider| class_provider(class_hash)) {
+                    Some(contract_class) => Some(contract_class),
+                    None => self.os_input_compiled_class(class_hash)?,
+                };
+            if resolved_class.is_some_and(|resolved_class| &resolved_class != registered_class) {
+                return Err(HintError::AssertionFailed(
+                    format!("Class hash {} was registered for another class", class_hash.to_hex_string())
+                        .into_boxed_str(),
+                ));
+            }
+        }
+        Ok(Some(registered_class.clone()))
+    }
+
+    /// Registers `message` with the nonce it was sent with on L1. The nonces of the messages of
+    /// a sender must increase: fails if `nonce` was already used by the sender.
+    pub fn register_l1_to_l2_message_with_nonce(
+        &mut self,
+        message: L1ToL2Message,
+        nonce: Felt252,
+    ) -> Result<(), HintError> {
+        let sender = message.from_address;
+        let is_registered = self
+            .pending_l1_message_nonces
+            .iter()
+            .any(|(pending, pending_nonce)| pending.from_address == sender && *pending_nonce == nonce);
+        let is_consumed = self.next_l1_nonces.get(&sender).is_some_and(|next_nonce| nonce < *next_nonce);
+        if is_registered || is_consumed {
+            return Err(HintError::CustomHint(
+                format!("L1 message nonce {} of {} was already used", nonce, sender.to_hex_string()).into_boxed_str(),
+            ));
+        }
+
+        self.pending_l1_message_nonces.push((message.clone(), nonce));
+        self.pending_l1_messages.push(message);
+        Ok(())
+    }
+
+    /// Returns the nonce the next message consumed from `sender` must have: the nonce following
+    /// the last consumed message of `sender`, or the lowest nonce of its pending messages if none
+    /// was consumed yet.
+    pub fn next_expected_l1_nonce(&self, sender: Felt252) -> Felt252 {
+        self.next_l1_nonces.get(&sender).copied().unwrap_or_else(|| {
+            self.pending_l1_message_nonces
+                .iter()
+                .filter(|(pending, _)| pending.from_address == sender)
+                .map(|(_, nonce)| *nonce)
+                .min()
+                .unwrap_or(Felt252::ZERO)
+        })
+    }
+
+    /// Marks a pending message from L1 as consumed. Fails if no such message was registered, if
+    /// it was consumed already or, for messages registered with a nonce, if an earlier message of
+    /// the same sender was not consumed yet.
+    pub fn consume_l1_to_l2_message(&mut self, message: &L1ToL2Message) -> Result<(), HintError> {
+        match self.pending_l1_messages.iter().position(|pending| pending == message) {
+            Some(index) => {
+                let nonce_index = self.pending_l1_message_nonces.iter().position(|(pending, _)| pending == message);
+                if let Some(nonce_index) = nonce_index {
+                    let nonce = self.pending_l1_message_nonces[nonce_index].1;
+                    let expected_nonce = self.next_expected_l1_nonce(message.from_address);
+                    if nonce != expected_nonce {
+                        return Err(HintError::CustomHint(
+                            format!(
+                                "L1 message from {} consumed out of order: nonce {}, expected {}",
+                                message.from_address.to_hex_string(),
+                                nonce,
+                                expected_nonce
+                            )
+                            .into_boxed_str(),
+                        ));
+                    }
+                    self.pending_l1_message_nonces.remove(nonce_index);
+                    self.next_l1_nonces.insert(message.from_address, nonce + Felt252::ONE);
+                }
+                let message = self.pending_l1_messages.remove(index);
+                self.consumed_l1_messages.push(message);
+                Ok(())
+            }
+            None if self.consumed_l1_messages.contains(message) => Err(HintError::CustomHint(
+                format!("L1 message to {} was already consumed", message.to_address).into_boxed_str(),
+            )),
+            None => {
+                Err(HintError::CustomHint(format!("No pending L1 message to {}", message.to_address).into_boxed_str()))
+            }
+        }
+    }
+
+    /// Consumes the message an L1 handler is called with when `validate_l1_messages` is set, does
+    /// nothing otherwise.
+    pub fn consume_l1_handler_message(&mut self, message: &L1ToL2Message) -> Result<(), HintError> {
+        if self.validate_l1_messages {
+            self.consume_l1_to_l2_message(message)?;
+        }
+        Ok(())
+    }
+}
+
 impl<PCS> ExecutionHelperWrapper<PCS>
 where
     PCS: PerContractStorage + 'static,
@@ -125,23 +864,82 @@ where
             .0
             .checked_sub(STORED_BLOCK_HASH_BUFFER)
             .map(|_| block_context.clone());
+        // The messages consumed by the L1 handler transactions of the block
+        let pending_l1_messages = os_input
+            .iter()
+            .flat_map(|os_input| os_input.transactions.iter())
+            .filter_map(L1ToL2Message::from_l1_handler)
+            .collect();
 
         Self {
             execution_helper: Rc::new(RwLock::new(ExecutionHelper {
                 _prev_block_context: prev_block_context,
+                block_context: block_context.clone(),
                 os_input,
                 kzg_manager: Default::default(),
+                os_logger: Default::default(),
+                syscall_transcript: Default::default(),
+                syscall_gas_consumed: 0,
                 tx_execution_info_iter: tx_execution_infos.into_iter(),
                 tx_execution_info: None,
+                current_tx_info: None,
+                chain_id: chain_id_to_felt(&block_context.chain_info().chain_id),
+                block_info: block_context.block_info().clone(),
+                sequencer_address_override: None,
                 tx_info_ptr: None,
                 call_iter: vec![].into_iter(),
                 call_execution_info_ptr: None,
                 old_block_number_and_hash: Some(old_block_number_and_hash),
                 call_info: None,
+                call_context_stack: vec![],
+                validate_reverted_calls: false,
                 result_iter: vec![].into_iter(),
                 deployed_contracts_iter: vec![].into_iter(),
                 execute_code_read_iter: vec![].into_iter(),
                 storage_by_address: contract_storage_map,
+                accessed_storage_keys: Default::default(),
+                storage_read_cache: Default::default(),
+                storage_snapshot: None,
+                storage_miss_policy: StorageMissPolicy::default(),
+                storage_fallback: None,
+                validate_storage_address: false,
+                reserved_storage_keys: vec![],
+                class_provider: None,
+                block_hash_provider: None,
+                block_hash_lower_bound: STORED_BLOCK_HASH_BUFFER,
+                starknet_version: StarknetVersion::default(),
+                compiled_class_cache: HashMap::new(),
+                registered_classes: HashMap::new(),
+                verify_registered_classes: false,
+                emitted_events: vec![],
+                split_event_selector: false,
+                warn_duplicate_events: false,
+                call_events: HashSet::new(),
+                warnings: vec![],
+                validate_event_ordering: false,
+                l2_to_l1_messages: vec![],
+                storage_writes: vec![],
+                on_storage_write: None,
+                hasher: Box::new(PedersenHash),
+                state_entry_access_order: vec![],
+                state_entry_segment_pool: SegmentPool::default(),
+                scratch_segment_pool: SegmentPool::default(),
+                deployed_contracts: HashMap::new(),
+                nonces: HashMap::new(),
+                deployed_address_cache: DeployedAddressCache::default(),
+                max_constructor_calldata_len: MAX_CONSTRUCTOR_CALLDATA_LEN,
+                class_replacements: HashMap::new(),
+                pending_l1_messages,
+                consumed_l1_messages: vec![],
+                validate_l1_messages: false,
+                pending_l1_message_nonces: vec![],
+                next_l1_nonces: HashMap::new(),
+                snapshots: vec![],
+                storage_write_journal: vec![],
+                dry_run: false,
+                recorded_writes: vec![],
+                recorded_events: vec![],
+                recorded_messages: vec![],
                 secp256k1_syscall_processor: Default::default(),
                 secp256r1_syscall_processor: Default::default(),
                 sha256_segment: None,
@@ -149,6 +947,359 @@ where
         }
     }
 
+    /// Creates an execution helper in dry-run mode: storage writes, events and messages to L1 are
+    /// recorded instead of being applied to the state.
+    pub fn record_only(
+        contract_storage_map: ContractStorageMap<PCS>,
+        tx_execution_infos: Vec<TransactionExecutionInfo>,
+        block_context: &BlockContext,
+        os_input: Option<Rc<StarknetOsInput>>,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) -> Self {
+        let execution_helper =
+            Self::new(contract_storage_map, tx_execution_infos, block_context, os_input, old_block_number_and_hash);
+        execution_helper.execution_helper.try_write().expect("the execution helper was just created").dry_run = true;
+        execution_helper
+    }
+
+    /// Serves storage reads from `storage_snapshot` first, e.g. to prove a block offline without
+    /// access to the full state. Once a snapshot is installed, keys found neither in the snapshot
+    /// nor in the contract storage read as zero, like on Starknet.
+    pub fn with_storage_snapshot(self, storage_snapshot: HashMap<ContractAddress, HashMap<Felt252, Felt252>>) -> Self {
+        let storage_snapshot = storage_snapshot
+            .into_iter()
+            .flat_map(|(address, values)| {
+                let address = *address.0.key();
+                values.into_iter().map(move |(key, value)| ((address, key), value))
+            })
+            .collect();
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").storage_snapshot =
+            Some(storage_snapshot);
+        self
+    }
+
+    /// Sets what storage reads return for absent keys, see `StorageMissPolicy`.
+    pub fn with_storage_miss_policy(self, storage_miss_policy: StorageMissPolicy) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").storage_miss_policy =
+            storage_miss_policy;
+        self
+    }
+
+    /// Reads the storage of contracts absent from the contract storage map through
+    /// `storage_fallback`, e.g. from a Starknet node. Values read this way are cached.
+    pub fn with_storage_fallback(self, storage_fallback: StorageFallback) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").storage_fallback =
+            Some(storage_fallback);
+        self
+    }
+
+    /// Resolves compiled classes through `class_provider` before looking them up in the OS input.
+    pub fn with_class_provider(self, class_provider: impl Fn(Felt252) -> Option<ContractClass> + 'static) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").class_provider =
+            Some(Box::new(class_provider));
+        self
+    }
+
+    /// Resolves the hashes returned by `get_block_hash` through `block_hash_provider` instead of
+    /// the storage of the block hash contract. Blocks within the disallowed window are not
+    /// looked up.
+    pub fn with_block_hash_provider(self, block_hash_provider: impl Fn(u64) -> Option<Felt252> + 'static) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").block_hash_provider =
+            Some(Box::new(block_hash_provider));
+        self
+    }
+
+    /// Sets the number of recent blocks whose hash `get_block_hash` does not disclose,
+    /// `STORED_BLOCK_HASH_BUFFER` by default, e.g. to prove blocks under the rules of another
+    /// Starknet version.
+    pub fn with_block_hash_lower_bound(self, block_hash_lower_bound: u64) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").block_hash_lower_bound =
+            block_hash_lower_bound;
+        self
+    }
+
+    /// Sets the Starknet version of the block, which decides the layout of the execution info
+    /// returned by `get_execution_info`. Defaults to the latest layout.
+    pub fn with_starknet_version(self, starknet_version: StarknetVersion) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").starknet_version =
+            starknet_version;
+        self
+    }
+
+    /// Stores the first key of the emitted events as their `selector`, apart from the other keys,
+    /// following the Cairo 1 convention of keying events by the hash of their name first.
+    pub fn with_event_selector_split(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").split_event_selector = true;
+        self
+    }
+
+    /// Records a warning when a call emits an event with the same keys and data as an event it
+    /// already emitted. This is valid but often a bug of the contract.
+    pub fn with_duplicate_event_warnings(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").warn_duplicate_events = true;
+        self
+    }
+
+    /// Checks, when a call dispatched by a syscall returns, that a failed call left no storage
+    /// writes, events or messages behind, i.e. that they were rolled back with `restore`.
+    pub fn with_reverted_call_validation(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").validate_reverted_calls =
+            true;
+        self
+    }
+
+    /// Checks that every L1 handler called by a `delegate_l1_handler` syscall consumes a message
+    /// from L1, see [`ExecutionHelper::consume_l1_handler_message`].
+    pub fn with_l1_message_validation(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").validate_l1_messages = true;
+        self
+    }
+
+    /// Rejects the deploys whose constructor calldata is longer than `max_constructor_calldata_len`,
+    /// `MAX_CONSTRUCTOR_CALLDATA_LEN` by default.
+    pub fn with_max_constructor_calldata_len(self, max_constructor_calldata_len: usize) -> Self {
+        self.execution_helper
+            .try_write()
+            .expect("the execution helper is not shared yet")
+            .max_constructor_calldata_len = max_constructor_calldata_len;
+        self
+    }
+
+    /// Rejects the storage reads and writes that do not target the contract being called, see
+    /// `ExecutionHelper::expected_self_address`.
+    pub fn with_storage_address_validation(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").validate_storage_address =
+            true;
+        self
+    }
+
+    /// Aborts the run when a syscall is entered after more than `max_total_steps` Cairo steps, see
+    /// `OsLogger::set_max_total_steps`.
+    pub fn with_max_total_steps(self, max_total_steps: usize) -> Self {
+        self.execution_helper
+            .try_write()
+            .expect("the execution helper is not shared yet")
+            .os_logger
+            .set_max_total_steps(max_total_steps);
+        self
+    }
+
+    /// Rejects the storage reads and writes of the keys in the inclusive `(low, high)` ranges, e.g.
+    /// the ones reserved for system storage.
+    pub fn with_reserved_storage_keys(self, reserved_storage_keys: Vec<(Felt252, Felt252)>) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").reserved_storage_keys =
+            reserved_storage_keys;
+        self
+    }
+
+    /// Checks the classes registered with `register_class_hash` against the class resolved for
+    /// their hash when `deploy` and `replace_class` use them.
+    pub fn with_class_hash_verification(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").verify_registered_classes =
+            true;
+        self
+    }
+
+    /// Checks the order of the events of every transaction when it ends, see
+    /// `validate_event_ordering`.
+    pub fn with_event_ordering_validation(self) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").validate_event_ordering =
+            true;
+        self
+    }
+
+    /// Calls `on_storage_write` with the contract address, the key and the new value of every
+    /// storage write applied through `write_storage_for_address`, in execution order, e.g. to
+    /// stream a state diff. Writes recorded in dry-run mode are not reported.
+    pub fn with_on_storage_write(
+        self,
+        on_storage_write: impl FnMut(ContractAddress, Felt252, Felt252) + 'static,
+    ) -> Self {
+        self.execution_helper.try_write().expect("the execution helper is not shared yet").on_storage_write =
+            Some(Box::new(on_storage_write));
+        self
+    }
+
+    /// Replaces the hash function of the syscall handlers, e.g. with a deterministic stub to test
+    /// the derivation of contract addresses.
+    pub fn with_hasher(self, hasher: impl Hasher + 'static) -> Self {
+        {
+            let mut eh_ref = self.execution_helper.try_write().expect("the execution helper is not shared yet");
+            eh_ref.hasher = Box::new(hasher);
+            // Cached addresses were computed with the previous hash function
+            eh_ref.deployed_address_cache.clear();
+        }
+        self
+    }
+
+    /// Returns the compiled class of `class_hash`, as needed to dispatch `call_contract` and
+    /// `library_call`. Resolved classes are cached so that each class is only converted once.
+    pub async fn get_compiled_class(&self, class_hash: Felt252) -> Result<ContractClass, HintError> {
+        let mut eh_ref = self.execution_helper.write().await;
+        if let Some(contract_class) = eh_ref.compiled_class_cache.get(&class_hash) {
+            return Ok(contract_class.clone());
+        }
+
+        let provided_class = eh_ref.class_provider.as_ref().and_then(|class_provider| class_provider(class_hash));
+        let contract_class = match provided_class {
+            Some(contract_class) => contract_class,
+            None => eh_ref.os_input_compiled_class(class_hash)?.ok_or_else(|| {
+                HintError::CustomHint(format!("Compiled class {} not found", class_hash).into_boxed_str())
+            })?,
+        };
+        eh_ref.compiled_class_cache.insert(class_hash, contract_class.clone());
+        Ok(contract_class)
+    }
+
+    /// Registers the precomputed hash of `class`, so that `deploy` and `replace_class` use `class`
+    /// for this hash instead of resolving it again.
+    pub async fn register_class_hash(&self, class: ContractClass, class_hash: Felt252) {
+        self.execution_helper.write().await.registered_classes.insert(class_hash, class);
+    }
+
+    /// Class hashes set by `replace_class` so far, by contract address.
+    pub async fn class_replacements(&self) -> HashMap<Felt252, Felt252> {
+        self.execution_helper.read().await.class_replacements.clone()
+    }
+
+    /// Makes `message` available to the L1 handler it targets.
+    pub async fn register_l1_to_l2_message(&self, message: L1ToL2Message) {
+        self.execution_helper.write().await.pending_l1_messages.push(message);
+    }
+
+    /// See [`ExecutionHelper::register_l1_to_l2_message_with_nonce`].
+    pub async fn register_l1_to_l2_message_with_nonce(
+        &self,
+        message: L1ToL2Message,
+        nonce: Felt252,
+    ) -> Result<(), HintError> {
+        self.execution_helper.write().await.register_l1_to_l2_message_with_nonce(message, nonce)
+    }
+
+    /// See [`ExecutionHelper::next_expected_l1_nonce`].
+    pub async fn next_expected_l1_nonce(&self, sender: Felt252) -> Felt252 {
+        self.execution_helper.read().await.next_expected_l1_nonce(sender)
+    }
+
+    /// See [`ExecutionHelper::consume_l1_to_l2_message`].
+    pub async fn consume_l1_to_l2_message(&self, message: &L1ToL2Message) -> Result<(), HintError> {
+        self.execution_helper.write().await.consume_l1_to_l2_message(message)
+    }
+
+    /// Messages from L1 consumed so far, in consumption order.
+    pub async fn consumed_l1_messages(&self) -> Vec<L1ToL2Message> {
+        self.execution_helper.read().await.consumed_l1_messages.clone()
+    }
+
+    /// See [`ExecutionHelper::class_hash_of`].
+    pub async fn class_hash_of(&self, contract_address: &Felt252) -> Option<Felt252> {
+        self.execution_helper.read().await.class_hash_of(contract_address)
+    }
+
+    /// Returns the current class hash of `contract_address`, see [`ExecutionHelper::class_hash_of`],
+    /// or zero if the contract is not deployed, like Starknet does.
+    pub async fn class_hash_at(&self, contract_address: Felt252) -> Felt252 {
+        self.class_hash_of(&contract_address).await.unwrap_or(Felt252::ZERO)
+    }
+
+    /// Returns the nonce of the account at `contract_address`, see [`ExecutionHelper::nonce_at`].
+    pub async fn nonce_at(&self, contract_address: Felt252) -> Felt252 {
+        self.execution_helper.read().await.nonce_at(&contract_address)
+    }
+
+    /// Increments the nonce of the account at `contract_address`, returning the new nonce.
+    pub async fn increment_nonce(&self, contract_address: Felt252) -> Felt252 {
+        let mut eh_ref = self.execution_helper.write().await;
+        let nonce = eh_ref.nonce_at(&contract_address) + Felt252::ONE;
+        eh_ref.nonces.insert(contract_address, nonce);
+        nonce
+    }
+
+    /// Events emitted so far by the current transaction, in emission order.
+    pub async fn emitted_events(&self) -> Vec<OrderedEvent> {
+        self.execution_helper.read().await.emitted_events.clone()
+    }
+
+    /// Events emitted so far by the current transaction, nested calls included, sorted by `order`,
+    /// e.g. to build a receipt. Events with the same order keep their emission order.
+    pub async fn flattened_events(&self) -> Vec<OrderedEvent> {
+        let mut events = self.emitted_events().await;
+        events.sort_by_key(|event| event.order);
+        events
+    }
+
+    /// Checks that the `order` fields of the events emitted by the current transaction are
+    /// 0, 1, 2... An event that was dropped or counted twice is reported with its index.
+    pub async fn validate_event_ordering(&self) -> Result<(), HintError> {
+        let eh_ref = self.execution_helper.read().await;
+        match eh_ref.emitted_events.iter().enumerate().find(|(index, event)| event.order != *index) {
+            Some((index, event)) => Err(HintError::AssertionFailed(
+                format!("Event #{index} has order {}, expected {index}", event.order).into_boxed_str(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the warnings recorded during the run, e.g. by `with_duplicate_event_warnings`.
+    pub async fn warnings(&self) -> Vec<String> {
+        self.execution_helper.read().await.warnings.clone()
+    }
+
+    /// Messages sent to L1 so far by the current transaction, in sending order.
+    pub async fn l2_to_l1_messages(&self) -> Vec<L2ToL1Message> {
+        self.execution_helper.read().await.l2_to_l1_messages.clone()
+    }
+
+    /// Events, messages to L1 and storage writes of the current transaction. In dry-run mode,
+    /// see `recorded_writes`, `recorded_events` and `recorded_messages` instead.
+    ///
+    /// The storage writes are coalesced per slot, see `coalesce_storage_writes`. Every individual
+    /// write is still available in `ExecutionHelper::storage_writes`.
+    pub async fn export_effects(&self) -> SyscallEffects {
+        let eh_ref = self.execution_helper.read().await;
+        SyscallEffects {
+            events: eh_ref.emitted_events.clone(),
+            l2_to_l1_messages: eh_ref.l2_to_l1_messages.clone(),
+            storage_writes: coalesce_storage_writes(&eh_ref.storage_writes),
+        }
+    }
+
+    /// Storage writes recorded in dry-run mode, coalesced per slot like in `export_effects`.
+    pub async fn recorded_writes(&self) -> Vec<RecordedStorageWrite> {
+        coalesce_storage_writes(&self.execution_helper.read().await.recorded_writes)
+    }
+
+    /// Events recorded in dry-run mode, in execution order.
+    pub async fn recorded_events(&self) -> Vec<RecordedEvent> {
+        self.execution_helper.read().await.recorded_events.clone()
+    }
+
+    /// Messages to L1 recorded in dry-run mode, in execution order.
+    pub async fn recorded_messages(&self) -> Vec<L2ToL1Message> {
+        self.execution_helper.read().await.recorded_messages.clone()
+    }
+
+    /// Captures the inputs the helper was created with, to reproduce the run from a single file.
+    pub async fn export_input(&self) -> Result<OsRunInput, SnOsError> {
+        let eh_ref = self.execution_helper.read().await;
+        let storage_snapshot = eh_ref.storage_snapshot.as_ref().map(|storage_snapshot| {
+            let mut values_by_address: HashMap<Felt252, HashMap<Felt252, Felt252>> = HashMap::new();
+            for ((address, key), value) in storage_snapshot {
+                values_by_address.entry(*address).or_default().insert(*key, *value);
+            }
+            values_by_address
+        });
+
+        Ok(OsRunInput {
+            general_config: StarknetGeneralConfig::try_from(eh_ref.block_context.clone())?,
+            block_number: eh_ref.block_context.block_info().block_number.0,
+            block_timestamp: eh_ref.block_context.block_info().block_timestamp.0,
+            old_block_number_and_hash: eh_ref.old_block_number_and_hash.unwrap_or_default(),
+            storage_snapshot,
+            os_input: eh_ref.os_input.as_deref().cloned(),
+        })
+    }
+
     pub async fn get_old_block_number_and_hash(&self) -> Result<(Felt252, Felt252), HintError> {
         let eh_ref = self.execution_helper.read().await;
         eh_ref.old_block_number_and_hash.ok_or(HintError::AssertionFailed(
@@ -156,6 +1307,40 @@ where
         ))
     }
 
+    /// Returns the resources consumed so far by each syscall, as recorded by the OS logger.
+    pub async fn syscall_usage(&self) -> HashMap<SyscallSelector, SyscallUsage> {
+        let eh_ref = self.execution_helper.read().await;
+        eh_ref.os_logger.syscall_usage().clone()
+    }
+
+    /// Returns the Cairo steps executed when the last syscall was entered.
+    pub async fn steps_consumed(&self) -> usize {
+        self.execution_helper.read().await.os_logger.steps_consumed()
+    }
+
+    /// Returns the syscalls dispatched so far, in execution order.
+    pub async fn transcript(&self) -> SyscallTranscript {
+        self.execution_helper.read().await.syscall_transcript.clone()
+    }
+
+    /// Returns the resources consumed so far by each syscall, ready to be reported.
+    pub async fn syscall_profile(&self) -> SyscallProfile {
+        SyscallProfile(self.syscall_usage().await)
+    }
+
+    /// Bundles the syscall profile, the gas consumed by the syscalls, the effects of the current
+    /// transaction and the warnings, e.g. to dump them to a single JSON file after a run.
+    pub async fn run_report(&self) -> RunReport {
+        let effects = self.export_effects().await;
+        let eh_ref = self.execution_helper.read().await;
+        RunReport {
+            syscall_profile: SyscallProfile(eh_ref.os_logger.syscall_usage().clone()),
+            gas_consumed: eh_ref.syscall_gas_consumed,
+            effects,
+            warnings: eh_ref.warnings.clone(),
+        }
+    }
+
     pub async fn start_tx(&self, tx_info_ptr: Option<Relocatable>) {
         let mut eh_ref = self.execution_helper.write().await;
         assert!(eh_ref.tx_info_ptr.is_none());
@@ -163,6 +1348,10 @@ where
         assert!(eh_ref.tx_execution_info.is_none());
         eh_ref.tx_execution_info = eh_ref.tx_execution_info_iter.next();
         eh_ref.call_iter = eh_ref.tx_execution_info.as_ref().unwrap().gen_call_iterator();
+        eh_ref.call_context_stack.clear();
+        eh_ref.emitted_events.clear();
+        eh_ref.l2_to_l1_messages.clear();
+        eh_ref.storage_writes.clear();
     }
     pub async fn end_tx(&self) {
         let mut eh_ref = self.execution_helper.write().await;
@@ -170,7 +1359,36 @@ where
         eh_ref.tx_info_ptr = None;
         assert!(eh_ref.tx_execution_info.is_some());
         eh_ref.tx_execution_info = None;
+        eh_ref.current_tx_info = None;
+    }
+
+    /// Sets the fields of the transaction being executed, until `end_tx` is called.
+    pub async fn set_current_tx_info(&self, tx_info: TransactionInfo) {
+        self.execution_helper.write().await.current_tx_info = Some(tx_info);
     }
+
+    /// Overrides the info of the block seen by the `get_block_number` and `get_block_timestamp`
+    /// syscalls.
+    pub async fn set_block_info(&self, block_info: BlockInfo) {
+        self.execution_helper.write().await.block_info = block_info;
+    }
+
+    /// See `ExecutionHelper::block_info_view`.
+    pub async fn block_info(&self) -> BlockInfoView {
+        self.execution_helper.read().await.block_info_view()
+    }
+
+    /// Overrides the address returned by the `get_sequencer_address` syscall, e.g. to simulate a
+    /// transaction sequenced by someone else.
+    pub async fn set_sequencer_address(&self, sequencer_address: Felt252) {
+        self.execution_helper.write().await.sequencer_address_override = Some(sequencer_address);
+    }
+
+    /// See `ExecutionHelper::current_calldata`.
+    pub async fn current_calldata(&self) -> Vec<Felt252> {
+        self.execution_helper.read().await.current_calldata().to_vec()
+    }
+
     pub async fn skip_tx(&self) {
         self.start_tx(None).await;
         self.end_tx().await
@@ -184,6 +1402,7 @@ where
 
         assert!(eh_ref.call_info.is_none());
         let call_info = eh_ref.call_iter.next().unwrap();
+        eh_ref.call_events.clear();
 
         // unpack deployed calls
         eh_ref.deployed_contracts_iter = call_info
@@ -221,38 +1440,257 @@ where
         assert_iterators_exhausted(&eh_ref);
         assert!(eh_ref.call_info.is_some());
         eh_ref.call_info = None;
+        eh_ref.call_events.clear();
     }
     pub async fn skip_call(&mut self) {
         self.enter_call(None).await;
         self.exit_call().await;
     }
 
+    /// Reads `key` from the storage of `address`, see `normalize_storage_key`.
     pub async fn read_storage_for_address(&mut self, address: Felt252, key: Felt252) -> Result<Felt252, StorageError> {
+        let key = normalize_storage_key(key);
         let mut eh_ref = self.execution_helper.write().await;
-        let storage_by_address = &mut eh_ref.storage_by_address;
-        if let Some(storage) = storage_by_address.get_mut(&address) {
-            return storage.read(key.to_biguint()).await.ok_or(StorageError::ContentNotFound);
+        eh_ref.check_storage_address(address)?;
+        eh_ref.check_storage_key(address, key)?;
+        eh_ref.accessed_storage_keys.entry(address).or_default().insert(key);
+
+        let has_snapshot = match &eh_ref.storage_snapshot {
+            Some(storage_snapshot) => match storage_snapshot.get(&(address, key)) {
+                Some(value) => return Ok(*value),
+                None => true,
+            },
+            None => false,
+        };
+
+        if let Some(value) = eh_ref.storage_read_cache.get(&(address, key)) {
+            return Ok(*value);
+        }
+
+        let value = match eh_ref.storage_by_address.get_mut(&address) {
+            Some(storage) => storage.try_read(key.to_biguint()).await?,
+            None => match eh_ref.storage_fallback.as_ref() {
+                Some(storage_fallback) => {
+                    let value = storage_fallback(address, key).await?;
+                    if let Some(value) = value {
+                        eh_ref.storage_read_cache.insert((address, key), value);
+                    }
+                    value
+                }
+                None if has_snapshot => None,
+                // Without a snapshot, the storage of every contract accessed by the block is expected
+                None => return Err(StorageError::ContentNotFound),
+            },
+        };
+        match value {
+            Some(value) => Ok(value),
+            None if has_snapshot || eh_ref.storage_miss_policy == StorageMissPolicy::ReturnZero => Ok(Felt252::ZERO),
+            None => Err(StorageError::ContentNotFound),
+        }
+    }
+
+    /// Reads the given (contract address, key) pairs ahead of time so that subsequent calls to
+    /// `read_storage_for_address` are served from memory.
+    ///
+    /// Reads on different contracts are issued concurrently. Keys are normalized like in
+    /// `read_storage_for_address`. Keys that cannot be read, or that belong to a contract without
+    /// storage, are left for `read_storage_for_address` to handle; read failures are recorded as
+    /// warnings.
+    pub async fn prefetch_storage(&mut self, requests: &[(Felt252, Felt252)]) {
+        let mut eh_ref = self.execution_helper.write().await;
+        let eh_ref = &mut *eh_ref;
+
+        let mut keys_by_address: HashMap<Felt252, Vec<Felt252>> = HashMap::new();
+        for (address, key) in requests {
+            let key = normalize_storage_key(*key);
+            if !eh_ref.storage_read_cache.contains_key(&(*address, key)) {
+                keys_by_address.entry(*address).or_default().push(key);
+            }
         }
 
-        Err(StorageError::ContentNotFound)
+        let reads = eh_ref.storage_by_address.iter_mut().filter_map(|(address, storage)| {
+            let keys = keys_by_address.remove(address)?;
+            Some(async move {
+                let mut values = Vec::with_capacity(keys.len());
+                let mut warnings = vec![];
+                for key in keys {
+                    match storage.try_read(key.to_biguint()).await {
+                        Ok(Some(value)) => values.push(((*address, key), value)),
+                        Ok(None) => {}
+                        Err(e) => warnings.push(format!(
+                            "Failed to prefetch key {} of contract {}: {}",
+                            key.to_hex_string(),
+                            address.to_hex_string(),
+                            e
+                        )),
+                    }
+                }
+                (values, warnings)
+            })
+        });
+        for (values, warnings) in futures::future::join_all(reads).await {
+            eh_ref.storage_read_cache.extend(values);
+            eh_ref.warnings.extend(warnings);
+        }
+    }
+
+    /// Allocates `n` segments for the new state entries of the contracts that the block is expected
+    /// to touch, in one go. The state entry hints draw from them before allocating new segments.
+    pub async fn reserve_state_entry_segments(&self, vm: &mut VirtualMachine, n: usize) {
+        self.execution_helper.write().await.state_entry_segment_pool.reserve(vm, n);
+    }
+
+    /// Returns a segment for a new state entry, reserved or newly allocated.
+    pub async fn take_state_entry_segment(&self, vm: &mut VirtualMachine) -> Relocatable {
+        self.execution_helper.write().await.state_entry_segment_pool.allocate(vm)
     }
 
+    /// Records that the OS allocated `new_state_entry` to update the state of `contract_address`.
+    pub async fn record_state_entry_access(&self, contract_address: Felt252, new_state_entry: Relocatable) {
+        self.execution_helper.write().await.state_entry_access_order.push((contract_address, new_state_entry));
+    }
+
+    /// The new state entries allocated so far with their contract address, in allocation order.
+    pub async fn state_entry_access_order(&self) -> Vec<(Felt252, Relocatable)> {
+        self.execution_helper.read().await.state_entry_access_order.clone()
+    }
+
+    /// Returns the storage keys read through `read_storage_for_address`, per contract address.
+    pub async fn accessed_storage_keys(&self) -> HashMap<Felt252, HashSet<Felt252>> {
+        self.execution_helper.read().await.accessed_storage_keys.clone()
+    }
+
+    /// Resets the storage access log, typically between transactions.
+    pub async fn clear_storage_access_log(&self) {
+        self.execution_helper.write().await.accessed_storage_keys.clear();
+    }
+
+    /// Writes `value` under `key` in the storage of `address`, see `normalize_storage_key`.
     pub async fn write_storage_for_address(
         &mut self,
         address: Felt252,
         key: Felt252,
         value: Felt252,
     ) -> Result<(), StorageError> {
+        let key = normalize_storage_key(key);
         let mut eh_ref = self.execution_helper.write().await;
-        let storage_by_address = &mut eh_ref.storage_by_address;
-        if let Some(storage) = storage_by_address.get_mut(&address) {
+        let eh_ref = &mut *eh_ref;
+        eh_ref.check_storage_address(address)?;
+        eh_ref.check_storage_key(address, key)?;
+        // Writes count as accesses, and writing zero has to be recorded like any other value
+        eh_ref.accessed_storage_keys.entry(address).or_default().insert(key);
+        if eh_ref.dry_run {
+            let old_value = match eh_ref
+                .recorded_writes
+                .iter()
+                .rev()
+                .find(|write| write.contract_address == address && write.key == key)
+            {
+                Some(write) => write.new_value,
+                None => current_storage_value(eh_ref, address, key).await?,
+            };
+            eh_ref.recorded_writes.push(RecordedStorageWrite {
+                contract_address: address,
+                key,
+                old_value,
+                new_value: value,
+            });
+            return Ok(());
+        }
+
+        if eh_ref.storage_by_address.contains_key(&address) {
+            let previous_value = current_storage_value(eh_ref, address, key).await?;
+            if !eh_ref.snapshots.is_empty() {
+                eh_ref.storage_write_journal.push(StorageWriteJournalEntry {
+                    contract_address: address,
+                    key,
+                    previous_value,
+                });
+            }
+            let storage = eh_ref.storage_by_address.get_mut(&address).expect("the storage of the contract exists");
+            let contract_address = if eh_ref.on_storage_write.is_some() {
+                Some(ContractAddress(PatriciaKey::try_from(address).map_err(|e| StorageError::Backend(e.to_string()))?))
+            } else {
+                None
+            };
             storage.write(key.to_biguint(), value);
+            eh_ref.storage_writes.push(RecordedStorageWrite {
+                contract_address: address,
+                key,
+                old_value: previous_value,
+                new_value: value,
+            });
+            if let (Some(on_storage_write), Some(contract_address)) =
+                (eh_ref.on_storage_write.as_mut(), contract_address)
+            {
+                on_storage_write(contract_address, key, value);
+            }
+            // Keep prefetched values coherent with the writes
+            if let Some(cached_value) = eh_ref.storage_read_cache.get_mut(&(address, key)) {
+                *cached_value = value;
+            }
+            if let Some(snapshot_value) =
+                eh_ref.storage_snapshot.as_mut().and_then(|storage_snapshot| storage_snapshot.get_mut(&(address, key)))
+            {
+                *snapshot_value = value;
+            }
             Ok(())
         } else {
             Err(StorageError::ContentNotFound)
         }
     }
 
+    /// Takes a checkpoint of the storage writes, events and messages so that they can be rolled
+    /// back with `restore`, e.g. when a nested call reverts. Snapshots nest like a stack.
+    pub async fn snapshot(&self) -> SnapshotId {
+        let mut eh_ref = self.execution_helper.write().await;
+        let snapshot = ExecutionSnapshot {
+            n_storage_writes: eh_ref.storage_write_journal.len(),
+            n_applied_storage_writes: eh_ref.storage_writes.len(),
+            n_events: eh_ref.emitted_events.len(),
+            n_messages: eh_ref.l2_to_l1_messages.len(),
+        };
+        eh_ref.snapshots.push(snapshot);
+        SnapshotId(eh_ref.snapshots.len() - 1)
+    }
+
+    /// Rolls back everything done since `snapshot_id` was taken. The snapshot, and any snapshot
+    /// taken after it, is released.
+    pub async fn restore(&self, snapshot_id: SnapshotId) -> Result<(), HintError> {
+        let mut eh_ref = self.execution_helper.write().await;
+        let eh_ref = &mut *eh_ref;
+        let snapshot = release_snapshots(eh_ref, snapshot_id)?;
+
+        for entry in eh_ref.storage_write_journal.drain(snapshot.n_storage_writes..).rev() {
+            if let Some(storage) = eh_ref.storage_by_address.get_mut(&entry.contract_address) {
+                storage.write(entry.key.to_biguint(), entry.previous_value);
+            }
+            if let Some(cached_value) = eh_ref.storage_read_cache.get_mut(&(entry.contract_address, entry.key)) {
+                *cached_value = entry.previous_value;
+            }
+            if let Some(snapshot_value) = eh_ref
+                .storage_snapshot
+                .as_mut()
+                .and_then(|storage_snapshot| storage_snapshot.get_mut(&(entry.contract_address, entry.key)))
+            {
+                *snapshot_value = entry.previous_value;
+            }
+        }
+        eh_ref.storage_writes.truncate(snapshot.n_applied_storage_writes);
+        eh_ref.emitted_events.truncate(snapshot.n_events);
+        eh_ref.l2_to_l1_messages.truncate(snapshot.n_messages);
+
+        Ok(())
+    }
+
+    /// Keeps everything done since `snapshot_id` was taken and releases the snapshot, and any
+    /// snapshot taken after it.
+    pub async fn commit(&self, snapshot_id: SnapshotId) -> Result<(), HintError> {
+        let mut eh_ref = self.execution_helper.write().await;
+        release_snapshots(&mut eh_ref, snapshot_id)?;
+        Ok(())
+    }
+
     pub async fn compute_storage_commitments(&self) -> Result<HashMap<Felt252, CommitmentInfo>, CommitmentInfoError> {
         let mut eh_ref = self.execution_helper.write().await;
         let storage_by_address = &mut eh_ref.storage_by_address;
@@ -268,6 +1706,50 @@ where
     }
 }
 
+/// Value of the slot before a write, zero if the contract storage does not hold it. Fails if the
+/// storage backend cannot be read.
+async fn current_storage_value<PCS>(
+    eh_ref: &mut ExecutionHelper<PCS>,
+    address: Felt252,
+    key: Felt252,
+) -> Result<Felt252, StorageError>
+where
+    PCS: PerContractStorage,
+{
+    if let Some(value) = eh_ref
+        .storage_snapshot
+        .as_ref()
+        .and_then(|storage_snapshot| storage_snapshot.get(&(address, key)))
+        .or_else(|| eh_ref.storage_read_cache.get(&(address, key)))
+    {
+        return Ok(*value);
+    }
+    match eh_ref.storage_by_address.get_mut(&address) {
+        Some(storage) => Ok(storage.try_read(key.to_biguint()).await?.unwrap_or(Felt252::ZERO)),
+        None => Ok(Felt252::ZERO),
+    }
+}
+
+/// Pops `snapshot_id` and the snapshots above it, returning `snapshot_id`. The journal is only
+/// needed while a snapshot is active.
+fn release_snapshots<PCS>(
+    eh_ref: &mut ExecutionHelper<PCS>,
+    snapshot_id: SnapshotId,
+) -> Result<ExecutionSnapshot, HintError>
+where
+    PCS: PerContractStorage,
+{
+    if snapshot_id.0 >= eh_ref.snapshots.len() {
+        return Err(HintError::CustomHint(format!("Unknown snapshot {:?}", snapshot_id).into_boxed_str()));
+    }
+    eh_ref.snapshots.truncate(snapshot_id.0 + 1);
+    let snapshot = eh_ref.snapshots.pop().expect("snapshot exists");
+    if eh_ref.snapshots.is_empty() {
+        eh_ref.storage_write_journal.clear();
+    }
+    Ok(snapshot)
+}
+
 fn assert_iterators_exhausted<PCS>(eh_ref: &ExecutionHelper<PCS>)
 where
     PCS: PerContractStorage,
@@ -334,3 +1816,1027 @@ impl GenCallTopology for CallInfo {
         results.into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::sync::Arc;
+
+    use blockifier::execution::entry_point::CallEntryPoint;
+    use rstest::{fixture, rstest};
+    use starknet_api::transaction::Calldata;
+    use starknet_os_types::casm_contract_class::GenericCasmContractClass;
+
+    use super::*;
+    use crate::io::input::StarknetOsInput;
+    use crate::starknet::business_logic::fact_state::contract_state_objects::ContractState;
+    use crate::starknet::starknet_storage::{CommitmentInfo, OsSingleStarknetStorage, StorageLeaf};
+    use crate::starkware_utils::commitment_tree::base_types::{Height, TreeIndex};
+    use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
+    use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
+    use crate::storage::dict_storage::DictStorage;
+    use crate::storage::storage::FactFetchingContext;
+
+    #[allow(clippy::upper_case_acronyms)]
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    #[fixture]
+    fn block_context() -> BlockContext {
+        BlockContext::create_for_account_testing()
+    }
+
+    #[fixture]
+    fn old_block_number_and_hash(block_context: BlockContext) -> (Felt252, Felt252) {
+        (Felt252::from(block_context.block_info().block_number.0 - STORED_BLOCK_HASH_BUFFER), Felt252::from(66_u64))
+    }
+
+    async fn empty_contract_storage() -> PCS {
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+        OsSingleStarknetStorage::new(tree.clone(), tree, &[], ffc).await.unwrap()
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_accessed_storage_keys(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_a = Felt252::from(0x100);
+        let contract_b = Felt252::from(0x200);
+        let contract_storage_map = ContractStorageMap::from([
+            (contract_a, empty_contract_storage().await),
+            (contract_b, empty_contract_storage().await),
+        ]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        execution_helper.read_storage_for_address(contract_a, Felt252::ONE).await.unwrap();
+        execution_helper.read_storage_for_address(contract_a, Felt252::TWO).await.unwrap();
+        execution_helper.read_storage_for_address(contract_a, Felt252::ONE).await.unwrap();
+        execution_helper.read_storage_for_address(contract_b, Felt252::THREE).await.unwrap();
+
+        let accessed_storage_keys = execution_helper.accessed_storage_keys().await;
+        assert_eq!(
+            accessed_storage_keys,
+            HashMap::from([
+                (contract_a, HashSet::from([Felt252::ONE, Felt252::TWO])),
+                (contract_b, HashSet::from([Felt252::THREE])),
+            ])
+        );
+
+        execution_helper.clear_storage_access_log().await;
+        assert!(execution_helper.accessed_storage_keys().await.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_current_calldata(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let entry_point_calldata = vec![Felt252::from(10), Felt252::from(11)];
+        let tx_execution_info = TransactionExecutionInfo {
+            execute_call_info: Some(CallInfo {
+                call: CallEntryPoint {
+                    calldata: Calldata(Arc::new(entry_point_calldata.clone())),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![tx_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        assert!(execution_helper.current_calldata().await.is_empty());
+
+        execution_helper.start_tx(None).await;
+        assert_eq!(execution_helper.current_calldata().await, entry_point_calldata);
+
+        let outer_calldata = vec![Felt252::from(20)];
+        let inner_calldata = vec![Felt252::from(30), Felt252::from(31), Felt252::from(32)];
+        execution_helper.execution_helper.write().await.enter_call_context(outer_calldata.clone());
+        execution_helper.execution_helper.write().await.enter_call_context(inner_calldata.clone());
+        assert_eq!(execution_helper.current_calldata().await, inner_calldata);
+
+        execution_helper.execution_helper.write().await.exit_call_context().unwrap();
+        assert_eq!(execution_helper.current_calldata().await, outer_calldata);
+        execution_helper.execution_helper.write().await.exit_call_context().unwrap();
+        assert_eq!(execution_helper.current_calldata().await, entry_point_calldata);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_l1_message_nonces(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let sender = Felt252::from(0xabcd);
+        let messages: Vec<_> = (0..3)
+            .map(|i| L1ToL2Message {
+                from_address: sender,
+                to_address: Felt252::from(0x1234),
+                selector: Felt252::from(0x5678),
+                payload: vec![Felt252::from(i)],
+            })
+            .collect();
+        for (nonce, message) in messages.iter().enumerate() {
+            execution_helper.register_l1_to_l2_message_with_nonce(message.clone(), Felt252::from(nonce)).await.unwrap();
+        }
+        assert_eq!(execution_helper.next_expected_l1_nonce(sender).await, Felt252::ZERO);
+
+        execution_helper.consume_l1_to_l2_message(&messages[0]).await.unwrap();
+        assert_eq!(execution_helper.next_expected_l1_nonce(sender).await, Felt252::ONE);
+
+        // Nonce 1 was not consumed yet
+        let error = execution_helper.consume_l1_to_l2_message(&messages[2]).await.unwrap_err();
+        assert!(error.to_string().contains("consumed out of order: nonce 2, expected 1"), "{error}");
+        assert_eq!(execution_helper.consumed_l1_messages().await, vec![messages[0].clone()]);
+
+        execution_helper.consume_l1_to_l2_message(&messages[1]).await.unwrap();
+        execution_helper.consume_l1_to_l2_message(&messages[2]).await.unwrap();
+        assert_eq!(execution_helper.next_expected_l1_nonce(sender).await, Felt252::THREE);
+
+        // Nonces cannot be reused
+        let error =
+            execution_helper.register_l1_to_l2_message_with_nonce(messages[0].clone(), Felt252::ONE).await.unwrap_err();
+        assert!(error.to_string().contains("was already used"), "{error}");
+        assert_eq!(execution_helper.next_expected_l1_nonce(Felt252::from(0xef)).await, Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_block_info(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        let block_info = execution_helper.block_info().await;
+        assert_eq!(block_info.block_number(), block_context.block_info().block_number);
+        assert_eq!(block_info.block_timestamp(), block_context.block_info().block_timestamp);
+        assert_eq!(block_info.sequencer_address(), *block_context.block_info().sequencer_address.0.key());
+        assert_eq!(block_info.chain_id(), chain_id_to_felt(&block_context.chain_info().chain_id));
+
+        // The overrides are taken into account
+        let sequencer_address = Felt252::from(0x5e9);
+        execution_helper.set_sequencer_address(sequencer_address).await;
+        execution_helper
+            .set_block_info(BlockInfo { block_number: BlockNumber(1234), ..block_context.block_info().clone() })
+            .await;
+        let block_info = execution_helper.block_info().await;
+        assert_eq!(block_info.block_number(), BlockNumber(1234));
+        assert_eq!(block_info.sequencer_address(), sequencer_address);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_reverted_call_validation(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_reverted_call_validation();
+        let key = Felt252::from(42);
+
+        // A failed call whose writes were rolled back
+        execution_helper.execution_helper.write().await.enter_call_context(vec![]);
+        let snapshot_id = execution_helper.snapshot().await;
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::from(7)).await.unwrap();
+        execution_helper.execution_helper.write().await.mark_call_failed();
+        execution_helper.restore(snapshot_id).await.unwrap();
+        execution_helper.execution_helper.write().await.exit_call_context().unwrap();
+        assert_eq!(execution_helper.read_storage_for_address(contract_address, key).await.unwrap(), Felt252::ZERO);
+
+        // A successful call keeps its writes
+        execution_helper.execution_helper.write().await.enter_call_context(vec![]);
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::from(8)).await.unwrap();
+        execution_helper.execution_helper.write().await.exit_call_context().unwrap();
+
+        // A failed call whose writes were not rolled back
+        execution_helper.execution_helper.write().await.enter_call_context(vec![]);
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::from(9)).await.unwrap();
+        execution_helper.execution_helper.write().await.mark_call_failed();
+        let error = execution_helper.execution_helper.write().await.exit_call_context().unwrap_err();
+        assert!(
+            matches!(&error, HintError::AssertionFailed(message) if message.contains("1 storage writes, 0 events")),
+            "{error}"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_with_storage_snapshot(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract = Felt252::from(0x100);
+        let storage_snapshot = HashMap::from([(
+            ContractAddress(PatriciaKey::try_from(contract).unwrap()),
+            HashMap::from([(Felt252::ONE, Felt252::from(10)), (Felt252::TWO, Felt252::from(20))]),
+        )]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_storage_snapshot(storage_snapshot);
+
+        assert_eq!(execution_helper.read_storage_for_address(contract, Felt252::ONE).await.unwrap(), Felt252::from(10));
+        assert_eq!(execution_helper.read_storage_for_address(contract, Felt252::TWO).await.unwrap(), Felt252::from(20));
+        assert_eq!(execution_helper.read_storage_for_address(contract, Felt252::THREE).await.unwrap(), Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_get_compiled_class(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        let compiled_class = GenericCasmContractClass::from_bytes(casm_bytes.to_vec());
+        let class_hash = Felt252::from(0x1234);
+        let preloaded_classes: HashMap<Felt252, ContractClass> =
+            HashMap::from([(class_hash, compiled_class.to_blockifier_contract_class().unwrap().into())]);
+
+        let n_lookups = Rc::new(Cell::new(0));
+        let provider_lookups = n_lookups.clone();
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_class_provider(move |class_hash| {
+            provider_lookups.set(provider_lookups.get() + 1);
+            preloaded_classes.get(&class_hash).cloned()
+        });
+
+        let contract_class = execution_helper.get_compiled_class(class_hash).await.unwrap();
+        assert!(matches!(contract_class, ContractClass::V1(_)));
+        assert_eq!(n_lookups.get(), 1);
+
+        // The second lookup is served from the cache
+        assert_eq!(execution_helper.get_compiled_class(class_hash).await.unwrap(), contract_class);
+        assert_eq!(n_lookups.get(), 1);
+
+        assert!(execution_helper.get_compiled_class(Felt252::from(0x5678)).await.is_err());
+    }
+
+    /// Per-contract storage that counts how many times the backend is hit.
+    #[derive(Debug, Default)]
+    struct CountingStorage {
+        values: HashMap<TreeIndex, Felt252>,
+        n_reads: Rc<Cell<usize>>,
+        // Simulates a backend that cannot be reached
+        unavailable: bool,
+    }
+
+    impl PerContractStorage for CountingStorage {
+        async fn compute_commitment(&mut self) -> Result<CommitmentInfo, CommitmentInfoError> {
+            // The tests do not commit the storage
+            Ok(CommitmentInfo::default())
+        }
+
+        async fn read(&mut self, key: TreeIndex) -> Option<Felt252> {
+            self.n_reads.set(self.n_reads.get() + 1);
+            self.values.get(&key).copied()
+        }
+
+        async fn try_read(&mut self, key: TreeIndex) -> Result<Option<Felt252>, StorageError> {
+            if self.unavailable {
+                return Err(StorageError::Backend("connection refused".to_string()));
+            }
+            Ok(self.read(key).await)
+        }
+
+        fn write(&mut self, key: TreeIndex, value: Felt252) {
+            self.values.insert(key, value);
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_prefetch_storage(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_a = Felt252::from(0x100);
+        let contract_b = Felt252::from(0x200);
+        let n_reads = Rc::new(Cell::new(0));
+        let storage_with_values = |values: &[(u64, u64)]| CountingStorage {
+            values: values.iter().map(|(key, value)| (TreeIndex::from(*key), Felt252::from(*value))).collect(),
+            n_reads: n_reads.clone(),
+            unavailable: false,
+        };
+        let contract_storage_map = ContractStorageMap::from([
+            (contract_a, storage_with_values(&[(1, 10), (2, 20)])),
+            (contract_b, storage_with_values(&[(3, 30)])),
+        ]);
+        let mut execution_helper = ExecutionHelperWrapper::<CountingStorage>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        let requests = [(contract_a, Felt252::ONE), (contract_a, Felt252::TWO), (contract_b, Felt252::THREE)];
+        execution_helper.prefetch_storage(&requests).await;
+        assert_eq!(n_reads.get(), 3);
+
+        // Prefetching again and reading the prefetched keys does not hit the backend
+        execution_helper.prefetch_storage(&requests).await;
+        for _ in 0..10 {
+            assert_eq!(execution_helper.read_storage_for_address(contract_a, Felt252::ONE).await.unwrap(), 10.into());
+            assert_eq!(execution_helper.read_storage_for_address(contract_a, Felt252::TWO).await.unwrap(), 20.into());
+            assert_eq!(execution_helper.read_storage_for_address(contract_b, Felt252::THREE).await.unwrap(), 30.into());
+        }
+        assert_eq!(n_reads.get(), 3);
+
+        // Write-then-read returns the written value
+        execution_helper.write_storage_for_address(contract_a, Felt252::ONE, 11.into()).await.unwrap();
+        assert_eq!(execution_helper.read_storage_for_address(contract_a, Felt252::ONE).await.unwrap(), 11.into());
+        assert_eq!(n_reads.get(), 3);
+
+        // Keys that were not prefetched still go to the backend
+        assert_eq!(execution_helper.read_storage_for_address(contract_b, Felt252::from(4)).await.unwrap(), 0.into());
+        assert_eq!(n_reads.get(), 4);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_prefetch_storage_keys_and_errors(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let contract = Felt252::from(0x100);
+        let unavailable_contract = Felt252::from(0x200);
+        let n_reads = Rc::new(Cell::new(0));
+        let contract_storage_map = ContractStorageMap::from([
+            (
+                contract,
+                CountingStorage {
+                    values: HashMap::from([(TreeIndex::from(5u64), Felt252::from(50))]),
+                    n_reads: n_reads.clone(),
+                    unavailable: false,
+                },
+            ),
+            (unavailable_contract, CountingStorage { unavailable: true, ..Default::default() }),
+        ]);
+        let mut execution_helper = ExecutionHelperWrapper::<CountingStorage>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        // 2**251 + 5 is prefetched as 5, the key `read_storage_for_address` looks up
+        let unreduced_key = Felt252::from(&(BigUint::from(1u8) << 251)) + Felt252::from(5);
+        execution_helper.prefetch_storage(&[(contract, unreduced_key), (unavailable_contract, Felt252::ONE)]).await;
+        assert_eq!(n_reads.get(), 1);
+        assert_eq!(execution_helper.read_storage_for_address(contract, Felt252::from(5)).await.unwrap(), 50.into());
+        assert_eq!(n_reads.get(), 1);
+
+        // The backend failure is not swallowed
+        let warnings = execution_helper.warnings().await;
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("connection refused"), "{warnings:?}");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_storage_miss_policy(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract = Felt252::from(0x100);
+        let unavailable_contract = Felt252::from(0x200);
+        let absent_key = Felt252::from(42);
+
+        for storage_miss_policy in [StorageMissPolicy::ReturnZero, StorageMissPolicy::Error] {
+            let contract_storage_map = ContractStorageMap::from([
+                (contract, CountingStorage::default()),
+                (unavailable_contract, CountingStorage { unavailable: true, ..Default::default() }),
+            ]);
+            let mut execution_helper = ExecutionHelperWrapper::<CountingStorage>::new(
+                contract_storage_map,
+                vec![],
+                &block_context,
+                None,
+                old_block_number_and_hash,
+            )
+            .with_storage_miss_policy(storage_miss_policy);
+
+            let result = execution_helper.read_storage_for_address(contract, absent_key).await;
+            match storage_miss_policy {
+                StorageMissPolicy::ReturnZero => assert_eq!(result.unwrap(), Felt252::ZERO),
+                StorageMissPolicy::Error => assert!(matches!(result, Err(StorageError::ContentNotFound))),
+            }
+
+            let result = execution_helper.read_storage_for_address(unavailable_contract, absent_key).await;
+            assert!(matches!(result, Err(StorageError::Backend(_))), "unexpected result: {result:?}");
+
+            // Writes read the value they overwrite, and must not record a zero in its place
+            let result =
+                execution_helper.write_storage_for_address(unavailable_contract, absent_key, Felt252::ONE).await;
+            assert!(matches!(result, Err(StorageError::Backend(_))), "unexpected result: {result:?}");
+            assert!(execution_helper.execution_helper.read().await.storage_writes.is_empty());
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_snapshot_restore(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let (key_a, key_b) = (Felt252::from(1), Felt252::from(2));
+
+        execution_helper.write_storage_for_address(contract_address, key_a, Felt252::from(10)).await.unwrap();
+        let snapshot = execution_helper.snapshot().await;
+        execution_helper.write_storage_for_address(contract_address, key_a, Felt252::from(11)).await.unwrap();
+
+        // A nested snapshot that is kept is still rolled back with the outer one
+        let nested_snapshot = execution_helper.snapshot().await;
+        execution_helper.write_storage_for_address(contract_address, key_b, Felt252::from(20)).await.unwrap();
+        execution_helper.execution_helper.write().await.emitted_events.push(OrderedEvent {
+            order: 0,
+            contract_address,
+            selector: None,
+            keys: vec![],
+            data: vec![],
+        });
+        execution_helper.commit(nested_snapshot).await.unwrap();
+
+        execution_helper.restore(snapshot).await.unwrap();
+        assert_eq!(execution_helper.read_storage_for_address(contract_address, key_a).await.unwrap(), 10.into());
+        assert_eq!(execution_helper.read_storage_for_address(contract_address, key_b).await.unwrap(), 0.into());
+        assert!(execution_helper.emitted_events().await.is_empty());
+
+        // Both snapshots were released
+        assert!(execution_helper.restore(snapshot).await.is_err());
+        assert!(execution_helper.execution_helper.read().await.storage_write_journal.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_syscall_profile(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let builtin_ptrs = Relocatable::from((1, 0));
+        {
+            let os_logger = &mut execution_helper.execution_helper.write().await.os_logger;
+            os_logger
+                .enter_syscall(SyscallSelector::StorageRead, false, 100, builtin_ptrs, Relocatable::from((2, 0)))
+                .unwrap();
+            os_logger
+                .exit_syscall(SyscallSelector::StorageRead, 130, builtin_ptrs, (2, 1).into(), &HashMap::new())
+                .unwrap();
+            os_logger
+                .enter_syscall(SyscallSelector::StorageRead, false, 200, builtin_ptrs, Relocatable::from((2, 1)))
+                .unwrap();
+            os_logger
+                .exit_syscall(SyscallSelector::StorageRead, 220, builtin_ptrs, (2, 2).into(), &HashMap::new())
+                .unwrap();
+        }
+
+        let profile = execution_helper.syscall_profile().await;
+        assert_eq!(
+            profile.0,
+            HashMap::from([(
+                SyscallSelector::StorageRead,
+                SyscallUsage { n_calls: 2, n_steps: 50, range_check_usage: 2, keccak_rounds: 0 }
+            )])
+        );
+
+        let json = profile.to_json().unwrap();
+        assert!(json.contains("\"StorageRead\""));
+        assert_eq!(serde_json::from_str::<SyscallProfile>(&json).unwrap(), profile);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_storage_fallback(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_address = Felt252::from(0x300);
+        let n_fallback_reads = Rc::new(Cell::new(0));
+        let n_fallback_reads_clone = n_fallback_reads.clone();
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_storage_fallback(Box::new(move |_contract_address, key| {
+            n_fallback_reads_clone.set(n_fallback_reads_clone.get() + 1);
+            Box::pin(async move { Ok((key == Felt252::ONE).then_some(Felt252::from(5))) })
+        }));
+
+        for _ in 0..2 {
+            let value = execution_helper.read_storage_for_address(contract_address, Felt252::ONE).await.unwrap();
+            assert_eq!(value, Felt252::from(5));
+        }
+        // The value was cached after the first read
+        assert_eq!(n_fallback_reads.get(), 1);
+        // Keys absent from the fallback follow the miss policy
+        let value = execution_helper.read_storage_for_address(contract_address, Felt252::TWO).await.unwrap();
+        assert_eq!(value, Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_validate_event_ordering(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        let event = |order| OrderedEvent {
+            order,
+            contract_address: Felt252::from(0x100),
+            selector: None,
+            keys: vec![],
+            data: vec![],
+        };
+
+        execution_helper.execution_helper.write().await.emitted_events = vec![event(0), event(1), event(2)];
+        execution_helper.validate_event_ordering().await.unwrap();
+
+        // The event of order 2 was dropped
+        execution_helper.execution_helper.write().await.emitted_events = vec![event(0), event(1), event(3)];
+        let result = execution_helper.validate_event_ordering().await;
+        assert!(
+            matches!(result, Err(HintError::AssertionFailed(msg)) if msg.as_ref() == "Event #2 has order 3, expected 2")
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_storage_key_normalization(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        // 2**251 + 5 and 5 address the same slot
+        let address_bound = Felt252::from(&(BigUint::from(1u8) << 251));
+        let key = Felt252::from(5);
+        let unreduced_key = address_bound + key;
+        assert_eq!(normalize_storage_key(unreduced_key), key);
+        assert_eq!(normalize_storage_key(key), key);
+
+        execution_helper.write_storage_for_address(contract_address, unreduced_key, Felt252::from(10)).await.unwrap();
+        assert_eq!(execution_helper.read_storage_for_address(contract_address, key).await.unwrap(), Felt252::from(10));
+
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::from(20)).await.unwrap();
+        assert_eq!(
+            execution_helper.read_storage_for_address(contract_address, unreduced_key).await.unwrap(),
+            Felt252::from(20)
+        );
+
+        // Both writes are recorded under the normalized key
+        let effects = execution_helper.export_effects().await;
+        assert_eq!(
+            effects.storage_writes,
+            vec![RecordedStorageWrite {
+                contract_address,
+                key,
+                old_value: Felt252::ZERO,
+                new_value: Felt252::from(20)
+            }]
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_export_effects_round_trip(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        execution_helper.write_storage_for_address(contract_address, Felt252::ONE, Felt252::from(10)).await.unwrap();
+        // Felts that do not fit in 64 bits must survive the round-trip as well
+        execution_helper.write_storage_for_address(contract_address, Felt252::TWO, Felt252::MAX).await.unwrap();
+        {
+            let mut eh_ref = execution_helper.execution_helper.write().await;
+            eh_ref.emitted_events.push(OrderedEvent {
+                order: 0,
+                contract_address,
+                selector: None,
+                keys: vec![Felt252::from(0xabc)],
+                data: vec![Felt252::ONE, Felt252::TWO],
+            });
+            eh_ref.l2_to_l1_messages.push(L2ToL1Message {
+                from_address: contract_address,
+                to_address: Felt252::from(0x1234),
+                payload: vec![Felt252::from(u128::MAX)],
+            });
+        }
+
+        let effects = execution_helper.export_effects().await;
+        assert_eq!(effects.events.len(), 1);
+        assert_eq!(effects.l2_to_l1_messages.len(), 1);
+        assert_eq!(
+            effects.storage_writes,
+            vec![
+                RecordedStorageWrite {
+                    contract_address,
+                    key: Felt252::ONE,
+                    old_value: Felt252::ZERO,
+                    new_value: Felt252::from(10)
+                },
+                RecordedStorageWrite {
+                    contract_address,
+                    key: Felt252::TWO,
+                    old_value: Felt252::ZERO,
+                    new_value: Felt252::MAX
+                },
+            ]
+        );
+
+        let bytes = effects.to_bytes().unwrap();
+        assert_eq!(SyscallEffects::from_bytes(&bytes).unwrap(), effects);
+
+        let event = &effects.events[0];
+        assert_eq!(&OrderedEvent::from_bytes(&event.to_bytes().unwrap()).unwrap(), event);
+        // A split selector survives the round-trip as well
+        let event = OrderedEvent { selector: Some(Felt252::from(0xabc)), keys: vec![], ..event.clone() };
+        assert_eq!(OrderedEvent::from_bytes(&event.to_bytes().unwrap()).unwrap(), event);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_on_storage_write(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_a = Felt252::from(0x100);
+        let contract_b = Felt252::from(0x200);
+        let contract_storage_map = ContractStorageMap::from([
+            (contract_a, empty_contract_storage().await),
+            (contract_b, empty_contract_storage().await),
+        ]);
+
+        let observed_writes = Rc::new(RefCell::new(vec![]));
+        let observed_writes_clone = observed_writes.clone();
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_on_storage_write(move |contract_address, key, value| {
+            observed_writes_clone.borrow_mut().push((contract_address, key, value))
+        });
+
+        execution_helper.write_storage_for_address(contract_a, Felt252::ONE, Felt252::from(10)).await.unwrap();
+        execution_helper.write_storage_for_address(contract_b, Felt252::ONE, Felt252::from(20)).await.unwrap();
+        execution_helper.write_storage_for_address(contract_a, Felt252::ONE, Felt252::from(11)).await.unwrap();
+        // Writes that are not applied are not reported
+        assert!(
+            execution_helper.write_storage_for_address(Felt252::from(0x300), Felt252::ONE, Felt252::ONE).await.is_err()
+        );
+
+        let address_a = ContractAddress(PatriciaKey::try_from(contract_a).unwrap());
+        let address_b = ContractAddress(PatriciaKey::try_from(contract_b).unwrap());
+        assert_eq!(
+            *observed_writes.borrow(),
+            vec![
+                (address_a, Felt252::ONE, Felt252::from(10)),
+                (address_b, Felt252::ONE, Felt252::from(20)),
+                (address_a, Felt252::ONE, Felt252::from(11)),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_storage_address_validation(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let contract_a = Felt252::from(0x100);
+        let contract_b = Felt252::from(0x200);
+        let contract_storage_map = ContractStorageMap::from([
+            (contract_a, empty_contract_storage().await),
+            (contract_b, empty_contract_storage().await),
+        ]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_storage_address_validation();
+
+        let mut call_info = CallInfo::default();
+        call_info.call.storage_address = ContractAddress(PatriciaKey::try_from(contract_a).unwrap());
+        execution_helper.execution_helper.write().await.call_info = Some(call_info.clone());
+
+        execution_helper.write_storage_for_address(contract_a, Felt252::ONE, Felt252::from(10)).await.unwrap();
+        let result = execution_helper.write_storage_for_address(contract_b, Felt252::ONE, Felt252::from(20)).await;
+        assert!(matches!(
+            result,
+            Err(StorageError::UnexpectedContractAddress { expected, actual }) if expected == contract_a && actual == contract_b
+        ));
+        assert!(execution_helper.read_storage_for_address(contract_b, Felt252::ONE).await.is_err());
+        assert_eq!(execution_helper.execution_helper.read().await.storage_writes.len(), 1);
+
+        // Library calls are not checked
+        call_info.call.call_type = CallType::Delegate;
+        execution_helper.execution_helper.write().await.call_info = Some(call_info);
+        execution_helper.write_storage_for_address(contract_b, Felt252::ONE, Felt252::from(20)).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_reserved_storage_keys(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        )
+        .with_reserved_storage_keys(vec![(Felt252::from(10), Felt252::from(20))]);
+
+        for key in [Felt252::from(10), Felt252::from(15), Felt252::from(20)] {
+            let result = execution_helper.read_storage_for_address(contract_address, key).await;
+            assert!(matches!(
+                result,
+                Err(StorageError::ReservedStorageKey { contract_address: address, key: reserved_key })
+                    if address == contract_address && reserved_key == key
+            ));
+            let result = execution_helper.write_storage_for_address(contract_address, key, Felt252::ONE).await;
+            assert!(matches!(result, Err(StorageError::ReservedStorageKey { .. })));
+        }
+        assert!(execution_helper.execution_helper.read().await.accessed_storage_keys.is_empty());
+
+        assert_eq!(
+            execution_helper.read_storage_for_address(contract_address, Felt252::from(21)).await.unwrap(),
+            Felt252::ZERO
+        );
+        execution_helper.write_storage_for_address(contract_address, Felt252::from(9), Felt252::ONE).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_dry_run_records_storage_writes(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::record_only(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        let key = Felt252::from(42);
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::from(8000)).await.unwrap();
+
+        assert_eq!(
+            execution_helper.recorded_writes().await,
+            vec![RecordedStorageWrite {
+                contract_address,
+                key,
+                old_value: Felt252::ZERO,
+                new_value: Felt252::from(8000)
+            }]
+        );
+        assert_eq!(execution_helper.read_storage_for_address(contract_address, key).await.unwrap(), Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_storage_write_to_zero(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+
+        let key = Felt252::from(42);
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::from(7)).await.unwrap();
+        execution_helper.write_storage_for_address(contract_address, key, Felt252::ZERO).await.unwrap();
+
+        // The individual writes, `export_effects` coalesces them
+        let storage_writes = execution_helper.execution_helper.read().await.storage_writes.clone();
+        assert_eq!(
+            storage_writes,
+            vec![
+                RecordedStorageWrite { contract_address, key, old_value: Felt252::ZERO, new_value: Felt252::from(7) },
+                RecordedStorageWrite { contract_address, key, old_value: Felt252::from(7), new_value: Felt252::ZERO },
+            ]
+        );
+        assert!(storage_writes[0].fills_slot());
+        assert!(storage_writes[1].clears_slot());
+        assert_eq!(execution_helper.accessed_storage_keys().await[&contract_address], HashSet::from([key]));
+        assert_eq!(execution_helper.read_storage_for_address(contract_address, key).await.unwrap(), Felt252::ZERO);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_nonces(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let account = Felt252::from(0x100);
+        let unknown_account = Felt252::from(0x200);
+        let storage_commitment_tree =
+            PatriciaTree { root: starknet_os_types::hash::Hash::empty(), height: Height(251) };
+        let os_input = StarknetOsInput {
+            contract_state_commitment_info: CommitmentInfo::default(),
+            contract_class_commitment_info: CommitmentInfo::default(),
+            deprecated_compiled_classes: HashMap::new(),
+            compiled_classes: HashMap::new(),
+            compiled_class_visited_pcs: HashMap::new(),
+            contracts: HashMap::from([(
+                account,
+                ContractState::create(vec![0; 32], storage_commitment_tree, Felt252::from(5)),
+            )]),
+            contract_address_to_class_hash: HashMap::new(),
+            class_hash_to_compiled_class_hash: HashMap::new(),
+            general_config: Default::default(),
+            transactions: vec![],
+            declared_class_hash_to_component_hashes: HashMap::new(),
+            new_block_hash: Felt252::ZERO,
+            prev_block_hash: Felt252::ZERO,
+            full_output: false,
+        };
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            Some(Rc::new(os_input)),
+            old_block_number_and_hash,
+        );
+
+        // Read through to the state of the OS input
+        assert_eq!(execution_helper.nonce_at(account).await, Felt252::from(5));
+        assert_eq!(execution_helper.nonce_at(unknown_account).await, Felt252::ZERO);
+
+        assert_eq!(execution_helper.increment_nonce(account).await, Felt252::from(6));
+        assert_eq!(execution_helper.increment_nonce(unknown_account).await, Felt252::ONE);
+        assert_eq!(execution_helper.nonce_at(account).await, Felt252::from(6));
+        assert_eq!(execution_helper.nonce_at(unknown_account).await, Felt252::ONE);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_l1_handler_messages_from_os_input(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let l1_handler = InternalTransaction {
+            r#type: "L1_HANDLER".to_string(),
+            contract_address: Some(Felt252::from(0x1234)),
+            entry_point_selector: Some(Felt252::from(0x5678)),
+            calldata: Some(vec![Felt252::from(0xabcd), Felt252::ONE, Felt252::TWO]),
+            ..Default::default()
+        };
+        let invoke = InternalTransaction {
+            r#type: "INVOKE_FUNCTION".to_string(),
+            contract_address: Some(Felt252::from(0x1234)),
+            calldata: Some(vec![Felt252::ONE]),
+            ..Default::default()
+        };
+        let os_input = StarknetOsInput {
+            contract_state_commitment_info: CommitmentInfo::default(),
+            contract_class_commitment_info: CommitmentInfo::default(),
+            deprecated_compiled_classes: HashMap::new(),
+            compiled_classes: HashMap::new(),
+            compiled_class_visited_pcs: HashMap::new(),
+            contracts: HashMap::new(),
+            contract_address_to_class_hash: HashMap::new(),
+            class_hash_to_compiled_class_hash: HashMap::new(),
+            general_config: Default::default(),
+            transactions: vec![invoke, l1_handler],
+            declared_class_hash_to_component_hashes: HashMap::new(),
+            new_block_hash: Felt252::ZERO,
+            prev_block_hash: Felt252::ZERO,
+            full_output: false,
+        };
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            Some(Rc::new(os_input)),
+            old_block_number_and_hash,
+        )
+        .with_l1_message_validation();
+
+        // Only the L1 handler transaction consumes a message
+        let message = L1ToL2Message {
+            from_address: Felt252::from(0xabcd),
+            to_address: Felt252::from(0x1234),
+            selector: Felt252::from(0x5678),
+            payload: vec![Felt252::ONE, Felt252::TWO],
+        };
+        let mut eh_ref = execution_helper.execution_helper.write().await;
+        assert_eq!(eh_ref.pending_l1_messages, vec![message.clone()]);
+        eh_ref.consume_l1_handler_message(&message).unwrap();
+        assert!(eh_ref.consume_l1_handler_message(&message).is_err());
+
+        // Without validation, the L1 handlers are not checked
+        eh_ref.validate_l1_messages = false;
+        eh_ref.consume_l1_handler_message(&message).unwrap();
+        assert_eq!(eh_ref.consumed_l1_messages, vec![message]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_coalesce_storage_writes(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[values(false, true)] dry_run: bool,
+    ) {
+        let contract_address = Felt252::from(0x100);
+        let contract_storage_map = ContractStorageMap::from([(contract_address, empty_contract_storage().await)]);
+        let mut execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            contract_storage_map,
+            vec![],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        execution_helper.execution_helper.write().await.dry_run = dry_run;
+
+        let (key, other_key) = (Felt252::from(42), Felt252::from(43));
+        for value in [5, 6, 7] {
+            execution_helper.write_storage_for_address(contract_address, key, Felt252::from(value)).await.unwrap();
+        }
+        execution_helper.write_storage_for_address(contract_address, other_key, Felt252::ONE).await.unwrap();
+
+        let coalesced_writes = if dry_run {
+            execution_helper.recorded_writes().await
+        } else {
+            execution_helper.export_effects().await.storage_writes
+        };
+        assert_eq!(
+            coalesced_writes,
+            vec![
+                RecordedStorageWrite { contract_address, key, old_value: Felt252::ZERO, new_value: Felt252::from(7) },
+                RecordedStorageWrite {
+                    contract_address,
+                    key: other_key,
+                    old_value: Felt252::ZERO,
+                    new_value: Felt252::ONE
+                },
+            ]
+        );
+
+        // Every write is still logged
+        let eh_ref = execution_helper.execution_helper.read().await;
+        let write_log = if dry_run { &eh_ref.recorded_writes } else { &eh_ref.storage_writes };
+        assert_eq!(write_log.len(), 4);
+        assert_eq!(write_log[2].old_value, Felt252::from(6));
+    }
+}
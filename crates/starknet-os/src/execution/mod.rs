@@ -2,6 +2,10 @@ mod constants;
 pub mod deprecated_syscall_handler;
 pub mod execute_syscalls;
 pub mod helper;
+pub mod run_diff;
+pub mod run_input;
 pub mod secp_handler;
+pub mod segment_pool;
 pub mod syscall_handler;
+pub mod syscall_handler_backend;
 pub mod syscall_handler_utils;
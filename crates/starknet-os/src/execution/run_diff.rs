@@ -0,0 +1,354 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+
+use cairo_vm::Felt252;
+
+use super::helper::{
+    ExecutionHelperWrapper, L2ToL1Message, OrderedEvent, RecordedEvent, RecordedStorageWrite, StorageAccess,
+    SyscallTranscript,
+};
+use crate::starknet::starknet_storage::PerContractStorage;
+use crate::storage::storage::StorageError;
+
+/// The first point where two runs of the execution helper disagree.
+///
+/// `index` is the position in the corresponding log: storage writes and messages applied to the
+/// state come first, followed by the ones recorded in dry-run mode. `None` means that the log of
+/// this run is shorter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunDivergence {
+    StorageWrite {
+        index: usize,
+        a: Option<RecordedStorageWrite>,
+        b: Option<RecordedStorageWrite>,
+    },
+    Event {
+        index: usize,
+        a: Option<OrderedEvent>,
+        b: Option<OrderedEvent>,
+    },
+    RecordedEvent {
+        index: usize,
+        a: Option<RecordedEvent>,
+        b: Option<RecordedEvent>,
+    },
+    Message {
+        index: usize,
+        a: Option<L2ToL1Message>,
+        b: Option<L2ToL1Message>,
+    },
+    /// `key` of `contract_address` was accessed by only one of the runs, `a` if `accessed_by_a`.
+    AccessedStorageKey {
+        contract_address: Felt252,
+        key: Felt252,
+        accessed_by_a: bool,
+    },
+}
+
+fn fmt_entry<T: Debug>(entry: &Option<T>) -> String {
+    match entry {
+        Some(entry) => format!("{:?}", entry),
+        None => "nothing".to_string(),
+    }
+}
+
+impl Display for RunDivergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StorageWrite { index, a, b } => {
+                write!(f, "storage write #{index}")?;
+                if let Some(write) = a.as_ref().or(b.as_ref()) {
+                    write!(
+                        f,
+                        " (contract {}, key {})",
+                        write.contract_address.to_hex_string(),
+                        write.key.to_hex_string()
+                    )?;
+                }
+                write!(f, ": {} vs {}", fmt_entry(a), fmt_entry(b))
+            }
+            Self::Event { index, a, b } => write!(f, "event #{index}: {} vs {}", fmt_entry(a), fmt_entry(b)),
+            Self::RecordedEvent { index, a, b } => {
+                write!(f, "recorded event #{index}: {} vs {}", fmt_entry(a), fmt_entry(b))
+            }
+            Self::Message { index, a, b } => write!(f, "message #{index}: {} vs {}", fmt_entry(a), fmt_entry(b)),
+            Self::AccessedStorageKey { contract_address, key, accessed_by_a } => write!(
+                f,
+                "key {} of contract {} was only accessed by run {}",
+                key.to_hex_string(),
+                contract_address.to_hex_string(),
+                if *accessed_by_a { "a" } else { "b" }
+            ),
+        }
+    }
+}
+
+/// The result of [`diff_runs`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunDiff {
+    pub first_divergence: Option<RunDivergence>,
+}
+
+impl RunDiff {
+    pub fn is_empty(&self) -> bool {
+        self.first_divergence.is_none()
+    }
+}
+
+/// Returns the index of the first entry that differs between `a` and `b`, with both entries.
+fn first_mismatch<T: Clone + PartialEq>(a: &[T], b: &[T]) -> Option<(usize, Option<T>, Option<T>)> {
+    (0..a.len().max(b.len()))
+        .find(|&index| a.get(index) != b.get(index))
+        .map(|index| (index, a.get(index).cloned(), b.get(index).cloned()))
+}
+
+/// Compares the side effects recorded by two execution helpers, e.g. two executions of the same
+/// block, and reports the first divergence.
+///
+/// Storage writes are compared first, then events, messages and finally the accessed storage
+/// keys. Contracts and keys are visited in increasing order so that the report is deterministic.
+pub async fn diff_runs<PCS>(a: &ExecutionHelperWrapper<PCS>, b: &ExecutionHelperWrapper<PCS>) -> RunDiff
+where
+    PCS: PerContractStorage,
+{
+    let a = a.execution_helper.read().await;
+    let b = b.execution_helper.read().await;
+
+    let writes_a: Vec<_> = a.storage_writes.iter().chain(&a.recorded_writes).cloned().collect();
+    let writes_b: Vec<_> = b.storage_writes.iter().chain(&b.recorded_writes).cloned().collect();
+    if let Some((index, a, b)) = first_mismatch(&writes_a, &writes_b) {
+        return RunDiff { first_divergence: Some(RunDivergence::StorageWrite { index, a, b }) };
+    }
+
+    if let Some((index, a, b)) = first_mismatch(&a.emitted_events, &b.emitted_events) {
+        return RunDiff { first_divergence: Some(RunDivergence::Event { index, a, b }) };
+    }
+    if let Some((index, a, b)) = first_mismatch(&a.recorded_events, &b.recorded_events) {
+        return RunDiff { first_divergence: Some(RunDivergence::RecordedEvent { index, a, b }) };
+    }
+
+    let messages_a: Vec<_> = a.l2_to_l1_messages.iter().chain(&a.recorded_messages).cloned().collect();
+    let messages_b: Vec<_> = b.l2_to_l1_messages.iter().chain(&b.recorded_messages).cloned().collect();
+    if let Some((index, a, b)) = first_mismatch(&messages_a, &messages_b) {
+        return RunDiff { first_divergence: Some(RunDivergence::Message { index, a, b }) };
+    }
+
+    let empty = HashSet::new();
+    let contract_addresses: BTreeSet<_> =
+        a.accessed_storage_keys.keys().chain(b.accessed_storage_keys.keys()).copied().collect();
+    for contract_address in contract_addresses {
+        let keys_a = a.accessed_storage_keys.get(&contract_address).unwrap_or(&empty);
+        let keys_b = b.accessed_storage_keys.get(&contract_address).unwrap_or(&empty);
+        let first_key = keys_a.symmetric_difference(keys_b).min();
+        if let Some(&key) = first_key {
+            let accessed_by_a = keys_a.contains(&key);
+            return RunDiff {
+                first_divergence: Some(RunDivergence::AccessedStorageKey { contract_address, key, accessed_by_a }),
+            };
+        }
+    }
+
+    RunDiff::default()
+}
+
+/// A storage read of a transcript that returned another value when replayed, see
+/// [`replay_transcript`]. `index` is the position of the read in the transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayDivergence {
+    pub index: usize,
+    pub contract_address: Felt252,
+    pub key: Felt252,
+    pub recorded: Felt252,
+    pub replayed: Felt252,
+}
+
+impl Display for ReplayDivergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "storage read #{} (contract {}, key {}): recorded {}, replayed {}",
+            self.index,
+            self.contract_address.to_hex_string(),
+            self.key.to_hex_string(),
+            self.recorded.to_hex_string(),
+            self.replayed.to_hex_string()
+        )
+    }
+}
+
+/// Reads again the storage slots read by the syscalls of `transcript` from `execution_helper`,
+/// e.g. a fresh helper seeded with the state of the recorded run, and reports the first read
+/// that returns another value.
+///
+/// Entries without a recorded storage read are skipped, and so are the reads of the slots that
+/// the transcript wrote before: their value depends on the write, which is not recorded.
+pub async fn replay_transcript<PCS>(
+    transcript: &SyscallTranscript,
+    execution_helper: &mut ExecutionHelperWrapper<PCS>,
+) -> Result<Option<ReplayDivergence>, StorageError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let mut written_slots = HashSet::new();
+    for entry in transcript.entries() {
+        match entry.storage_access {
+            Some(StorageAccess::Write { contract_address, key }) => {
+                written_slots.insert((contract_address, key));
+            }
+            Some(StorageAccess::Read { contract_address, key, value })
+                if !written_slots.contains(&(contract_address, key)) =>
+            {
+                let replayed = execution_helper.read_storage_for_address(contract_address, key).await?;
+                if replayed != value {
+                    return Ok(Some(ReplayDivergence {
+                        index: entry.index,
+                        contract_address,
+                        key,
+                        recorded: value,
+                        replayed,
+                    }));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use blockifier::context::BlockContext;
+    use rstest::{fixture, rstest};
+    use starknet_api::core::{ContractAddress, PatriciaKey};
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::execution::helper::ContractStorageMap;
+    use crate::execution::syscall_handler_utils::SyscallSelector;
+    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::storage::dict_storage::DictStorage;
+
+    #[allow(clippy::upper_case_acronyms)]
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    #[fixture]
+    fn block_context() -> BlockContext {
+        BlockContext::create_for_account_testing()
+    }
+
+    fn execution_helper(block_context: &BlockContext) -> ExecutionHelperWrapper<PCS> {
+        let block_number = block_context.block_info().block_number.0;
+        ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            block_context,
+            None,
+            (Felt252::from(block_number - 10), Felt252::from(66_u64)),
+        )
+    }
+
+    fn write(key: u64, value: u64) -> RecordedStorageWrite {
+        RecordedStorageWrite {
+            contract_address: Felt252::from(0x100),
+            key: Felt252::from(key),
+            old_value: Felt252::ZERO,
+            new_value: Felt252::from(value),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_diff_runs_storage_write(block_context: BlockContext) {
+        let run_a = execution_helper(&block_context);
+        let run_b = execution_helper(&block_context);
+        assert!(diff_runs(&run_a, &run_b).await.is_empty());
+
+        run_a.execution_helper.write().await.storage_writes.extend([write(1, 10), write(2, 20), write(3, 30)]);
+        run_b.execution_helper.write().await.storage_writes.extend([write(1, 10), write(2, 21), write(3, 30)]);
+
+        let diff = diff_runs(&run_a, &run_b).await;
+        assert_eq!(
+            diff.first_divergence,
+            Some(RunDivergence::StorageWrite { index: 1, a: Some(write(2, 20)), b: Some(write(2, 21)) })
+        );
+        let report = diff.first_divergence.unwrap().to_string();
+        assert!(report.starts_with("storage write #1 (contract 0x100, key 0x2)"), "{report}");
+    }
+
+    /// A helper reading `values` as the storage of contract 0x100, by key.
+    fn seeded_execution_helper(block_context: &BlockContext, values: &[(u64, u64)]) -> ExecutionHelperWrapper<PCS> {
+        let storage_snapshot = HashMap::from([(
+            ContractAddress(PatriciaKey::try_from(Felt252::from(0x100)).unwrap()),
+            values.iter().map(|(key, value)| (Felt252::from(*key), Felt252::from(*value))).collect(),
+        )]);
+        execution_helper(block_context).with_storage_snapshot(storage_snapshot)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_replay_transcript(block_context: BlockContext) {
+        let contract_address = Felt252::from(0x100);
+        let mut recorded_run = seeded_execution_helper(&block_context, &[(1, 10), (2, 20)]);
+
+        // Reads of keys 1 and 2, an event and a write of key 2 followed by a read of its new value
+        let mut transcript = SyscallTranscript::default();
+        for key in [Felt252::ONE, Felt252::TWO] {
+            let value = recorded_run.read_storage_for_address(contract_address, key).await.unwrap();
+            transcript.record(SyscallSelector::StorageRead, format!("key={}", key.to_hex_string()));
+            transcript.record_storage_access(StorageAccess::Read { contract_address, key, value });
+        }
+        transcript.record(SyscallSelector::EmitEvent, "0 keys, 0 data felts".to_string());
+        transcript.record(SyscallSelector::StorageWrite, "key=0x2".to_string());
+        transcript.record_storage_access(StorageAccess::Write { contract_address, key: Felt252::TWO });
+        transcript.record(SyscallSelector::StorageRead, "key=0x2".to_string());
+        let storage_access = StorageAccess::Read { contract_address, key: Felt252::TWO, value: Felt252::from(21) };
+        transcript.record_storage_access(storage_access);
+
+        let mut same_state = seeded_execution_helper(&block_context, &[(1, 10), (2, 20)]);
+        assert_eq!(replay_transcript(&transcript, &mut same_state).await.unwrap(), None);
+
+        let mut other_state = seeded_execution_helper(&block_context, &[(1, 10), (2, 22)]);
+        let divergence = replay_transcript(&transcript, &mut other_state).await.unwrap();
+        assert_eq!(
+            divergence,
+            Some(ReplayDivergence {
+                index: 1,
+                contract_address,
+                key: Felt252::TWO,
+                recorded: Felt252::from(20),
+                replayed: Felt252::from(22),
+            })
+        );
+        assert_eq!(
+            divergence.unwrap().to_string(),
+            "storage read #1 (contract 0x100, key 0x2): recorded 0x14, replayed 0x16"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_diff_runs_accessed_storage_keys(block_context: BlockContext) {
+        let run_a = execution_helper(&block_context);
+        let run_b = execution_helper(&block_context);
+        let contract_address = Felt252::from(0x100);
+        run_a
+            .execution_helper
+            .write()
+            .await
+            .accessed_storage_keys
+            .insert(contract_address, HashSet::from([Felt252::from(1), Felt252::from(2)]));
+        run_b
+            .execution_helper
+            .write()
+            .await
+            .accessed_storage_keys
+            .insert(contract_address, HashSet::from([Felt252::from(1)]));
+
+        assert_eq!(
+            diff_runs(&run_a, &run_b).await.first_divergence,
+            Some(RunDivergence::AccessedStorageKey { contract_address, key: Felt252::from(2), accessed_by_a: true })
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::rc::Rc;
+use std::{fs, path};
+
+use blockifier::bouncer::BouncerConfig;
+use blockifier::context::{BlockContext, ChainInfo, FeeTokenAddresses};
+use cairo_vm::Felt252;
+use serde::{Deserialize, Serialize};
+use starknet_api::block::{BlockNumber, BlockTimestamp};
+
+use crate::config::StarknetGeneralConfig;
+use crate::error::SnOsError;
+use crate::execution::helper::{ContractStorageMap, ExecutionHelperWrapper};
+use crate::io::input::StarknetOsInput;
+use crate::starknet::starknet_storage::PerContractStorage;
+
+/// The inputs an execution helper was created with, dumped to a single JSON file to reproduce a
+/// run, see `ExecutionHelperWrapper::export_input`.
+///
+/// The block context is reduced to the general config and the block number and timestamp. The
+/// execution infos of the transactions cannot be deserialized and are not part of the bundle: the
+/// transactions themselves are in `os_input`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OsRunInput {
+    pub general_config: StarknetGeneralConfig,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub old_block_number_and_hash: (Felt252, Felt252),
+    /// Storage values installed with `with_storage_snapshot`, by contract address.
+    pub storage_snapshot: Option<HashMap<Felt252, HashMap<Felt252, Felt252>>>,
+    /// Classes, class hashes of the contracts and transactions of the block.
+    pub os_input: Option<StarknetOsInput>,
+}
+
+impl OsRunInput {
+    pub fn load(path: &path::Path) -> Result<Self, SnOsError> {
+        let raw_input = fs::read_to_string(path)?;
+        let input = serde_json::from_str(&raw_input)?;
+
+        Ok(input)
+    }
+
+    pub fn dump(&self, path: &path::Path) -> Result<(), SnOsError> {
+        fs::File::create(path)?.write_all(&serde_json::to_vec(&self)?)?;
+
+        Ok(())
+    }
+
+    pub fn block_context(&self) -> BlockContext {
+        let empty_block_context = self.general_config.empty_block_context();
+
+        let mut block_info = empty_block_context.block_info().clone();
+        block_info.block_number = BlockNumber(self.block_number);
+        block_info.block_timestamp = BlockTimestamp(self.block_timestamp);
+
+        let os_config = &self.general_config.starknet_os_config;
+        let chain_info = ChainInfo {
+            chain_id: os_config.chain_id.clone(),
+            fee_token_addresses: FeeTokenAddresses {
+                eth_fee_token_address: os_config.fee_token_address,
+                strk_fee_token_address: os_config.deprecated_fee_token_address,
+            },
+        };
+
+        BlockContext::new(
+            block_info,
+            chain_info,
+            empty_block_context.versioned_constants().clone(),
+            BouncerConfig::max(),
+        )
+    }
+
+    /// Creates an execution helper from the bundle. Its contract storage is empty: the reads are
+    /// served by the storage snapshot.
+    pub fn into_execution_helper<PCS>(self) -> ExecutionHelperWrapper<PCS>
+    where
+        PCS: PerContractStorage + 'static,
+    {
+        let block_context = self.block_context();
+        let storage_snapshot = self.storage_snapshot.map(|storage_snapshot| {
+            storage_snapshot
+                .into_iter()
+                .flat_map(|(address, values)| values.into_iter().map(move |(key, value)| ((address, key), value)))
+                .collect()
+        });
+
+        let execution_helper = ExecutionHelperWrapper::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            self.os_input.map(Rc::new),
+            self.old_block_number_and_hash,
+        );
+        execution_helper
+            .execution_helper
+            .try_write()
+            .expect("the execution helper was just created")
+            .storage_snapshot = storage_snapshot;
+        execution_helper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_api::core::{ContractAddress, PatriciaKey};
+    use starknet_os_types::chain_id::chain_id_from_felt;
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::io::InternalTransaction;
+    use crate::starknet::starknet_storage::{CommitmentInfo, OsSingleStarknetStorage};
+    use crate::storage::dict_storage::DictStorage;
+
+    #[allow(clippy::upper_case_acronyms)]
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    fn os_input() -> StarknetOsInput {
+        StarknetOsInput {
+            contract_state_commitment_info: CommitmentInfo::default(),
+            contract_class_commitment_info: CommitmentInfo::default(),
+            deprecated_compiled_classes: HashMap::new(),
+            compiled_classes: HashMap::new(),
+            compiled_class_visited_pcs: HashMap::new(),
+            contracts: HashMap::new(),
+            contract_address_to_class_hash: HashMap::from([(Felt252::from(0x100), Felt252::from(0x1234))]),
+            class_hash_to_compiled_class_hash: HashMap::from([(Felt252::from(0x1234), Felt252::from(0x5678))]),
+            general_config: StarknetGeneralConfig::default(),
+            transactions: vec![InternalTransaction { hash_value: Felt252::from(0xabc), ..Default::default() }],
+            declared_class_hash_to_component_hashes: HashMap::new(),
+            new_block_hash: Felt252::from(2),
+            prev_block_hash: Felt252::ONE,
+            full_output: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_input_round_trip() {
+        let block_context = BlockContext::create_for_account_testing();
+        let contract = Felt252::from(0x100);
+        let storage_snapshot = HashMap::from([(
+            ContractAddress(PatriciaKey::try_from(contract).unwrap()),
+            HashMap::from([(Felt252::ONE, Felt252::from(10))]),
+        )]);
+        let execution_helper = ExecutionHelperWrapper::<PCS>::new(
+            ContractStorageMap::default(),
+            vec![],
+            &block_context,
+            Some(Rc::new(os_input())),
+            (Felt252::from(10), Felt252::from(66)),
+        )
+        .with_storage_snapshot(storage_snapshot);
+
+        let input = execution_helper.export_input().await.unwrap();
+        assert_eq!(input.block_number, block_context.block_info().block_number.0);
+        assert_eq!(
+            input.storage_snapshot,
+            Some(HashMap::from([(contract, HashMap::from([(Felt252::ONE, Felt252::from(10))]))]))
+        );
+
+        let json = serde_json::to_string(&input).unwrap();
+        let mut imported = serde_json::from_str::<OsRunInput>(&json).unwrap().into_execution_helper::<PCS>();
+
+        let reexported = imported.export_input().await.unwrap();
+        assert_eq!(serde_json::to_value(&reexported).unwrap(), serde_json::to_value(&input).unwrap());
+        assert_eq!(
+            chain_id_from_felt(imported.execution_helper.read().await.chain_id),
+            block_context.chain_info().chain_id
+        );
+        assert_eq!(imported.class_hash_of(&contract).await, Some(Felt252::from(0x1234)));
+        assert_eq!(imported.read_storage_for_address(contract, Felt252::ONE).await.unwrap(), Felt252::from(10));
+    }
+}
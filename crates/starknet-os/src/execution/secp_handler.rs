@@ -1,4 +1,5 @@
 use std::cell::OnceCell;
+use std::collections::HashSet;
 use std::marker::PhantomData;
 
 use ark_ec::short_weierstrass::SWCurveConfig;
@@ -23,6 +24,106 @@ use crate::starknet::starknet_storage::PerContractStorage;
 pub struct SecpSyscallProcessor<C: SWCurveConfig> {
     processor: SecpHintProcessor<C>,
     segment: OnceCell<Relocatable>,
+    // Number of points allocated so far, and the ids of the ones released since
+    n_points: usize,
+    freed_points: HashSet<usize>,
+    /// Maximum number of points that are not freed, unbounded if `None`.
+    pub max_live_points: Option<usize>,
+    /// Frees points once their coordinates are read by `get_xy`.
+    pub free_after_get_xy: bool,
+}
+
+impl<C: SWCurveConfig> SecpSyscallProcessor<C> {
+    /// Converts a point handle returned to the contract into the id of the point in the hint
+    /// processor. Handles are pointers in the segment of this curve, any other value is rejected.
+    fn point_id(&self, handle: Relocatable) -> SyscallResult<usize> {
+        let segment = self.segment.get().filter(|segment| segment.segment_index == handle.segment_index);
+        match segment {
+            Some(segment)
+                if handle.offset >= segment.offset
+                    && (handle.offset - segment.offset) % EcCoordinate::cairo_size() == 0 =>
+            {
+                let point_id = (handle.offset - segment.offset) / EcCoordinate::cairo_size();
+                if self.freed_points.contains(&point_id) {
+                    return Err(SyscallExecutionError::InvalidSyscallInput {
+                        input: Felt252::from(handle.offset),
+                        info: "Secp point was freed".to_string(),
+                    });
+                }
+                Ok(point_id)
+            }
+            _ => Err(SyscallExecutionError::InvalidSyscallInput {
+                input: Felt252::from(handle.offset),
+                info: "Invalid Secp point ID".to_string(),
+            }),
+        }
+    }
+
+    /// Number of points allocated and not freed yet.
+    pub fn live_points(&self) -> usize {
+        self.n_points - self.freed_points.len()
+    }
+
+    /// Fails if allocating one more point would exceed `max_live_points`.
+    fn check_point_limit(&self) -> SyscallResult<()> {
+        match self.max_live_points {
+            Some(limit) if self.live_points() >= limit => Err(SyscallExecutionError::TooManySecpPoints { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the handle of the point that the hint processor just allocated with id `point_id`.
+    fn register_point(&mut self, vm: &mut VirtualMachine, point_id: usize) -> SyscallResult<Relocatable> {
+        self.n_points += 1;
+        let segment = self.segment.get_or_init(|| vm.add_memory_segment());
+        Ok((*segment + point_id * EcCoordinate::cairo_size())?)
+    }
+
+    /// Drops every point, keeping `max_live_points` and `free_after_get_xy`. Points are allocated
+    /// in a new segment afterwards, so the handles returned so far are rejected.
+    pub fn reset(&mut self) {
+        *self = Self {
+            max_live_points: self.max_live_points,
+            free_after_get_xy: self.free_after_get_xy,
+            ..Default::default()
+        };
+    }
+
+    /// Releases the point designated by `handle`, which cannot be used afterwards.
+    ///
+    /// The hint processor keeps the coordinates of every point, freeing points lets long runs
+    /// stay under `max_live_points`.
+    pub fn free_point(&mut self, handle: Relocatable) -> SyscallResult<()> {
+        let point_id = self.point_id(handle)?;
+        self.freed_points.insert(point_id);
+        Ok(())
+    }
+}
+
+impl<C: SWCurveConfig> SecpSyscallProcessor<C>
+where
+    C::BaseField: PrimeField,
+{
+    fn new_point(
+        &mut self,
+        vm: &mut VirtualMachine,
+        coordinates: EcPointCoordinates,
+    ) -> SyscallResult<Option<Relocatable>> {
+        self.check_point_limit()?;
+        let res = self.processor.secp_new(coordinates)?;
+        res.optional_ec_point_id.map(|point_id| self.register_point(vm, point_id)).transpose()
+    }
+
+    fn get_xy(&mut self, handle: Relocatable) -> SyscallResult<EcPointCoordinates> {
+        let point_id = self.point_id(handle)?;
+        let res = self
+            .processor
+            .secp_get_xy(blockifier::execution::syscalls::secp::SecpGetXyRequest { ec_point_id: point_id.into() })?;
+        if self.free_after_get_xy {
+            self.freed_points.insert(point_id);
+        }
+        Ok(res)
+    }
 }
 
 /// This trait is private and not callable outside this module.
@@ -97,15 +198,8 @@ where
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
         let secp_handler = &mut <ExecutionHelper<PCS> as GetSecpSyscallHandler<C>>::get_secp_handler(&mut eh_ref);
 
-        let res = secp_handler.processor.secp_new(request)?;
-        if let Some(ec_point) = res.optional_ec_point_id {
-            let segment = secp_handler.segment.get_or_init(|| vm.add_memory_segment());
-            return Ok(SecpOptionalEcPointResponse {
-                optional_ec_point_id: Some((*segment + ec_point * EcCoordinate::cairo_size())?),
-            });
-        }
-
-        Ok(SecpOptionalEcPointResponse { optional_ec_point_id: None })
+        let optional_ec_point_id = secp_handler.new_point(vm, request)?;
+        Ok(SecpOptionalEcPointResponse { optional_ec_point_id })
     }
     fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
         match response.optional_ec_point_id {
@@ -161,15 +255,12 @@ where
     ) -> SyscallResult<Self::Response> {
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
         let secp_handler = &mut <ExecutionHelper<PCS> as GetSecpSyscallHandler<C>>::get_secp_handler(&mut eh_ref);
+        secp_handler.check_point_limit()?;
         let res = secp_handler.processor.secp_get_point_from_x(request)?;
-        if let Some(ec_point) = res.optional_ec_point_id {
-            let segment = secp_handler.segment.get_or_init(|| vm.add_memory_segment());
-            return Ok(SecpOptionalEcPointResponse {
-                optional_ec_point_id: Some((*segment + ec_point * EcCoordinate::cairo_size())?),
-            });
-        }
+        let optional_ec_point_id =
+            res.optional_ec_point_id.map(|point_id| secp_handler.register_point(vm, point_id)).transpose()?;
 
-        Ok(SecpOptionalEcPointResponse { optional_ec_point_id: None })
+        Ok(SecpOptionalEcPointResponse { optional_ec_point_id })
     }
 
     fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
@@ -224,7 +315,7 @@ where
 
     async fn execute(
         request: Self::Request,
-        _vm: &mut VirtualMachine,
+        vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response>
@@ -233,15 +324,14 @@ where
     {
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
         let secp_handler = &mut <ExecutionHelper<PCS> as GetSecpSyscallHandler<C>>::get_secp_handler(&mut eh_ref);
-        let offset = request.ec_point_id;
-        let segment = secp_handler.segment.get().unwrap();
         let request = blockifier::execution::syscalls::secp::SecpMulRequest {
-            ec_point_id: (offset.offset / EcCoordinate::cairo_size()).into(),
+            ec_point_id: secp_handler.point_id(request.ec_point_id)?.into(),
             multiplier: request.multiplier,
         };
+        secp_handler.check_point_limit()?;
         let res = secp_handler.processor.secp_mul(request)?;
 
-        Ok(SecpOpResponse { ec_point_id: (*segment + res.ec_point_id * EcCoordinate::cairo_size())? })
+        Ok(SecpOpResponse { ec_point_id: secp_handler.register_point(vm, res.ec_point_id)? })
     }
 
     fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
@@ -279,7 +369,7 @@ where
 
     async fn execute(
         request: Self::Request,
-        _vm: &mut VirtualMachine,
+        vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response>
@@ -289,13 +379,13 @@ where
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
         let secp_handler = &mut <ExecutionHelper<PCS> as GetSecpSyscallHandler<C>>::get_secp_handler(&mut eh_ref);
         let request = blockifier::execution::syscalls::secp::SecpAddRequest {
-            lhs_id: (request.lhs_id.offset / EcCoordinate::cairo_size()).into(),
-            rhs_id: (request.rhs_id.offset / EcCoordinate::cairo_size()).into(),
+            lhs_id: secp_handler.point_id(request.lhs_id)?.into(),
+            rhs_id: secp_handler.point_id(request.rhs_id)?.into(),
         };
+        secp_handler.check_point_limit()?;
         let res = secp_handler.processor.secp_add(request)?;
-        let segment = secp_handler.segment.get().unwrap();
 
-        Ok(SecpOpResponse { ec_point_id: (*segment + res.ec_point_id * EcCoordinate::cairo_size())? })
+        Ok(SecpOpResponse { ec_point_id: secp_handler.register_point(vm, res.ec_point_id)? })
     }
 
     fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
@@ -337,14 +427,7 @@ where
     {
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
         let secp_handler = &mut <ExecutionHelper<PCS> as GetSecpSyscallHandler<C>>::get_secp_handler(&mut eh_ref);
-        let offset = request.ec_point_id;
-        let _ = secp_handler.segment.get().unwrap();
-        let request = blockifier::execution::syscalls::secp::SecpGetXyRequest {
-            ec_point_id: (offset.offset / EcCoordinate::cairo_size()).into(),
-        };
-
-        let res = secp_handler.processor.secp_get_xy(request)?;
-        Ok(res)
+        secp_handler.get_xy(request.ec_point_id)
     }
     fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
         pub fn write_u256(vm: &mut VirtualMachine, ptr: &mut Relocatable, value: BigUint) -> Result<(), MemoryError> {
@@ -368,10 +451,14 @@ mod tests {
         SecpMulRequest, SecpOpRespone, SecpOptionalEcPointResponse,
     };
     use blockifier::execution::syscalls::SyscallResult;
+    use cairo_vm::vm::vm_core::VirtualMachine;
     use num_bigint::BigUint;
     use num_traits::{FromPrimitive, Num};
     use rstest::rstest;
 
+    use super::SecpSyscallProcessor;
+    use crate::execution::syscall_handler_utils::SyscallExecutionError;
+
     fn parse_hex(hex_str: &str) -> BigUint {
         let trimmed_hex_str = hex_str.trim_start_matches("0x");
         BigUint::from_str_radix(trimmed_hex_str, 16).unwrap()
@@ -517,4 +604,32 @@ mod tests {
         let response = processor.secp_new(request);
         assert!(response.is_err());
     }
+
+    #[test]
+    fn test_secp_point_limit_and_free() {
+        let mut vm = VirtualMachine::new(false);
+        let mut secp_handler =
+            SecpSyscallProcessor::<ark_secp256k1::Config> { max_live_points: Some(3), ..Default::default() };
+        let point = || create_point(parse_hex(K1_X_POINT), parse_hex(K1_Y_POINT));
+
+        let handles: Vec<_> = (0..3).map(|_| secp_handler.new_point(&mut vm, point()).unwrap().unwrap()).collect();
+        assert_eq!(secp_handler.live_points(), 3);
+        assert!(matches!(
+            secp_handler.new_point(&mut vm, point()),
+            Err(SyscallExecutionError::TooManySecpPoints { limit: 3 })
+        ));
+
+        secp_handler.free_point(handles[0]).unwrap();
+        secp_handler.free_point(handles[1]).unwrap();
+        assert_eq!(secp_handler.live_points(), 1);
+        assert!(secp_handler.free_point(handles[0]).is_err());
+        assert!(secp_handler.get_xy(handles[1]).is_err());
+
+        secp_handler.free_after_get_xy = true;
+        let handle = secp_handler.new_point(&mut vm, point()).unwrap().unwrap();
+        assert_eq!(secp_handler.live_points(), 2);
+        assert_eq!(secp_handler.get_xy(handle).unwrap(), point());
+        assert_eq!(secp_handler.live_points(), 1);
+        assert!(secp_handler.get_xy(handle).is_err());
+    }
 }
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::vm_core::VirtualMachine;
+
+/// Memory segments handed out before allocating new ones: segments reserved ahead of time and
+/// scratch segments returned unused.
+///
+/// The Cairo VM cannot free segments and its memory is write-once, so a segment can only be
+/// recycled while nothing was written to it, e.g. the segment of an empty array, which nothing
+/// reads from.
+#[derive(Debug, Default)]
+pub struct SegmentPool {
+    segments: VecDeque<Relocatable>,
+}
+
+impl SegmentPool {
+    /// Allocates `n` segments in one go.
+    pub fn reserve(&mut self, vm: &mut VirtualMachine, n: usize) {
+        self.segments.reserve(n);
+        for _ in 0..n {
+            self.segments.push_back(vm.add_memory_segment());
+        }
+    }
+
+    /// Returns a segment of the pool, or a new segment if the pool is empty.
+    pub fn allocate(&mut self, vm: &mut VirtualMachine) -> Relocatable {
+        self.segments.pop_front().unwrap_or_else(|| vm.add_memory_segment())
+    }
+
+    /// Returns `segment` to the pool. Fails if `segment` does not point to the start of a segment,
+    /// is already in the pool or was written to.
+    pub fn release(&mut self, vm: &VirtualMachine, segment: Relocatable) -> Result<(), HintError> {
+        let is_segment_start = segment.offset == 0
+            && usize::try_from(segment.segment_index).is_ok_and(|index| index < vm.segments.num_segments());
+        let reason = if !is_segment_start {
+            Some("not the start of a segment")
+        } else if self.segments.contains(&segment) {
+            Some("already released")
+        } else if vm.get_maybe(&segment).is_some() {
+            Some("the segment was written to")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => {
+                Err(HintError::CustomHint(format!("Cannot recycle segment {}: {}", segment, reason).into_boxed_str()))
+            }
+            None => {
+                self.segments.push_back(segment);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Relocatable> {
+        self.segments.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cairo_vm::Felt252;
+
+    use super::*;
+
+    #[test]
+    fn test_segment_pool() {
+        let mut vm = VirtualMachine::new(false);
+        let mut pool = SegmentPool::default();
+
+        // An empty pool allocates new segments
+        let first = pool.allocate(&mut vm);
+        let second = pool.allocate(&mut vm);
+        assert_ne!(first, second);
+        assert_eq!(vm.segments.num_segments(), 2);
+
+        // Unused segments are reused
+        pool.release(&vm, first).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.allocate(&mut vm), first);
+        assert!(pool.is_empty());
+        assert_eq!(vm.segments.num_segments(), 2);
+
+        // Segments holding data cannot be recycled
+        vm.insert_value(second, Felt252::ONE).unwrap();
+        let error = pool.release(&vm, second).unwrap_err();
+        assert!(matches!(error, HintError::CustomHint(message) if message.ends_with("the segment was written to")));
+        assert!(pool.release(&vm, (first + 1usize).unwrap()).is_err());
+
+        pool.release(&vm, first).unwrap();
+        assert!(pool.release(&vm, first).is_err());
+
+        // Reserved segments are handed out first
+        pool.reserve(&mut vm, 2);
+        let reserved: Vec<_> = pool.iter().copied().collect();
+        assert_eq!(pool.allocate(&mut vm), first);
+        assert_eq!(pool.allocate(&mut vm), reserved[1]);
+        assert_eq!(pool.allocate(&mut vm), reserved[2]);
+        assert_eq!(pool.allocate(&mut vm).segment_index, 4);
+    }
+}
@@ -1,33 +1,78 @@
+use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
 
+use blockifier::execution::call_info::CallInfo;
+use blockifier::execution::contract_class::ContractClass;
 use blockifier::execution::execution_utils::ReadOnlySegments;
+use blockifier::transaction::objects::TransactionInfo;
 use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
 use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::runners::builtin_runner::BuiltinRunner;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
+use starknet_api::data_availability::DataAvailabilityMode;
+use starknet_api::deprecated_contract_class::EntryPointType;
 use tokio::sync::RwLock;
 
-use super::helper::ExecutionHelperWrapper;
+use super::helper::{
+    ExecutionHelper, ExecutionHelperWrapper, L1ToL2Message, L2ToL1Message, OrderedEvent, RecordedEvent,
+    StarknetVersion, StorageAccess,
+};
 use crate::cairo_types::new_syscalls::{self};
+use crate::crypto::hasher::Hasher;
 use crate::execution::constants::{
-    BLOCK_HASH_CONTRACT_ADDRESS, CALL_CONTRACT_GAS_COST, DEPLOY_GAS_COST, EMIT_EVENT_GAS_COST, GET_BLOCK_HASH_GAS_COST,
-    GET_EXECUTION_INFO_GAS_COST, INVALID_INPUT_LENGTH_ERROR, KECCAK_FULL_RATE_IN_U64S, KECCAK_GAS_COST,
-    KECCAK_ROUND_COST_GAS_COST, LIBRARY_CALL_GAS_COST, REPLACE_CLASS_GAS_COST, SECP256K1_ADD_GAS_COST,
-    SECP256K1_GET_POINT_FROM_X_GAS_COST, SECP256K1_GET_XY_GAS_COST, SECP256K1_MUL_GAS_COST, SECP256K1_NEW_GAS_COST,
-    SECP256R1_ADD_GAS_COST, SECP256R1_GET_POINT_FROM_X_GAS_COST, SECP256R1_GET_XY_GAS_COST, SECP256R1_MUL_GAS_COST,
-    SECP256R1_NEW_GAS_COST, SEND_MESSAGE_TO_L1_GAS_COST, SHA256_PROCESS_BLOCK_GAS_COST, STORAGE_READ_GAS_COST,
-    STORAGE_WRITE_GAS_COST,
+    BLOCK_HASH_CONTRACT_ADDRESS, INVALID_INPUT_LENGTH_ERROR, KECCAK_FULL_RATE_IN_U64S, MAX_CALL_DEPTH,
 };
 use crate::execution::secp_handler::{
     SecpAddHandler, SecpGetPointFromXHandler, SecpGetXyHandler, SecpMulHandler, SecpNewHandler,
 };
+use crate::execution::segment_pool::SegmentPool;
+use crate::execution::syscall_handler_backend::SyscallHandlerBackend;
 use crate::execution::syscall_handler_utils::{
-    felt_from_ptr, run_handler, write_felt, write_maybe_relocatable, write_segment, EmptyRequest, EmptyResponse,
-    ReadOnlySegment, SyscallExecutionError, SyscallHandler, SyscallResult, SyscallSelector, WriteResponseResult,
+    felt_from_ptr, read_call_params, read_calldata, read_felt_array, run_handler, unimplemented_syscall, write_felt,
+    write_maybe_relocatable, write_segment, EmptyRequest, EmptyResponse, ReadOnlySegment, SyscallExecutionError,
+    SyscallGasCosts, SyscallHandler, SyscallResult, SyscallSelector, WriteResponseResult,
 };
+use crate::starknet::core::os::os_logger::{BuiltinUsage, SYSCALLS_LOG_TARGET};
+use crate::starknet::core::os::transaction_hash::create_resource_bounds_list;
 use crate::starknet::starknet_storage::PerContractStorage;
+use crate::utils::execute_coroutine;
+
+/// Observer called with the selector and the pointer of a syscall, see
+/// `OsSyscallHandlerWrapper::set_before_syscall`.
+pub type SyscallHook = Box<dyn FnMut(SyscallSelector, Relocatable)>;
+
+/// Observers called around each syscall dispatched by the handler.
+#[derive(Default)]
+struct SyscallHooks {
+    before_syscall: Option<SyscallHook>,
+    after_syscall: Option<SyscallHook>,
+}
+
+impl SyscallHooks {
+    fn before_syscall(&mut self, selector: SyscallSelector, syscall_ptr: Relocatable) {
+        if let Some(hook) = self.before_syscall.as_mut() {
+            hook(selector, syscall_ptr);
+        }
+    }
+
+    fn after_syscall(&mut self, selector: SyscallSelector, syscall_ptr: Relocatable) {
+        if let Some(hook) = self.after_syscall.as_mut() {
+            hook(selector, syscall_ptr);
+        }
+    }
+}
+
+impl std::fmt::Debug for SyscallHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyscallHooks")
+            .field("before_syscall", &self.before_syscall.is_some())
+            .field("after_syscall", &self.after_syscall.is_some())
+            .finish()
+    }
+}
 
 /// DeprecatedSyscallHandler implementation for execution of system calls in the StarkNet OS
 #[derive(Debug)]
@@ -38,6 +83,45 @@ where
     pub exec_wrapper: ExecutionHelperWrapper<PCS>,
     pub syscall_ptr: Option<Relocatable>,
     pub segments: ReadOnlySegments,
+    pub gas_costs: SyscallGasCosts,
+    pub max_call_depth: usize,
+    // Fail with the shared "unimplemented syscall" error on the syscalls without a handler
+    strict_syscalls: bool,
+    // Number of contract calls dispatched by syscalls that did not return yet
+    call_depth: usize,
+    // Check that the syscall pointer moves past each executed syscall, see `set_syscall_ptr_validation`
+    validate_syscall_ptr: bool,
+    // End of the response of the syscall executed last
+    last_syscall_end: Option<Relocatable>,
+    // Write the initial builtin pointers in the os_context allocated by `set_syscall_ptr`
+    os_context_prologue: bool,
+    // Observers of the dispatched syscalls, see `set_before_syscall` and `set_after_syscall`
+    hooks: SyscallHooks,
+}
+
+impl<PCS> OsSyscallHandler<PCS>
+where
+    PCS: PerContractStorage,
+{
+    /// Fails if `syscall_ptr` points before the end of the syscall executed last, in its segment.
+    fn check_syscall_ptr_advanced(&self, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        match self.last_syscall_end {
+            Some(last_syscall_end)
+                if self.validate_syscall_ptr
+                    && last_syscall_end.segment_index == syscall_ptr.segment_index
+                    && syscall_ptr.offset < last_syscall_end.offset =>
+            {
+                Err(HintError::AssertionFailed(
+                    format!(
+                        "syscall_ptr {} did not advance past the previous syscall, which ended at {}",
+                        syscall_ptr, last_syscall_end
+                    )
+                    .into_boxed_str(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// OsSyscallHandler is wrapped in Rc<RefCell<_>> in order
@@ -64,17 +148,32 @@ where
     PCS: PerContractStorage + 'static,
 {
     pub fn new(exec_wrapper: ExecutionHelperWrapper<PCS>) -> Self {
+        Self::with_gas_costs(exec_wrapper, SyscallGasCosts::default())
+    }
+
+    /// Same as `new`, charging the syscalls according to `gas_costs`.
+    pub fn with_gas_costs(exec_wrapper: ExecutionHelperWrapper<PCS>, gas_costs: SyscallGasCosts) -> Self {
         Self {
             syscall_handler: Rc::new(RwLock::new(OsSyscallHandler {
                 exec_wrapper,
                 syscall_ptr: None,
                 segments: ReadOnlySegments::default(),
+                gas_costs,
+                max_call_depth: MAX_CALL_DEPTH,
+                strict_syscalls: false,
+                call_depth: 0,
+                validate_syscall_ptr: false,
+                last_syscall_end: None,
+                os_context_prologue: false,
+                hooks: SyscallHooks::default(),
             })),
         }
     }
-    pub async fn set_syscall_ptr(&self, syscall_ptr: Relocatable) {
+    pub async fn set_syscall_ptr(&self, syscall_ptr: Relocatable) -> Result<(), HintError> {
         let mut syscall_handler = self.syscall_handler.write().await;
+        syscall_handler.check_syscall_ptr_advanced(syscall_ptr)?;
         syscall_handler.syscall_ptr = Some(syscall_ptr);
+        Ok(())
     }
 
     pub async fn syscall_ptr(&self) -> Option<Relocatable> {
@@ -82,6 +181,101 @@ where
         syscall_handler.syscall_ptr
     }
 
+    pub async fn set_max_call_depth(&self, max_call_depth: usize) {
+        self.syscall_handler.write().await.max_call_depth = max_call_depth;
+    }
+
+    /// In strict mode, the syscalls that the handler does not implement fail with the
+    /// "unimplemented syscall" error shared by both syscall handlers. The syscalls whose effects
+    /// are applied by the OS itself, like `storage_write`, are implemented as no-ops and still
+    /// succeed. Disabled by default.
+    pub async fn set_strict_syscalls(&self, strict_syscalls: bool) {
+        self.syscall_handler.write().await.strict_syscalls = strict_syscalls;
+    }
+
+    pub async fn strict_syscalls(&self) -> bool {
+        self.syscall_handler.read().await.strict_syscalls
+    }
+
+    /// When enabled, the syscall pointer passed to the next syscall or to `set_syscall_ptr` must
+    /// not point before the end of the response of the syscall executed last, e.g. because a
+    /// handler did not advance it. Disabled by default.
+    pub async fn set_syscall_ptr_validation(&self, validate_syscall_ptr: bool) {
+        self.syscall_handler.write().await.validate_syscall_ptr = validate_syscall_ptr;
+    }
+
+    /// When enabled, the `set_syscall_ptr` hint writes a pointer to the start of each builtin
+    /// segment in the `os_context` segment it allocates, for OS programs that read the builtin
+    /// pointers from the context prologue. Disabled by default: `os_context` is left empty.
+    pub async fn set_os_context_prologue(&self, os_context_prologue: bool) {
+        self.syscall_handler.write().await.os_context_prologue = os_context_prologue;
+    }
+
+    pub async fn os_context_prologue(&self) -> bool {
+        self.syscall_handler.read().await.os_context_prologue
+    }
+
+    /// Calls `hook` before each syscall dispatched by the handler, on top of its regular
+    /// execution, e.g. to time or log syscalls.
+    pub async fn set_before_syscall(&self, hook: impl FnMut(SyscallSelector, Relocatable) + 'static) {
+        self.syscall_handler.write().await.hooks.before_syscall = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` after each syscall dispatched by the handler, whether it succeeded or not.
+    pub async fn set_after_syscall(&self, hook: impl FnMut(SyscallSelector, Relocatable) + 'static) {
+        self.syscall_handler.write().await.hooks.after_syscall = Some(Box::new(hook));
+    }
+
+    /// Number of nested contract calls dispatched by syscalls, see `enter_contract_call`.
+    pub async fn current_call_depth(&self) -> usize {
+        self.syscall_handler.read().await.call_depth
+    }
+
+    /// Accounts for a syscall that dispatches a contract call. Fails when the call would nest
+    /// deeper than `max_call_depth`, so that runaway recursion is reported instead of exhausting
+    /// the resources of the host.
+    pub async fn enter_contract_call(&self) -> Result<(), HintError> {
+        let mut syscall_handler = self.syscall_handler.write().await;
+        if syscall_handler.call_depth >= syscall_handler.max_call_depth {
+            return Err(HintError::CustomHint(
+                format!("Maximal call depth of {} exceeded", syscall_handler.max_call_depth).into_boxed_str(),
+            ));
+        }
+        syscall_handler.call_depth += 1;
+        Ok(())
+    }
+
+    /// Accounts for the return of a call entered with `enter_contract_call`.
+    pub async fn exit_contract_call(&self) -> Result<(), HintError> {
+        let mut syscall_handler = self.syscall_handler.write().await;
+        syscall_handler.call_depth = syscall_handler
+            .call_depth
+            .checked_sub(1)
+            .ok_or(HintError::CustomHint(Box::from("Exiting a contract call that was not entered")))?;
+        Ok(())
+    }
+
+    /// Clears the state that must not leak from one transaction into the next, called by
+    /// `start_tx`:
+    /// - the syscall pointer and the end of the last syscall,
+    /// - the call depth counter,
+    /// - the secp256k1 and secp256r1 points, whose handles become invalid.
+    ///
+    /// The configuration of the handler (gas costs, maximal call depth, strict mode, secp point
+    /// limits) is kept, as well as the read-only segments, which span the whole run, and the state
+    /// of the execution helper that spans the block: storage and its caches, deployed contracts and
+    /// the cache of their addresses, class replacements and the OS logger.
+    pub async fn reset_for_new_transaction(&self) {
+        let mut syscall_handler = self.syscall_handler.write().await;
+        syscall_handler.syscall_ptr = None;
+        syscall_handler.call_depth = 0;
+        syscall_handler.last_syscall_end = None;
+
+        let mut execution_helper = syscall_handler.exec_wrapper.execution_helper.write().await;
+        execution_helper.secp256k1_syscall_processor.reset();
+        execution_helper.secp256r1_syscall_processor.reset();
+    }
+
     pub async fn set_sha256_segment(&self, sha256_segment: Relocatable) {
         let syscall_handler = self.syscall_handler.write().await;
         let mut execution_helper = syscall_handler.exec_wrapper.execution_helper.write().await;
@@ -96,125 +290,372 @@ where
         Ok(())
     }
 
+    /// Executes the syscall located at `syscall_ptr`, which must be a `selector` syscall.
+    async fn run_syscall<SH>(
+        &self,
+        vm: &mut VirtualMachine,
+        syscall_ptr: Relocatable,
+        selector: SyscallSelector,
+    ) -> Result<(), HintError>
+    where
+        SH: SyscallHandler<PCS>,
+    {
+        let mut syscall_handler = self.syscall_handler.write().await;
+        let syscall_handler = &mut *syscall_handler;
+        syscall_handler.check_syscall_ptr_advanced(syscall_ptr)?;
+        let mut ptr = syscall_ptr;
+        read_expected_selector(vm, &mut ptr, selector)?;
+
+        let gas_costs = &syscall_handler.gas_costs;
+        let gas_cost = syscall_gas_cost(gas_costs, selector)?;
+        syscall_handler.hooks.before_syscall(selector, syscall_ptr);
+        let result = run_handler::<SH, PCS>(&mut ptr, vm, &mut syscall_handler.exec_wrapper, gas_cost, gas_costs).await;
+        syscall_handler.hooks.after_syscall(selector, syscall_ptr);
+        result?;
+        syscall_handler.last_syscall_end = Some(ptr);
+        Ok(())
+    }
+
+    /// Executes the `emit_event` syscall located at `syscall_ptr`.
+    pub async fn emit_event(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<EmitEventHandler>(vm, syscall_ptr, SyscallSelector::EmitEvent).await
+    }
+
+    /// Executes the `send_message_to_l1` syscall located at `syscall_ptr`.
+    pub async fn send_message_to_l1(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SendMessageToL1Handler>(vm, syscall_ptr, SyscallSelector::SendMessageToL1).await
+    }
+
+    /// Executes the `library_call` syscall located at `syscall_ptr`.
+    pub async fn library_call(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<LibraryCallHandler>(vm, syscall_ptr, SyscallSelector::LibraryCall).await
+    }
+
+    /// Executes the `deploy` syscall located at `syscall_ptr`.
+    pub async fn deploy(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<DeployHandler>(vm, syscall_ptr, SyscallSelector::Deploy).await
+    }
+
+    /// Executes the `delegate_l1_handler` syscall located at `syscall_ptr`.
+    pub async fn delegate_l1_handler(
+        &self,
+        vm: &mut VirtualMachine,
+        syscall_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        self.run_syscall::<DelegateL1HandlerHandler>(vm, syscall_ptr, SyscallSelector::DelegateL1Handler).await
+    }
+
+    /// Executes the `replace_class` syscall located at `syscall_ptr`.
+    pub async fn replace_class(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<ReplaceClassHandler>(vm, syscall_ptr, SyscallSelector::ReplaceClass).await
+    }
+
+    /// Executes the `get_block_hash` syscall located at `syscall_ptr`.
+    pub async fn get_block_hash(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetBlockHashHandler>(vm, syscall_ptr, SyscallSelector::GetBlockHash).await
+    }
+
+    /// Executes the `get_class_hash_at` syscall located at `syscall_ptr`.
+    pub async fn get_class_hash_at(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetClassHashAtHandler>(vm, syscall_ptr, SyscallSelector::GetClassHashAt).await
+    }
+
+    /// Executes the `get_execution_info` syscall located at `syscall_ptr`.
+    pub async fn get_execution_info(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetExecutionInfoHandler>(vm, syscall_ptr, SyscallSelector::GetExecutionInfo).await
+    }
+
+    /// Executes the `get_block_number` syscall located at `syscall_ptr`.
+    pub async fn get_block_number(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetBlockNumberHandler>(vm, syscall_ptr, SyscallSelector::GetBlockNumber).await
+    }
+
+    /// Executes the `get_caller_address` syscall located at `syscall_ptr`.
+    pub async fn get_caller_address(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetCallerAddressHandler>(vm, syscall_ptr, SyscallSelector::GetCallerAddress).await
+    }
+
+    /// Executes the `get_contract_address` syscall located at `syscall_ptr`.
+    pub async fn get_contract_address(
+        &self,
+        vm: &mut VirtualMachine,
+        syscall_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        self.run_syscall::<GetContractAddressHandler>(vm, syscall_ptr, SyscallSelector::GetContractAddress).await
+    }
+
+    /// Executes the `get_sequencer_address` syscall located at `syscall_ptr`.
+    pub async fn get_sequencer_address(
+        &self,
+        vm: &mut VirtualMachine,
+        syscall_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        self.run_syscall::<GetSequencerAddressHandler>(vm, syscall_ptr, SyscallSelector::GetSequencerAddress).await
+    }
+
+    /// Executes the `get_block_timestamp` syscall located at `syscall_ptr`.
+    pub async fn get_block_timestamp(
+        &self,
+        vm: &mut VirtualMachine,
+        syscall_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        self.run_syscall::<GetBlockTimestampHandler>(vm, syscall_ptr, SyscallSelector::GetBlockTimestamp).await
+    }
+
+    /// Executes the `get_tx_info` syscall located at `syscall_ptr`.
+    ///
+    /// Fails outside of a transaction, e.g. if the hints were run out of order.
+    pub async fn get_tx_info(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetTxInfoHandler>(vm, syscall_ptr, SyscallSelector::GetTxInfo).await
+    }
+
+    /// Executes the `get_tx_signature` syscall located at `syscall_ptr`.
+    pub async fn get_tx_signature(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<GetTxSignatureHandler>(vm, syscall_ptr, SyscallSelector::GetTxSignature).await
+    }
+
+    /// Executes the `keccak` syscall located at `syscall_ptr`.
+    ///
+    /// An input whose length is not a multiple of the keccak rate is rejected with an error
+    /// instead of being reported to the contract as a syscall failure.
+    pub async fn keccak(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        let mut ptr = syscall_ptr;
+        read_expected_selector(vm, &mut ptr, SyscallSelector::Keccak)?;
+
+        // The request follows the gas counter.
+        let input_start = vm.get_relocatable((ptr + 1)?)?;
+        let input_end = vm.get_relocatable((ptr + 2)?)?;
+        let input_len = (input_end - input_start)?;
+        if input_len as u64 % KECCAK_FULL_RATE_IN_U64S != 0 {
+            return Err(SyscallExecutionError::InvalidSyscallInput {
+                input: Felt252::from(input_len),
+                info: format!("Keccak input length must be a multiple of {KECCAK_FULL_RATE_IN_U64S}."),
+            }
+            .into());
+        }
+
+        self.run_syscall::<KeccakHandler>(vm, syscall_ptr, SyscallSelector::Keccak).await
+    }
+
+    /// Executes the `secp256k1_new` syscall located at `syscall_ptr`.
+    ///
+    /// Off-curve points are reported to the contract as `None`, coordinates that do not fit in
+    /// the base field make the syscall fail. Points are allocated in the secp256k1 segment of the
+    /// execution helper so that later syscalls can refer to them by id.
+    pub async fn secp256k1_new(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpNewHandler<ark_secp256k1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256k1New).await
+    }
+
+    /// Executes the `secp256k1_add` syscall located at `syscall_ptr`.
+    pub async fn secp256k1_add(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpAddHandler<ark_secp256k1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256k1Add).await
+    }
+
+    /// Executes the `secp256k1_mul` syscall located at `syscall_ptr`.
+    ///
+    /// The scalar is a full u256, the multiplication is performed in the curve group so it is
+    /// implicitly reduced modulo the curve order.
+    pub async fn secp256k1_mul(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpMulHandler<ark_secp256k1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256k1Mul).await
+    }
+
+    /// Executes the `secp256k1_get_xy` syscall located at `syscall_ptr`.
+    pub async fn secp256k1_get_xy(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpGetXyHandler<ark_secp256k1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256k1GetXy)
+            .await
+    }
+
+    /// Executes the `secp256r1_add` syscall located at `syscall_ptr`.
+    pub async fn secp256r1_add(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpAddHandler<ark_secp256r1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256r1Add).await
+    }
+
+    /// Executes the `secp256r1_get_point_from_x` syscall located at `syscall_ptr`.
+    ///
+    /// Reports `None` to the contract when no point of the curve has this x coordinate.
+    pub async fn secp256r1_get_point_from_x(
+        &self,
+        vm: &mut VirtualMachine,
+        syscall_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        self.run_syscall::<SecpGetPointFromXHandler<ark_secp256r1::Config>>(
+            vm,
+            syscall_ptr,
+            SyscallSelector::Secp256r1GetPointFromX,
+        )
+        .await
+    }
+
+    /// Executes the `secp256r1_get_xy` syscall located at `syscall_ptr`.
+    pub async fn secp256r1_get_xy(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpGetXyHandler<ark_secp256r1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256r1GetXy)
+            .await
+    }
+
+    /// Executes the `secp256r1_mul` syscall located at `syscall_ptr`.
+    pub async fn secp256r1_mul(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpMulHandler<ark_secp256r1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256r1Mul).await
+    }
+
+    /// Executes the `secp256r1_new` syscall located at `syscall_ptr`.
+    ///
+    /// secp256r1 points live in their own segment, handles of secp256k1 points are rejected by
+    /// the secp256r1 syscalls and vice versa.
+    pub async fn secp256r1_new(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.run_syscall::<SecpNewHandler<ark_secp256r1::Config>>(vm, syscall_ptr, SyscallSelector::Secp256r1New).await
+    }
+
     pub async fn execute_syscall(&self, vm: &mut VirtualMachine, syscall_ptr: Relocatable) -> Result<(), HintError> {
         let mut syscall_handler = self.syscall_handler.write().await;
         let ptr = &mut syscall_handler.syscall_ptr.ok_or(HintError::CustomHint(Box::from("syscall_ptr is None")))?;
 
         assert_eq!(*ptr, syscall_ptr);
+        syscall_handler.check_syscall_ptr_advanced(syscall_ptr)?;
 
         let selector = SyscallSelector::try_from(felt_from_ptr(vm, ptr)?)?;
+        tracing::debug!(target: SYSCALLS_LOG_TARGET, selector = ?selector, "dispatching syscall");
 
+        let syscall_handler = &mut *syscall_handler;
+        let gas_costs = &syscall_handler.gas_costs;
+        let gas_cost = syscall_gas_cost(gas_costs, selector)?;
         let ehw = &mut syscall_handler.exec_wrapper;
+        syscall_handler.hooks.before_syscall(selector, syscall_ptr);
 
-        match selector {
+        let result = match selector {
             SyscallSelector::CallContract => {
-                run_handler::<CallContractHandler, PCS>(ptr, vm, ehw, CALL_CONTRACT_GAS_COST).await
+                run_handler::<CallContractHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::DelegateL1Handler => {
+                run_handler::<DelegateL1HandlerHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
-            SyscallSelector::Deploy => run_handler::<DeployHandler, PCS>(ptr, vm, ehw, DEPLOY_GAS_COST).await,
-            SyscallSelector::EmitEvent => run_handler::<EmitEventHandler, PCS>(ptr, vm, ehw, EMIT_EVENT_GAS_COST).await,
+            SyscallSelector::Deploy => run_handler::<DeployHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await,
+            SyscallSelector::EmitEvent => run_handler::<EmitEventHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await,
             SyscallSelector::GetBlockHash => {
-                run_handler::<GetBlockHashHandler, PCS>(ptr, vm, ehw, GET_BLOCK_HASH_GAS_COST).await
+                run_handler::<GetBlockHashHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::LibraryCall => {
-                run_handler::<LibraryCallHandler, PCS>(ptr, vm, ehw, LIBRARY_CALL_GAS_COST).await
+                run_handler::<LibraryCallHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::GetExecutionInfo => {
-                run_handler::<GetExecutionInfoHandler, PCS>(ptr, vm, ehw, GET_EXECUTION_INFO_GAS_COST).await
+                run_handler::<GetExecutionInfoHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetBlockNumber => {
+                run_handler::<GetBlockNumberHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetSequencerAddress => {
+                run_handler::<GetSequencerAddressHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetBlockTimestamp => {
+                run_handler::<GetBlockTimestampHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetCallerAddress => {
+                run_handler::<GetCallerAddressHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetClassHashAt => {
+                run_handler::<GetClassHashAtHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetContractAddress => {
+                run_handler::<GetContractAddressHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
+            }
+            SyscallSelector::GetTxInfo => run_handler::<GetTxInfoHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await,
+            SyscallSelector::GetTxSignature => {
+                run_handler::<GetTxSignatureHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::StorageRead => {
-                run_handler::<StorageReadHandler, PCS>(ptr, vm, ehw, STORAGE_READ_GAS_COST).await
+                run_handler::<StorageReadHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::StorageWrite => {
-                run_handler::<StorageWriteHandler, PCS>(ptr, vm, ehw, STORAGE_WRITE_GAS_COST).await
+                run_handler::<StorageWriteHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::SendMessageToL1 => {
-                run_handler::<SendMessageToL1Handler, PCS>(ptr, vm, ehw, SEND_MESSAGE_TO_L1_GAS_COST).await
+                run_handler::<SendMessageToL1Handler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::ReplaceClass => {
-                run_handler::<ReplaceClassHandler, PCS>(ptr, vm, ehw, REPLACE_CLASS_GAS_COST).await
+                run_handler::<ReplaceClassHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::LibraryCallL1Handler => {
-                run_handler::<LibraryCallHandler, PCS>(ptr, vm, ehw, LIBRARY_CALL_GAS_COST).await
+                run_handler::<LibraryCallHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
-            SyscallSelector::Keccak => run_handler::<KeccakHandler, PCS>(ptr, vm, ehw, KECCAK_GAS_COST).await,
+            SyscallSelector::Keccak => run_handler::<KeccakHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await,
             SyscallSelector::Secp256k1New => {
-                run_handler::<SecpNewHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, SECP256K1_NEW_GAS_COST).await
+                run_handler::<SecpNewHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Secp256k1GetXy => {
-                run_handler::<SecpGetXyHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, SECP256K1_GET_XY_GAS_COST)
-                    .await
+                run_handler::<SecpGetXyHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Secp256k1GetPointFromX => {
-                run_handler::<SecpGetPointFromXHandler<ark_secp256k1::Config>, PCS>(
-                    ptr,
-                    vm,
-                    ehw,
-                    SECP256K1_GET_POINT_FROM_X_GAS_COST,
-                )
-                .await
+                run_handler::<SecpGetPointFromXHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs)
+                    .await
             }
             SyscallSelector::Secp256k1Mul => {
-                run_handler::<SecpMulHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, SECP256K1_MUL_GAS_COST).await
+                run_handler::<SecpMulHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Secp256k1Add => {
-                run_handler::<SecpAddHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, SECP256K1_ADD_GAS_COST).await
+                run_handler::<SecpAddHandler<ark_secp256k1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
 
             SyscallSelector::Secp256r1New => {
-                run_handler::<SecpNewHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, SECP256R1_NEW_GAS_COST).await
+                run_handler::<SecpNewHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Secp256r1GetXy => {
-                run_handler::<SecpGetXyHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, SECP256R1_GET_XY_GAS_COST)
-                    .await
+                run_handler::<SecpGetXyHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Secp256r1GetPointFromX => {
-                run_handler::<SecpGetPointFromXHandler<ark_secp256r1::Config>, PCS>(
-                    ptr,
-                    vm,
-                    ehw,
-                    SECP256R1_GET_POINT_FROM_X_GAS_COST,
-                )
-                .await
+                run_handler::<SecpGetPointFromXHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs)
+                    .await
             }
             SyscallSelector::Secp256r1Mul => {
-                run_handler::<SecpMulHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, SECP256R1_MUL_GAS_COST).await
+                run_handler::<SecpMulHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Secp256r1Add => {
-                run_handler::<SecpAddHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, SECP256R1_ADD_GAS_COST).await
+                run_handler::<SecpAddHandler<ark_secp256r1::Config>, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
             SyscallSelector::Sha256ProcessBlock => {
-                run_handler::<Sha256ProcessBlockHandler, PCS>(ptr, vm, ehw, SHA256_PROCESS_BLOCK_GAS_COST).await
+                run_handler::<Sha256ProcessBlockHandler, PCS>(ptr, vm, ehw, gas_cost, gas_costs).await
             }
 
+            _ if syscall_handler.strict_syscalls => Err(unimplemented_syscall(selector)),
             _ => Err(HintError::CustomHint(format!("Unknown syscall selector: {:?}", selector).into())),
-        }?;
+        };
+        syscall_handler.hooks.after_syscall(selector, syscall_ptr);
+        result?;
 
         syscall_handler.syscall_ptr = Some(*ptr);
+        syscall_handler.last_syscall_end = Some(*ptr);
 
         Ok(())
     }
 }
 struct CallContractHandler;
 
+pub struct CallContractRequest {
+    pub contract_address: Felt252,
+    pub selector: Felt252,
+    pub calldata: Vec<Felt252>,
+}
+
 impl<PCS> SyscallHandler<PCS> for CallContractHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = CallContractRequest;
     type Response = ReadOnlySegment;
 
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<EmptyRequest> {
-        *ptr = (*ptr + new_syscalls::CallContractRequest::cairo_size())?;
-        Ok(EmptyRequest)
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<CallContractRequest> {
+        let contract_address = felt_from_ptr(vm, ptr)?;
+        let (selector, calldata) = read_call_params(vm, ptr)?;
+        Ok(CallContractRequest { contract_address, selector, calldata })
     }
 
     async fn execute(
-        _request: EmptyRequest,
+        request: CallContractRequest,
         vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         remaining_gas: &mut u64,
     ) -> SyscallResult<ReadOnlySegment> {
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        // Popped by the exit hint of the syscall, once the call returned.
+        eh_ref.enter_call_context(request.calldata);
         let result_iter = &mut eh_ref.result_iter;
         let result = result_iter
             .next()
@@ -225,6 +666,7 @@ where
         let retdata = result.retdata.0;
 
         if result.failed {
+            eh_ref.mark_call_failed();
             return Err(SyscallExecutionError::SyscallError { error_data: retdata });
         }
 
@@ -242,8 +684,61 @@ where
     }
 }
 
+/// Replays a call to an L1 handler. The first calldata element is the L1 sender and the rest is
+/// the message payload, see [`ExecutionHelper::consume_l1_handler_message`].
+struct DelegateL1HandlerHandler;
+
+impl<PCS> SyscallHandler<PCS> for DelegateL1HandlerHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = CallContractRequest;
+    type Response = ReadOnlySegment;
+
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<CallContractRequest> {
+        <CallContractHandler as SyscallHandler<PCS>>::read_request(vm, ptr)
+    }
+
+    async fn execute(
+        request: CallContractRequest,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        remaining_gas: &mut u64,
+    ) -> SyscallResult<ReadOnlySegment> {
+        let (from_address, payload) =
+            request.calldata.split_first().ok_or_else(|| SyscallExecutionError::InvalidSyscallInput {
+                input: Felt252::ZERO,
+                info: "L1 handler calldata must start with the L1 sender address".to_string(),
+            })?;
+        let message = L1ToL2Message {
+            from_address: *from_address,
+            to_address: request.contract_address,
+            selector: request.selector,
+            payload: payload.to_vec(),
+        };
+        exec_wrapper.execution_helper.write().await.consume_l1_handler_message(&message)?;
+
+        <CallContractHandler as SyscallHandler<PCS>>::execute(request, vm, exec_wrapper, remaining_gas).await
+    }
+
+    fn write_response(
+        response: ReadOnlySegment,
+        vm: &mut VirtualMachine,
+        ptr: &mut Relocatable,
+    ) -> WriteResponseResult {
+        write_segment(vm, ptr, response)
+    }
+}
+
 pub struct DeployHandler;
 
+pub struct DeployRequest {
+    pub class_hash: Felt252,
+    pub contract_address_salt: Felt252,
+    pub constructor_calldata: Vec<Felt252>,
+    pub deploy_from_zero: bool,
+}
+
 pub struct DeployResponse {
     pub contract_address: Felt252,
     pub constructor_retdata: ReadOnlySegment,
@@ -254,22 +749,81 @@ impl<PCS> SyscallHandler<PCS> for DeployHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = DeployRequest;
     type Response = DeployResponse;
 
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
-        *ptr = (*ptr + new_syscalls::DeployRequest::cairo_size())?;
-        Ok(EmptyRequest)
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        let class_hash = felt_from_ptr(vm, ptr)?;
+        let contract_address_salt = felt_from_ptr(vm, ptr)?;
+        let constructor_calldata = read_calldata(vm, ptr)?;
+        let deploy_from_zero = felt_from_ptr(vm, ptr)?;
+        let deploy_from_zero = match deploy_from_zero {
+            flag if flag == Felt252::ZERO => false,
+            flag if flag == Felt252::ONE => true,
+            flag => {
+                return Err(SyscallExecutionError::InvalidSyscallInput {
+                    input: flag,
+                    info: "The deploy_from_zero flag must be 0 or 1".to_string(),
+                });
+            }
+        };
+        Ok(DeployRequest { class_hash, contract_address_salt, constructor_calldata, deploy_from_zero })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!(
+            "class_hash={}, salt={}",
+            request.class_hash.to_hex_string(),
+            request.contract_address_salt.to_hex_string()
+        )
     }
 
     async fn execute(
-        _request: Self::Request,
+        request: Self::Request,
         vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
         let mut execution_helper = exec_wrapper.execution_helper.write().await;
 
+        // An empty calldata is valid, e.g. for classes without constructor
+        let max_len = execution_helper.max_constructor_calldata_len;
+        if request.constructor_calldata.len() > max_len {
+            return Err(SyscallExecutionError::ConstructorCalldataTooLong {
+                len: request.constructor_calldata.len(),
+                max_len,
+            });
+        }
+
+        let deployer_address = deployer_address_of(&request, current_contract_address(vm, &execution_helper)?);
+        let key = DeployedAddressKey {
+            class_hash: request.class_hash,
+            contract_address_salt: request.contract_address_salt,
+            constructor_calldata_hash: execution_helper.hasher.hash_many(&request.constructor_calldata),
+            deployer_address,
+        };
+        let contract_address = match execution_helper.deployed_address_cache.get(&key) {
+            Some(contract_address) => contract_address,
+            None => {
+                let contract_address = contract_address_from_calldata_hash(
+                    &request,
+                    deployer_address,
+                    key.constructor_calldata_hash,
+                    execution_helper.hasher.as_ref(),
+                );
+                execution_helper.deployed_address_cache.insert(key, contract_address);
+                contract_address
+            }
+        };
+        // Also checked on cache hits: deploying twice with the same parameters must fail
+        if execution_helper.deployed_contracts.contains_key(&contract_address) {
+            // The replayed run went through this deploy as well: skip its result and address so
+            // that the next calls and deploys replay their own if the contract recovers
+            execution_helper.result_iter.next();
+            execution_helper.deployed_contracts_iter.next();
+            return Err(SyscallExecutionError::ContractAddressUnavailable { contract_address });
+        }
+
         let result = execution_helper
             .result_iter
             .next()
@@ -288,15 +842,27 @@ where
 
         let constructor_retdata = ReadOnlySegment { start_ptr, length: retdata.len() };
 
-        let contract_address = execution_helper.deployed_contracts_iter.next().ok_or(HintError::SyscallError(
-            "No more deployed contracts available to replay".to_string().into_boxed_str(),
-        ))?;
+        let replayed_contract_address = execution_helper
+            .deployed_contracts_iter
+            .next()
+            .ok_or(SyscallExecutionError::InternalError(Box::from("No more deployed contracts available to replay")))?;
+        if replayed_contract_address != contract_address {
+            return Err(SyscallExecutionError::InternalError(
+                format!(
+                    "Deployed contract address mismatch: computed {}, replayed {}",
+                    contract_address.to_hex_string(),
+                    replayed_contract_address.to_hex_string()
+                )
+                .into(),
+            ));
+        }
+        execution_helper.deployed_contracts.insert(contract_address, request.class_hash);
 
-        let need_retdata_hack = if let Some(os_input) = execution_helper.os_input.as_ref() {
-            let class_hash = os_input
-                .contract_address_to_class_hash
-                .get(&contract_address)
-                .expect("No class_hash for contract_address");
+        let need_retdata_hack = if let Some(contract_class) = execution_helper.registered_class(request.class_hash)? {
+            n_constructors(&contract_class) == 0
+        } else if let Some(os_input) = execution_helper.os_input.as_ref() {
+            let class_hash =
+                &execution_helper.class_hash_of(&contract_address).expect("No class_hash for contract_address");
             let num_constructors =
                 if let Some(compiled_class_hash) = os_input.class_hash_to_compiled_class_hash.get(class_hash) {
                     let casm = os_input.compiled_classes.get(compiled_class_hash).expect("No CASM");
@@ -313,7 +879,7 @@ where
                         .get_starknet_api_contract_class()
                         .expect("couldn't get starknet api class")
                         .entry_points_by_type
-                        .get(&starknet_api::deprecated_contract_class::EntryPointType::Constructor)
+                        .get(&EntryPointType::Constructor)
                         .expect("should have constructor list")
                         .len();
                     num_constructors
@@ -349,26 +915,195 @@ where
     }
 }
 
+fn n_constructors(contract_class: &ContractClass) -> usize {
+    let constructors = match contract_class {
+        ContractClass::V0(contract_class) => {
+            contract_class.entry_points_by_type.get(&EntryPointType::Constructor).map(Vec::len)
+        }
+        ContractClass::V1(contract_class) => {
+            contract_class.entry_points_by_type.get(&EntryPointType::Constructor).map(Vec::len)
+        }
+    };
+    constructors.unwrap_or_default()
+}
+
+/// Prefix of the preimage of contract addresses.
+const CONTRACT_ADDRESS_PREFIX: &[u8] = b"STARKNET_CONTRACT_ADDRESS";
+
+/// Computes the address of a contract deployed by `current_contract_address`, see
+/// `calculate_contract_address` in the Starknet API:
+/// `h(PREFIX, deployer_address, salt, class_hash, h(constructor_calldata)) mod 2**251 - 256`.
+/// The deployer is zero if the request deploys from zero, `current_contract_address` otherwise.
+pub fn calculate_deployed_contract_address(
+    request: &DeployRequest,
+    current_contract_address: Felt252,
+    hasher: &dyn Hasher,
+) -> Felt252 {
+    let deployer_address = deployer_address_of(request, current_contract_address);
+    let constructor_calldata_hash = hasher.hash_many(&request.constructor_calldata);
+    contract_address_from_calldata_hash(request, deployer_address, constructor_calldata_hash, hasher)
+}
+
+/// Same as `calculate_deployed_contract_address` with the deployer already resolved, see
+/// `deployer_address_of`, and the constructor calldata already hashed.
+fn contract_address_from_calldata_hash(
+    request: &DeployRequest,
+    deployer_address: Felt252,
+    constructor_calldata_hash: Felt252,
+    hasher: &dyn Hasher,
+) -> Felt252 {
+    let hash = hasher.hash_many(&[
+        Felt252::from_bytes_be_slice(CONTRACT_ADDRESS_PREFIX),
+        deployer_address,
+        request.contract_address_salt,
+        request.class_hash,
+        constructor_calldata_hash,
+    ]);
+
+    let address_bound = (BigUint::from(1u8) << 251) - BigUint::from(256u32);
+    Felt252::from_bytes_be_slice(&(hash.to_biguint() % address_bound).to_bytes_be())
+}
+
+/// The deployer hashed into the address of the contract deployed by `request` from
+/// `current_contract_address`: zero if the request deploys from zero, the current contract
+/// otherwise.
+fn deployer_address_of(request: &DeployRequest, current_contract_address: Felt252) -> Felt252 {
+    match request.deploy_from_zero {
+        true => Felt252::ZERO,
+        false => current_contract_address,
+    }
+}
+
+/// Number of addresses kept by `DeployedAddressCache`.
+const DEPLOYED_ADDRESS_CACHE_SIZE: usize = 256;
+
+/// Parameters that determine the address of a deployed contract. The deployer is the resolved
+/// one, see `deployer_address_of`: zero for deployments from zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct DeployedAddressKey {
+    class_hash: Felt252,
+    contract_address_salt: Felt252,
+    constructor_calldata_hash: Felt252,
+    deployer_address: Felt252,
+}
+
+/// Least recently used cache of the addresses computed by the `deploy` syscall, for factory
+/// contracts deploying many instances with the same parameters. The constructor calldata is part
+/// of the key, so it is still hashed on a cache hit: only the hash of the address preimage is
+/// skipped.
+#[derive(Debug)]
+pub struct DeployedAddressCache {
+    capacity: usize,
+    addresses: HashMap<DeployedAddressKey, Felt252>,
+    // Least recently used key first
+    recency: VecDeque<DeployedAddressKey>,
+    hits: usize,
+}
+
+impl Default for DeployedAddressCache {
+    fn default() -> Self {
+        Self::new(DEPLOYED_ADDRESS_CACHE_SIZE)
+    }
+}
+
+impl DeployedAddressCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, addresses: HashMap::new(), recency: VecDeque::new(), hits: 0 }
+    }
+
+    /// Number of deployments whose address was served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn clear(&mut self) {
+        self.addresses.clear();
+        self.recency.clear();
+    }
+
+    fn get(&mut self, key: &DeployedAddressKey) -> Option<Felt252> {
+        let contract_address = *self.addresses.get(key)?;
+        if let Some(position) = self.recency.iter().position(|recent_key| recent_key == key) {
+            self.recency.remove(position);
+        }
+        self.recency.push_back(*key);
+        self.hits += 1;
+        Some(contract_address)
+    }
+
+    fn insert(&mut self, key: DeployedAddressKey, contract_address: Felt252) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.addresses.len() >= self.capacity {
+            if let Some(least_recent_key) = self.recency.pop_front() {
+                self.addresses.remove(&least_recent_key);
+            }
+        }
+        self.addresses.insert(key, contract_address);
+        self.recency.push_back(key);
+    }
+}
+
 pub struct EmitEventHandler;
 
+pub struct EmitEventRequest {
+    pub keys: Vec<Felt252>,
+    pub data: Vec<Felt252>,
+}
+
 impl<PCS> SyscallHandler<PCS> for EmitEventHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = EmitEventRequest;
     type Response = EmptyResponse;
 
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
-        *ptr = (*ptr + new_syscalls::EmitEventRequest::cairo_size())?;
-        Ok(EmptyRequest)
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        let keys = read_felt_array::<SyscallExecutionError>(vm, ptr)?;
+        let data = read_felt_array::<SyscallExecutionError>(vm, ptr)?;
+        Ok(EmitEventRequest { keys, data })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("{} keys, {} data felts", request.keys.len(), request.data.len())
+    }
+
+    fn variable_gas_cost(request: &Self::Request, gas_costs: &SyscallGasCosts) -> SyscallResult<u64> {
+        Ok(u64::try_from(request.data.len())? * gas_costs.emit_event_data_word)
     }
 
     async fn execute(
-        _request: Self::Request,
-        _vm: &mut VirtualMachine,
-        _exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        request: Self::Request,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
+        let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        let contract_address = current_contract_address(vm, &eh_ref)?;
+        if eh_ref.dry_run {
+            eh_ref.recorded_events.push(RecordedEvent {
+                contract_address,
+                keys: request.keys.clone(),
+                data: request.data.clone(),
+            });
+        }
+
+        if eh_ref.warn_duplicate_events && !eh_ref.call_events.insert((request.keys.clone(), request.data.clone())) {
+            let warning = format!(
+                "Contract {} emitted the same event twice in a call: keys={:?}, data={:?}",
+                contract_address.to_hex_string(),
+                request.keys,
+                request.data
+            );
+            eh_ref.warnings.push(warning);
+        }
+
+        // Events are ordered per transaction, so nested calls share the same counter.
+        let order = eh_ref.emitted_events.len();
+        let mut keys = request.keys;
+        let selector = if eh_ref.split_event_selector && !keys.is_empty() { Some(keys.remove(0)) } else { None };
+        eh_ref.emitted_events.push(OrderedEvent { order, contract_address, selector, keys, data: request.data });
         Ok(crate::execution::syscall_handler_utils::EmptyResponse {})
     }
 
@@ -381,16 +1116,69 @@ where
     }
 }
 
-pub struct GetBlockHashHandler;
-
-pub struct GetBlockHashRequest {
-    pub block_number: Felt252,
-}
-pub struct GetBlockHashResponse {
-    pub block_hash: Felt252,
-}
-
-impl<PCS> SyscallHandler<PCS> for GetBlockHashHandler
+impl<PCS> SyscallHandlerBackend for OsSyscallHandlerWrapper<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    fn set_syscall_ptr(&self, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        execute_coroutine(OsSyscallHandlerWrapper::set_syscall_ptr(self, syscall_ptr))?
+    }
+
+    fn syscall_ptr(&self) -> Result<Option<Relocatable>, HintError> {
+        execute_coroutine(OsSyscallHandlerWrapper::syscall_ptr(self))
+    }
+
+    fn execute(
+        &self,
+        selector: SyscallSelector,
+        syscall_ptr: Relocatable,
+        vm: &mut VirtualMachine,
+    ) -> Result<(), HintError> {
+        // The selector is part of the request, make sure it matches the one we were asked to run.
+        let mut ptr = syscall_ptr;
+        read_expected_selector(vm, &mut ptr, selector)?;
+        execute_coroutine(self.execute_syscall(vm, syscall_ptr))?
+    }
+}
+
+/// Reads the selector at `ptr` and checks that it matches the syscall we are about to execute.
+fn read_expected_selector(
+    vm: &VirtualMachine,
+    ptr: &mut Relocatable,
+    expected_selector: SyscallSelector,
+) -> Result<(), HintError> {
+    let selector = SyscallSelector::try_from(felt_from_ptr(vm, ptr)?)?;
+    if selector != expected_selector {
+        return Err(HintError::CustomHint(
+            format!("Expected a {:?} syscall, got {:?}", expected_selector, selector).into_boxed_str(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the address of the contract executing the current call, read from its execution info.
+fn current_contract_address<PCS>(vm: &VirtualMachine, execution_helper: &ExecutionHelper<PCS>) -> SyscallResult<Felt252>
+where
+    PCS: PerContractStorage,
+{
+    let execution_info_ptr = execution_helper
+        .call_execution_info_ptr
+        .ok_or(SyscallExecutionError::InternalError("No call is being executed".into()))?;
+    let contract_address =
+        vm.get_integer((execution_info_ptr + new_syscalls::ExecutionInfo::contract_address_offset())?)?;
+    Ok(contract_address.into_owned())
+}
+
+pub struct GetBlockHashHandler;
+
+pub struct GetBlockHashRequest {
+    pub block_number: Felt252,
+}
+pub struct GetBlockHashResponse {
+    pub block_hash: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetBlockHashHandler
 where
     PCS: PerContractStorage + 'static,
 {
@@ -403,12 +1191,37 @@ where
         Ok(GetBlockHashRequest { block_number })
     }
 
+    fn summarize(request: &Self::Request) -> String {
+        format!("block_number={}", request.block_number)
+    }
+
     async fn execute(
         request: GetBlockHashRequest,
         _vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
+        // Only blocks older than the last `block_hash_lower_bound` blocks are accessible, the
+        // hashes of more recent blocks resolve to zero.
+        let newest_accessible_block = {
+            let eh_ref = exec_wrapper.execution_helper.read().await;
+            eh_ref.block_info_view().block_number().0.checked_sub(eh_ref.block_hash_lower_bound)
+        };
+        if !newest_accessible_block.is_some_and(|block_number| request.block_number <= Felt252::from(block_number)) {
+            return Ok(GetBlockHashResponse { block_hash: Felt252::ZERO });
+        }
+
+        if let Some(block_hash_provider) = exec_wrapper.execution_helper.read().await.block_hash_provider.as_ref() {
+            let block_number =
+                request.block_number.to_u64().ok_or_else(|| SyscallExecutionError::InvalidSyscallInput {
+                    input: request.block_number,
+                    info: "Block number does not fit in 64 bits".to_string(),
+                })?;
+            let block_hash =
+                block_hash_provider(block_number).ok_or(SyscallExecutionError::BlockHashNotFound { block_number })?;
+            return Ok(GetBlockHashResponse { block_hash });
+        }
+
         // # The syscall handler should not directly read from the storage during the execution of
         // # transactions because the order in which reads and writes occur is not strictly linear.
         // # However, for the "block hash contract," this rule does not apply. This contract is updated
@@ -425,6 +1238,230 @@ where
     }
 }
 
+struct GetClassHashAtHandler;
+
+pub struct GetClassHashAtRequest {
+    pub contract_address: Felt252,
+}
+pub struct GetClassHashAtResponse {
+    pub class_hash: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetClassHashAtHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = GetClassHashAtRequest;
+    type Response = GetClassHashAtResponse;
+
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<GetClassHashAtRequest> {
+        let contract_address = vm.get_integer(*ptr)?.into_owned();
+        *ptr = (*ptr + new_syscalls::GetClassHashAtRequest::cairo_size())?;
+        Ok(GetClassHashAtRequest { contract_address })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("contract_address={}", request.contract_address.to_hex_string())
+    }
+
+    async fn execute(
+        request: GetClassHashAtRequest,
+        _vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let class_hash = exec_wrapper.class_hash_at(request.contract_address).await;
+        Ok(GetClassHashAtResponse { class_hash })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, response.class_hash)?;
+        Ok(())
+    }
+}
+
+struct GetBlockNumberHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetBlockNumberResponse {
+    pub block_number: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetBlockNumberHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = EmptyRequest;
+    type Response = GetBlockNumberResponse;
+
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        Ok(EmptyRequest)
+    }
+
+    async fn execute(
+        _request: Self::Request,
+        _vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let block_number = exec_wrapper.block_info().await.block_number();
+        Ok(GetBlockNumberResponse { block_number: Felt252::from(block_number.0) })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, response.block_number)?;
+        Ok(())
+    }
+}
+
+struct GetBlockTimestampHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetBlockTimestampResponse {
+    pub block_timestamp: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetBlockTimestampHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = EmptyRequest;
+    type Response = GetBlockTimestampResponse;
+
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        Ok(EmptyRequest)
+    }
+
+    async fn execute(
+        _request: Self::Request,
+        _vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let block_timestamp = exec_wrapper.block_info().await.block_timestamp();
+        Ok(GetBlockTimestampResponse { block_timestamp: Felt252::from(block_timestamp.0) })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, response.block_timestamp)?;
+        Ok(())
+    }
+}
+
+struct GetSequencerAddressHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetSequencerAddressResponse {
+    pub sequencer_address: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetSequencerAddressHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = EmptyRequest;
+    type Response = GetSequencerAddressResponse;
+
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        Ok(EmptyRequest)
+    }
+
+    async fn execute(
+        _request: Self::Request,
+        _vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let sequencer_address = exec_wrapper.block_info().await.sequencer_address();
+        Ok(GetSequencerAddressResponse { sequencer_address })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, response.sequencer_address)?;
+        Ok(())
+    }
+}
+
+struct GetCallerAddressHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetCallerAddressResponse {
+    pub caller_address: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetCallerAddressHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = EmptyRequest;
+    type Response = GetCallerAddressResponse;
+
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        Ok(EmptyRequest)
+    }
+
+    /// The caller is the contract that dispatched the current call, as recorded when the call was
+    /// executed. Calls made directly by the transaction report the origin of the transaction.
+    async fn execute(
+        _request: Self::Request,
+        _vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let eh_ref = exec_wrapper.execution_helper.read().await;
+        let call_info = eh_ref
+            .call_info
+            .as_ref()
+            .ok_or(SyscallExecutionError::InternalError("No call is being executed".into()))?;
+        Ok(GetCallerAddressResponse { caller_address: Felt252::from(call_info.call.caller_address) })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, response.caller_address)?;
+        Ok(())
+    }
+}
+
+struct GetContractAddressHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetContractAddressResponse {
+    pub contract_address: Felt252,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetContractAddressHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = EmptyRequest;
+    type Response = GetContractAddressResponse;
+
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        Ok(EmptyRequest)
+    }
+
+    /// The address of the contract whose storage the current call uses. Library calls run in the
+    /// context of the calling contract, so this is never the address of the library class.
+    async fn execute(
+        _request: Self::Request,
+        _vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let eh_ref = exec_wrapper.execution_helper.read().await;
+        let call_info = eh_ref
+            .call_info
+            .as_ref()
+            .ok_or(SyscallExecutionError::InternalError("No call is being executed".into()))?;
+        Ok(GetContractAddressResponse { contract_address: Felt252::from(call_info.call.storage_address) })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, response.contract_address)?;
+        Ok(())
+    }
+}
+
 pub struct GetExecutionInfoHandler;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -445,12 +1482,20 @@ where
 
     async fn execute(
         _request: Self::Request,
-        _vm: &mut VirtualMachine,
+        vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
+        // The execution info struct (block info, tx info, caller and contract addresses) is
+        // built by the OS when entering the call, the syscall only hands out a pointer to it.
         let eh_ref = exec_wrapper.execution_helper.read().await;
-        let execution_info_ptr = eh_ref.call_execution_info_ptr.unwrap();
+        let execution_info_ptr = eh_ref
+            .call_execution_info_ptr
+            .ok_or(SyscallExecutionError::InternalError("No call is being executed".into()))?;
+        let execution_info_ptr = match eh_ref.starknet_version {
+            StarknetVersion::V3 => execution_info_ptr,
+            StarknetVersion::V1 | StarknetVersion::V2 => write_legacy_execution_info(vm, execution_info_ptr)?,
+        };
         Ok(GetExecutionInfoResponse { execution_info_ptr })
     }
 
@@ -460,17 +1505,121 @@ where
     }
 }
 
-struct LibraryCallHandler;
+/// Copies the execution info at `execution_info_ptr` with a tx info that ends with the nonce, as
+/// expected by the contracts of Starknet versions before v3 transactions, and returns a pointer
+/// to the copy.
+fn write_legacy_execution_info(vm: &mut VirtualMachine, execution_info_ptr: Relocatable) -> SyscallResult<Relocatable> {
+    let mut execution_info = vm.get_continuous_range(execution_info_ptr, new_syscalls::ExecutionInfo::cairo_size())?;
+    let tx_info_ptr = vm.get_relocatable((execution_info_ptr + new_syscalls::ExecutionInfo::tx_info_offset())?)?;
+    let legacy_tx_info = vm.get_continuous_range(tx_info_ptr, new_syscalls::TxInfo::resource_bounds_start_offset())?;
+
+    let legacy_tx_info_ptr = vm.add_memory_segment();
+    vm.load_data(legacy_tx_info_ptr, &legacy_tx_info)?;
+    execution_info[new_syscalls::ExecutionInfo::tx_info_offset()] = legacy_tx_info_ptr.into();
+
+    let legacy_execution_info_ptr = vm.add_memory_segment();
+    vm.load_data(legacy_execution_info_ptr, &execution_info)?;
+    Ok(legacy_execution_info_ptr)
+}
 
-impl<PCS> SyscallHandler<PCS> for LibraryCallHandler
+/// Writes `values` in a segment of `segment_pool` and returns the start and end pointers of the
+/// segment. The segment of an empty array is returned to the pool since nothing reads from it.
+fn write_felt_segment(
+    vm: &mut VirtualMachine,
+    segment_pool: &mut SegmentPool,
+    values: &[Felt252],
+) -> SyscallResult<(Relocatable, Relocatable)> {
+    let start_ptr = segment_pool.allocate(vm);
+    if values.is_empty() {
+        segment_pool
+            .release(vm, start_ptr)
+            .map_err(|error| SyscallExecutionError::InternalError(error.to_string().into_boxed_str()))?;
+        return Ok((start_ptr, start_ptr));
+    }
+    let end_ptr = vm.load_data(start_ptr, &values.iter().map(MaybeRelocatable::from).collect::<Vec<_>>())?;
+    Ok((start_ptr, end_ptr))
+}
+
+fn data_availability_mode_to_felt(mode: DataAvailabilityMode) -> Felt252 {
+    match mode {
+        DataAvailabilityMode::L1 => Felt252::ZERO,
+        DataAvailabilityMode::L2 => Felt252::ONE,
+    }
+}
+
+/// Writes a `TxInfo` struct for `tx_info` and returns a pointer to it. Arrays (signature,
+/// resource bounds, paymaster data and account deployment data) are written in their own
+/// segments, taken from `segment_pool`. The fields introduced by v3 transactions are zero or empty
+/// for older versions.
+pub fn write_tx_info(
+    vm: &mut VirtualMachine,
+    segment_pool: &mut SegmentPool,
+    tx_info: &TransactionInfo,
+    chain_id: Felt252,
+) -> SyscallResult<Relocatable> {
+    let (common_fields, max_fee, v3_fields) = match tx_info {
+        TransactionInfo::Current(context) => (&context.common_fields, Felt252::ZERO, Some(context)),
+        TransactionInfo::Deprecated(context) => (&context.common_fields, Felt252::from(context.max_fee.0), None),
+    };
+    let resource_bounds =
+        v3_fields.map(|context| create_resource_bounds_list(&context.resource_bounds)).unwrap_or_default();
+    let tip = v3_fields.map(|context| Felt252::from(context.tip.0)).unwrap_or_default();
+    let paymaster_data = v3_fields.map(|context| context.paymaster_data.0.clone()).unwrap_or_default();
+    let nonce_data_availability_mode = v3_fields
+        .map(|context| data_availability_mode_to_felt(context.nonce_data_availability_mode))
+        .unwrap_or_default();
+    let fee_data_availability_mode =
+        v3_fields.map(|context| data_availability_mode_to_felt(context.fee_data_availability_mode)).unwrap_or_default();
+    let account_deployment_data =
+        v3_fields.map(|context| context.account_deployment_data.0.clone()).unwrap_or_default();
+
+    let (signature_start, signature_end) = write_felt_segment(vm, segment_pool, &common_fields.signature.0)?;
+    let (resource_bounds_start, resource_bounds_end) = write_felt_segment(vm, segment_pool, &resource_bounds)?;
+    let (paymaster_data_start, paymaster_data_end) = write_felt_segment(vm, segment_pool, &paymaster_data)?;
+    let (account_deployment_data_start, account_deployment_data_end) =
+        write_felt_segment(vm, segment_pool, &account_deployment_data)?;
+
+    let tx_info: Vec<MaybeRelocatable> = vec![
+        common_fields.version.0.into(),
+        (*common_fields.sender_address.0.key()).into(),
+        max_fee.into(),
+        signature_start.into(),
+        signature_end.into(),
+        common_fields.transaction_hash.0.into(),
+        chain_id.into(),
+        common_fields.nonce.0.into(),
+        resource_bounds_start.into(),
+        resource_bounds_end.into(),
+        tip.into(),
+        paymaster_data_start.into(),
+        paymaster_data_end.into(),
+        nonce_data_availability_mode.into(),
+        fee_data_availability_mode.into(),
+        account_deployment_data_start.into(),
+        account_deployment_data_end.into(),
+    ];
+    debug_assert_eq!(tx_info.len(), new_syscalls::TxInfo::cairo_size());
+
+    let tx_info_ptr = vm.add_memory_segment();
+    vm.load_data(tx_info_ptr, &tx_info)?;
+    Ok(tx_info_ptr)
+}
+
+struct GetTxInfoHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetTxInfoResponse {
+    pub tx_info_ptr: Relocatable,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetTxInfoHandler
 where
     PCS: PerContractStorage + 'static,
 {
     type Request = EmptyRequest;
-    type Response = ReadOnlySegment;
+    type Response = GetTxInfoResponse;
 
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
-        *ptr = (*ptr + new_syscalls::LibraryCallRequest::cairo_size())?;
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
         Ok(EmptyRequest)
     }
 
@@ -478,9 +1627,115 @@ where
         _request: Self::Request,
         vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let mut eh_guard = exec_wrapper.execution_helper.write().await;
+        let eh_ref = &mut *eh_guard;
+        let tx_info = eh_ref
+            .current_tx_info
+            .as_ref()
+            .ok_or(SyscallExecutionError::NoActiveTransaction { syscall: "get_tx_info" })?;
+        let chain_id = eh_ref.block_info_view().chain_id();
+        let tx_info_ptr = write_tx_info(vm, &mut eh_ref.scratch_segment_pool, tx_info, chain_id)?;
+        Ok(GetTxInfoResponse { tx_info_ptr })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_maybe_relocatable(vm, ptr, response.tx_info_ptr)?;
+        Ok(())
+    }
+}
+
+struct GetTxSignatureHandler;
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct GetTxSignatureResponse {
+    pub signature_len: usize,
+    pub signature: Relocatable,
+}
+
+impl<PCS> SyscallHandler<PCS> for GetTxSignatureHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = EmptyRequest;
+    type Response = GetTxSignatureResponse;
+
+    fn read_request(_vm: &VirtualMachine, _ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        Ok(EmptyRequest)
+    }
+
+    /// The signature is written in its own segment. Contracts iterate over it, so an empty
+    /// signature still gets a valid pointer.
+    async fn execute(
+        _request: Self::Request,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
+    ) -> SyscallResult<Self::Response> {
+        let mut eh_guard = exec_wrapper.execution_helper.write().await;
+        let eh_ref = &mut *eh_guard;
+        let tx_info = eh_ref
+            .current_tx_info
+            .as_ref()
+            .ok_or(SyscallExecutionError::NoActiveTransaction { syscall: "get_tx_signature" })?;
+        let signature = match tx_info {
+            TransactionInfo::Current(context) => &context.common_fields.signature.0,
+            TransactionInfo::Deprecated(context) => &context.common_fields.signature.0,
+        };
+        let (signature_start, _) = write_felt_segment(vm, &mut eh_ref.scratch_segment_pool, signature)?;
+        Ok(GetTxSignatureResponse { signature_len: signature.len(), signature: signature_start })
+    }
+
+    fn write_response(response: Self::Response, vm: &mut VirtualMachine, ptr: &mut Relocatable) -> WriteResponseResult {
+        write_felt(vm, ptr, Felt252::from(response.signature_len))?;
+        write_maybe_relocatable(vm, ptr, response.signature)?;
+        Ok(())
+    }
+}
+
+struct LibraryCallHandler;
+
+pub struct LibraryCallRequest {
+    pub class_hash: Felt252,
+    pub selector: Felt252,
+    pub calldata: Vec<Felt252>,
+}
+
+impl<PCS> SyscallHandler<PCS> for LibraryCallHandler
+where
+    PCS: PerContractStorage + 'static,
+{
+    type Request = LibraryCallRequest;
+    type Response = ReadOnlySegment;
+
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        let class_hash = felt_from_ptr(vm, ptr)?;
+        let (selector, calldata) = read_call_params(vm, ptr)?;
+        Ok(LibraryCallRequest { class_hash, selector, calldata })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("class_hash={}, selector={}", request.class_hash.to_hex_string(), request.selector.to_hex_string())
+    }
+
+    async fn execute(
+        request: Self::Request,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
         let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        if let Some(call_info) = eh_ref.call_info.as_ref() {
+            // The inner calls are replayed in order, the next result is the one of this call.
+            let call_index = call_info.inner_calls.len().checked_sub(eh_ref.result_iter.len());
+            if let Some(inner_call) = call_index.and_then(|index| call_info.inner_calls.get(index)) {
+                check_library_call_context(call_info, inner_call, &request)?;
+            }
+        }
+        // Popped by the exit hint of the syscall, once the call returned.
+        eh_ref.enter_call_context(request.calldata);
+
         let result_iter = &mut eh_ref.result_iter;
         let result = result_iter
             .next()
@@ -491,6 +1746,7 @@ where
         let retdata = result.retdata.0;
 
         if result.failed {
+            eh_ref.mark_call_failed();
             return Err(SyscallExecutionError::SyscallError { error_data: retdata });
         }
 
@@ -504,26 +1760,86 @@ where
     }
 }
 
+fn syscall_gas_cost(gas_costs: &SyscallGasCosts, selector: SyscallSelector) -> Result<u64, HintError> {
+    gas_costs
+        .syscall_cost(selector)
+        .ok_or_else(|| HintError::CustomHint(format!("No gas cost for syscall {:?}", selector).into()))
+}
+
+/// A library call runs the code of another class on the storage of the caller: checks that the
+/// replayed call targets the requested class and did not switch to another storage context.
+fn check_library_call_context(
+    caller: &CallInfo,
+    library_call: &CallInfo,
+    request: &LibraryCallRequest,
+) -> SyscallResult<()> {
+    let class_hash = library_call.call.class_hash.map(|class_hash| class_hash.0);
+    if class_hash != Some(request.class_hash) {
+        return Err(SyscallExecutionError::InternalError(
+            format!(
+                "Library call to class {} was replayed with class {:?}",
+                request.class_hash.to_hex_string(),
+                class_hash.map(|class_hash| class_hash.to_hex_string())
+            )
+            .into(),
+        ));
+    }
+
+    if library_call.call.storage_address != caller.call.storage_address {
+        return Err(SyscallExecutionError::InternalError(
+            format!(
+                "Library call to class {} must run on the storage of {}, not {}",
+                request.class_hash.to_hex_string(),
+                Felt252::from(caller.call.storage_address).to_hex_string(),
+                Felt252::from(library_call.call.storage_address).to_hex_string()
+            )
+            .into(),
+        ));
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct ReplaceClassHandler;
 
+pub struct ReplaceClassRequest {
+    pub class_hash: Felt252,
+}
+
 impl<PCS> SyscallHandler<PCS> for ReplaceClassHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = ReplaceClassRequest;
     type Response = EmptyResponse;
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
-        *ptr = (*ptr + new_syscalls::ReplaceClassRequest::cairo_size())?;
-        Ok(EmptyRequest)
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        let class_hash = felt_from_ptr(vm, ptr)?;
+        Ok(ReplaceClassRequest { class_hash })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("class_hash={}", request.class_hash.to_hex_string())
     }
 
     async fn execute(
-        _request: Self::Request,
-        _vm: &mut VirtualMachine,
-        _exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        request: Self::Request,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
+        if request.class_hash == Felt252::ZERO {
+            return Err(SyscallExecutionError::InvalidSyscallInput {
+                input: request.class_hash,
+                info: "Cannot replace a class with class hash zero".to_string(),
+            });
+        }
+
+        let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        let contract_address = current_contract_address(vm, &eh_ref)?;
+        // Only checks a registered class, the new class is loaded when the contract is called
+        eh_ref.registered_class(request.class_hash)?;
+        eh_ref.class_replacements.insert(contract_address, request.class_hash);
         Ok(crate::execution::syscall_handler_utils::EmptyResponse {})
     }
 
@@ -538,24 +1854,66 @@ where
 
 struct SendMessageToL1Handler;
 
+pub struct SendMessageToL1Request {
+    pub to_address: Felt252,
+    pub payload: Vec<Felt252>,
+}
+
 impl<PCS> SyscallHandler<PCS> for SendMessageToL1Handler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = SendMessageToL1Request;
     type Response = EmptyResponse;
 
-    fn read_request(_vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
-        *ptr = (*ptr + new_syscalls::SendMessageToL1Request::cairo_size())?;
-        Ok(EmptyRequest)
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request> {
+        let to_address = felt_from_ptr(vm, ptr)?;
+
+        // The payload size is declared by its (start, end) pointers, make sure they describe
+        // a valid range before reading it.
+        let payload_start = vm.get_relocatable(*ptr)?;
+        let payload_end = vm.get_relocatable((*ptr + 1)?)?;
+        if payload_start.segment_index != payload_end.segment_index || payload_end.offset < payload_start.offset {
+            return Err(SyscallExecutionError::InvalidSyscallInput {
+                input: to_address,
+                info: format!("Invalid message payload bounds: {payload_start} - {payload_end}"),
+            });
+        }
+        let payload = read_felt_array::<SyscallExecutionError>(vm, ptr)?;
+        let declared_size = payload_end.offset - payload_start.offset;
+        if payload.len() != declared_size {
+            return Err(SyscallExecutionError::InvalidSyscallInput {
+                input: to_address,
+                info: format!(
+                    "Message payload length {} does not match its declared size {declared_size}",
+                    payload.len()
+                ),
+            });
+        }
+
+        Ok(SendMessageToL1Request { to_address, payload })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("to_address={}, {} payload felts", request.to_address.to_hex_string(), request.payload.len())
     }
 
     async fn execute(
-        _request: Self::Request,
-        _vm: &mut VirtualMachine,
-        _exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        request: Self::Request,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
+        let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        let message = L2ToL1Message {
+            from_address: current_contract_address(vm, &eh_ref)?,
+            to_address: request.to_address,
+            payload: request.payload,
+        };
+        if eh_ref.dry_run {
+            eh_ref.recorded_messages.push(message.clone());
+        }
+        eh_ref.l2_to_l1_messages.push(message);
         Ok(crate::execution::syscall_handler_utils::EmptyResponse {})
     }
 
@@ -569,6 +1927,9 @@ where
 }
 
 pub struct StorageReadHandler;
+pub struct StorageReadRequest {
+    pub key: Felt252,
+}
 pub struct StorageReadResponse {
     pub value: Felt252,
 }
@@ -577,21 +1938,26 @@ impl<PCS> SyscallHandler<PCS> for StorageReadHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = StorageReadRequest;
     type Response = StorageReadResponse;
 
-    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<EmptyRequest> {
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<StorageReadRequest> {
         let address_domain = vm.get_integer(*ptr)?.into_owned();
         if address_domain != Felt252::ZERO {
             return Err(SyscallExecutionError::InvalidAddressDomain { address_domain });
         }
+        let key = vm.get_integer((*ptr + new_syscalls::StorageReadRequest::key_offset())?)?.into_owned();
         *ptr = (*ptr + new_syscalls::StorageReadRequest::cairo_size())?;
-        Ok(EmptyRequest)
+        Ok(StorageReadRequest { key })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("key={}", request.key.to_hex_string())
     }
 
     async fn execute(
-        _request: EmptyRequest,
-        _vm: &mut VirtualMachine,
+        request: StorageReadRequest,
+        vm: &mut VirtualMachine,
         exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<StorageReadResponse> {
@@ -600,6 +1966,10 @@ where
         let value = eh_ref.execute_code_read_iter.next().ok_or(HintError::SyscallError(
             "n: No more storage reads available to replay".to_string().into_boxed_str(),
         ))?;
+        if let Ok(contract_address) = current_contract_address(vm, &eh_ref) {
+            let storage_access = StorageAccess::Read { contract_address, key: request.key, value };
+            eh_ref.syscall_transcript.record_storage_access(storage_access);
+        }
         Ok(StorageReadResponse { value })
     }
     fn write_response(
@@ -613,28 +1983,42 @@ where
 }
 
 pub struct StorageWriteHandler;
+pub struct StorageWriteRequest {
+    pub key: Felt252,
+}
+
 impl<PCS> SyscallHandler<PCS> for StorageWriteHandler
 where
     PCS: PerContractStorage + 'static,
 {
-    type Request = EmptyRequest;
+    type Request = StorageWriteRequest;
     type Response = EmptyResponse;
 
-    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<EmptyRequest> {
+    fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<StorageWriteRequest> {
         let address_domain = vm.get_integer(*ptr)?.into_owned();
         if address_domain != Felt252::ZERO {
             return Err(SyscallExecutionError::InvalidAddressDomain { address_domain });
         }
+        let key = vm.get_integer((*ptr + new_syscalls::StorageWriteRequest::key_offset())?)?.into_owned();
         *ptr = (*ptr + new_syscalls::StorageWriteRequest::cairo_size())?;
-        Ok(EmptyRequest)
+        Ok(StorageWriteRequest { key })
+    }
+
+    fn summarize(request: &Self::Request) -> String {
+        format!("key={}", request.key.to_hex_string())
     }
 
     async fn execute(
-        _request: EmptyRequest,
-        _vm: &mut VirtualMachine,
-        _exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        request: StorageWriteRequest,
+        vm: &mut VirtualMachine,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
         _remaining_gas: &mut u64,
     ) -> SyscallResult<EmptyResponse> {
+        let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        if let Ok(contract_address) = current_contract_address(vm, &eh_ref) {
+            let storage_access = StorageAccess::Write { contract_address, key: request.key };
+            eh_ref.syscall_transcript.record_storage_access(storage_access);
+        }
         Ok(EmptyResponse {})
     }
     fn write_response(
@@ -671,28 +2055,47 @@ where
         Ok(KeccakRequest { input_start, input_end })
     }
 
+    fn variable_gas_cost(request: &Self::Request, gas_costs: &SyscallGasCosts) -> SyscallResult<u64> {
+        // Invalid lengths are reported by `execute`.
+        let input_len = (request.input_end - request.input_start)?;
+        let n_rounds = u64::try_from(input_len)? / KECCAK_FULL_RATE_IN_U64S;
+        Ok(n_rounds * gas_costs.keccak_round)
+    }
+
     async fn execute(
         request: Self::Request,
         vm: &mut VirtualMachine,
-        _exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
-        remaining_gas: &mut u64,
+        exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
+        _remaining_gas: &mut u64,
     ) -> SyscallResult<Self::Response> {
         let input_len = (request.input_end - request.input_start)?;
         // The to_usize unwrap will not fail as the constant value is 17
-        let (n_rounds, remainder) = num_integer::div_rem(input_len, KECCAK_FULL_RATE_IN_U64S.to_usize().unwrap());
+        let remainder = input_len % KECCAK_FULL_RATE_IN_U64S.to_usize().unwrap();
 
         if remainder != 0 {
             return Err(SyscallExecutionError::SyscallError {
                 error_data: vec![Felt252::from_hex_unchecked(INVALID_INPUT_LENGTH_ERROR)],
             });
         }
-        let n_rounds = u64::try_from(n_rounds)?;
-        let gas_cost = n_rounds * KECCAK_ROUND_COST_GAS_COST;
 
-        if gas_cost > *remaining_gas {
-            return Err(SyscallExecutionError::OutOfGas { remaining_gas: (*remaining_gas) });
+        let n_rounds = input_len / KECCAK_FULL_RATE_IN_U64S.to_usize().unwrap();
+        {
+            let mut eh_ref = exec_wrapper.execution_helper.write().await;
+            eh_ref.os_logger.record_keccak_rounds(SyscallSelector::Keccak, n_rounds);
+            eh_ref.syscall_transcript.record_keccak_rounds(n_rounds);
+
+            // The OS runs one instance of the keccak builtin per round.
+            let keccak_runner =
+                vm.get_builtin_runners().iter().find(|runner| matches!(runner, BuiltinRunner::Keccak(_)));
+            if let Some(keccak_runner) = keccak_runner {
+                let usage = BuiltinUsage {
+                    name: "keccak",
+                    segment_index: keccak_runner.base() as isize,
+                    n_cells: n_rounds * keccak_runner.cells_per_instance() as usize,
+                };
+                eh_ref.os_logger.record_builtin_usage(SyscallSelector::Keccak, usage);
+            }
         }
-        *remaining_gas -= gas_cost;
 
         let input_felt_array = vm.get_integer_range(request.input_start, input_len)?;
 
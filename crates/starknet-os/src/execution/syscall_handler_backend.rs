@@ -0,0 +1,49 @@
+use std::rc::Rc;
+
+use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::vm_core::VirtualMachine;
+
+use crate::execution::syscall_handler_utils::SyscallSelector;
+
+/// Common interface of the deprecated and new syscall handlers.
+///
+/// Hints that do not care which syscall handler is installed in the execution scopes go through
+/// this trait, which also makes it possible to plug in other backends (e.g. mocks in tests).
+/// The methods are synchronous as they are called from hints, implementations backed by async
+/// code run it with `execute_coroutine`.
+pub trait SyscallHandlerBackend {
+    fn set_syscall_ptr(&self, syscall_ptr: Relocatable) -> Result<(), HintError>;
+
+    fn syscall_ptr(&self) -> Result<Option<Relocatable>, HintError>;
+
+    /// Executes the syscall identified by `selector` whose request is located at `syscall_ptr`.
+    fn execute(
+        &self,
+        selector: SyscallSelector,
+        syscall_ptr: Relocatable,
+        vm: &mut VirtualMachine,
+    ) -> Result<(), HintError>;
+}
+
+impl<T> SyscallHandlerBackend for Rc<T>
+where
+    T: SyscallHandlerBackend + ?Sized,
+{
+    fn set_syscall_ptr(&self, syscall_ptr: Relocatable) -> Result<(), HintError> {
+        self.as_ref().set_syscall_ptr(syscall_ptr)
+    }
+
+    fn syscall_ptr(&self) -> Result<Option<Relocatable>, HintError> {
+        self.as_ref().syscall_ptr()
+    }
+
+    fn execute(
+        &self,
+        selector: SyscallSelector,
+        syscall_ptr: Relocatable,
+        vm: &mut VirtualMachine,
+    ) -> Result<(), HintError> {
+        self.as_ref().execute(selector, syscall_ptr, vm)
+    }
+}
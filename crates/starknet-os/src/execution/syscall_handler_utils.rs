@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::num::TryFromIntError;
 
 use blockifier::execution::syscalls::hint_processor::SyscallExecutionError as BlockifierSyscallError;
+use cairo_lang_starknet::contract::starknet_keccak;
+use cairo_lang_starknet_classes::casm_contract_class::{CasmContractClass, CasmContractEntryPoint};
 use cairo_vm::types::errors::math_errors::MathError;
 use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
 use cairo_vm::vm::errors::hint_errors::HintError;
@@ -8,15 +11,26 @@ use cairo_vm::vm::errors::memory_errors::MemoryError;
 use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
+use num_bigint::BigUint;
 use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use starknet_api::deprecated_contract_class::EntryPointType;
 use thiserror::Error;
 
-use crate::execution::constants::SYSCALL_BASE_GAS_COST;
+use crate::execution::constants::{
+    CALL_CONTRACT_GAS_COST, DEPLOY_GAS_COST, EMIT_EVENT_GAS_COST, GET_BLOCK_HASH_GAS_COST, GET_CLASS_HASH_AT_GAS_COST,
+    GET_EXECUTION_INFO_GAS_COST, KECCAK_GAS_COST, KECCAK_ROUND_COST_GAS_COST, LIBRARY_CALL_GAS_COST,
+    REPLACE_CLASS_GAS_COST, SECP256K1_ADD_GAS_COST, SECP256K1_GET_POINT_FROM_X_GAS_COST, SECP256K1_GET_XY_GAS_COST,
+    SECP256K1_MUL_GAS_COST, SECP256K1_NEW_GAS_COST, SECP256R1_ADD_GAS_COST, SECP256R1_GET_POINT_FROM_X_GAS_COST,
+    SECP256R1_GET_XY_GAS_COST, SECP256R1_MUL_GAS_COST, SECP256R1_NEW_GAS_COST, SEND_MESSAGE_TO_L1_GAS_COST,
+    SHA256_PROCESS_BLOCK_GAS_COST, STORAGE_READ_GAS_COST, STORAGE_WRITE_GAS_COST, SYSCALL_BASE_GAS_COST,
+};
 use crate::execution::helper::ExecutionHelperWrapper;
 use crate::starknet::starknet_storage::PerContractStorage;
 use crate::storage::storage::StorageError;
+use crate::utils::display_felt;
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum SyscallSelector {
     CallContract,
     DelegateCall,
@@ -27,6 +41,7 @@ pub enum SyscallSelector {
     GetBlockNumber,
     GetBlockTimestamp,
     GetCallerAddress,
+    GetClassHashAt,
     GetContractAddress,
     GetExecutionInfo,
     GetSequencerAddress,
@@ -52,6 +67,77 @@ pub enum SyscallSelector {
     StorageWrite,
 }
 
+impl SyscallSelector {
+    /// Whether the syscall executes a contract entry point.
+    pub fn dispatches_contract_call(&self) -> bool {
+        matches!(
+            self,
+            SyscallSelector::CallContract
+                | SyscallSelector::DelegateCall
+                | SyscallSelector::DelegateL1Handler
+                | SyscallSelector::Deploy
+                | SyscallSelector::LibraryCall
+                | SyscallSelector::LibraryCallL1Handler
+        )
+    }
+}
+
+/// The syscall selectors known to the OS, with the short string the OS program uses for each.
+///
+/// Keeping this table in one place guards against ABI drift when the OS program is upgraded:
+/// a selector that is not listed here is rejected instead of being silently misrouted.
+pub const KNOWN_SYSCALL_SELECTORS: [(SyscallSelector, &[u8]); 33] = [
+    (SyscallSelector::CallContract, b"CallContract"),
+    (SyscallSelector::DelegateCall, b"DelegateCall"),
+    (SyscallSelector::DelegateL1Handler, b"DelegateL1Handler"),
+    (SyscallSelector::Deploy, b"Deploy"),
+    (SyscallSelector::EmitEvent, b"EmitEvent"),
+    (SyscallSelector::GetBlockHash, b"GetBlockHash"),
+    (SyscallSelector::GetBlockNumber, b"GetBlockNumber"),
+    (SyscallSelector::GetBlockTimestamp, b"GetBlockTimestamp"),
+    (SyscallSelector::GetCallerAddress, b"GetCallerAddress"),
+    (SyscallSelector::GetClassHashAt, b"GetClassHashAt"),
+    (SyscallSelector::GetContractAddress, b"GetContractAddress"),
+    (SyscallSelector::GetExecutionInfo, b"GetExecutionInfo"),
+    (SyscallSelector::GetSequencerAddress, b"GetSequencerAddress"),
+    (SyscallSelector::GetTxInfo, b"GetTxInfo"),
+    (SyscallSelector::GetTxSignature, b"GetTxSignature"),
+    (SyscallSelector::Keccak, b"Keccak"),
+    (SyscallSelector::LibraryCall, b"LibraryCall"),
+    (SyscallSelector::LibraryCallL1Handler, b"LibraryCallL1Handler"),
+    (SyscallSelector::ReplaceClass, b"ReplaceClass"),
+    (SyscallSelector::Sha256ProcessBlock, b"Sha256ProcessBlock"),
+    (SyscallSelector::Secp256k1Add, b"Secp256k1Add"),
+    (SyscallSelector::Secp256k1GetPointFromX, b"Secp256k1GetPointFromX"),
+    (SyscallSelector::Secp256k1GetXy, b"Secp256k1GetXy"),
+    (SyscallSelector::Secp256k1Mul, b"Secp256k1Mul"),
+    (SyscallSelector::Secp256k1New, b"Secp256k1New"),
+    (SyscallSelector::Secp256r1Add, b"Secp256r1Add"),
+    (SyscallSelector::Secp256r1GetPointFromX, b"Secp256r1GetPointFromX"),
+    (SyscallSelector::Secp256r1GetXy, b"Secp256r1GetXy"),
+    (SyscallSelector::Secp256r1Mul, b"Secp256r1Mul"),
+    (SyscallSelector::Secp256r1New, b"Secp256r1New"),
+    (SyscallSelector::SendMessageToL1, b"SendMessageToL1"),
+    (SyscallSelector::StorageRead, b"StorageRead"),
+    (SyscallSelector::StorageWrite, b"StorageWrite"),
+];
+
+impl SyscallSelector {
+    /// The short string identifying the syscall in the OS program, e.g. `b"StorageRead"`.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        KNOWN_SYSCALL_SELECTORS
+            .iter()
+            .find_map(|(selector, bytes)| (selector == self).then_some(*bytes))
+            .expect("every syscall selector is listed in KNOWN_SYSCALL_SELECTORS")
+    }
+}
+
+impl From<SyscallSelector> for Felt252 {
+    fn from(selector: SyscallSelector) -> Self {
+        Felt252::from_bytes_be_slice(selector.as_bytes())
+    }
+}
+
 impl TryFrom<Felt252> for SyscallSelector {
     type Error = HintError;
     fn try_from(raw_selector: Felt252) -> Result<Self, Self::Error> {
@@ -59,41 +145,14 @@ impl TryFrom<Felt252> for SyscallSelector {
         let selector_bytes = raw_selector.to_bytes_be();
         let first_non_zero = selector_bytes.iter().position(|&byte| byte != b'\0').unwrap_or(32);
 
-        match &selector_bytes[first_non_zero..] {
-            b"CallContract" => Ok(Self::CallContract),
-            b"DelegateCall" => Ok(Self::DelegateCall),
-            b"DelegateL1Handler" => Ok(Self::DelegateL1Handler),
-            b"Deploy" => Ok(Self::Deploy),
-            b"EmitEvent" => Ok(Self::EmitEvent),
-            b"GetBlockHash" => Ok(Self::GetBlockHash),
-            b"GetBlockNumber" => Ok(Self::GetBlockNumber),
-            b"GetBlockTimestamp" => Ok(Self::GetBlockTimestamp),
-            b"GetCallerAddress" => Ok(Self::GetCallerAddress),
-            b"GetContractAddress" => Ok(Self::GetContractAddress),
-            b"GetExecutionInfo" => Ok(Self::GetExecutionInfo),
-            b"GetSequencerAddress" => Ok(Self::GetSequencerAddress),
-            b"GetTxInfo" => Ok(Self::GetTxInfo),
-            b"GetTxSignature" => Ok(Self::GetTxSignature),
-            b"Keccak" => Ok(Self::Keccak),
-            b"LibraryCall" => Ok(Self::LibraryCall),
-            b"LibraryCallL1Handler" => Ok(Self::LibraryCallL1Handler),
-            b"ReplaceClass" => Ok(Self::ReplaceClass),
-            b"Sha256ProcessBlock" => Ok(Self::Sha256ProcessBlock),
-            b"Secp256k1Add" => Ok(Self::Secp256k1Add),
-            b"Secp256k1GetPointFromX" => Ok(Self::Secp256k1GetPointFromX),
-            b"Secp256k1GetXy" => Ok(Self::Secp256k1GetXy),
-            b"Secp256k1Mul" => Ok(Self::Secp256k1Mul),
-            b"Secp256k1New" => Ok(Self::Secp256k1New),
-            b"Secp256r1Add" => Ok(Self::Secp256r1Add),
-            b"Secp256r1GetPointFromX" => Ok(Self::Secp256r1GetPointFromX),
-            b"Secp256r1GetXy" => Ok(Self::Secp256r1GetXy),
-            b"Secp256r1Mul" => Ok(Self::Secp256r1Mul),
-            b"Secp256r1New" => Ok(Self::Secp256r1New),
-            b"SendMessageToL1" => Ok(Self::SendMessageToL1),
-            b"StorageRead" => Ok(Self::StorageRead),
-            b"StorageWrite" => Ok(Self::StorageWrite),
-            _ => Err(HintError::CustomHint(format!("Unknown syscall selector: {}", raw_selector).into())),
-        }
+        KNOWN_SYSCALL_SELECTORS
+            .iter()
+            .find_map(|(selector, bytes)| (*bytes == &selector_bytes[first_non_zero..]).then_some(*selector))
+            .ok_or_else(|| {
+                HintError::CustomHint(
+                    format!("Unknown syscall selector: {}", raw_selector.to_hex_string()).into_boxed_str(),
+                )
+            })
     }
 }
 
@@ -110,17 +169,14 @@ pub fn ignore_felt(ptr: &mut Relocatable) -> SyscallResult<()> {
 
 pub fn read_felt_array<TErr>(vm: &VirtualMachine, ptr: &mut Relocatable) -> Result<Vec<Felt252>, TErr>
 where
-    TErr: From<VirtualMachineError> + From<MemoryError> + From<MathError>,
+    TErr: From<MemoryError> + From<MathError> + From<HintError>,
 {
     let array_data_start_ptr = vm.get_relocatable(*ptr)?;
     *ptr = (*ptr + 1)?;
     let array_data_end_ptr = vm.get_relocatable(*ptr)?;
     *ptr = (*ptr + 1)?;
-    let array_size = (array_data_end_ptr - array_data_start_ptr)?;
 
-    let values = vm.get_integer_range(array_data_start_ptr, array_size)?;
-
-    Ok(values.into_iter().map(|felt| felt.into_owned()).collect())
+    Ok(crate::utils::read_felt_array(vm, array_data_start_ptr, array_data_end_ptr)?)
 }
 
 pub fn ignore_felt_array(ptr: &mut Relocatable) -> SyscallResult<()> {
@@ -158,9 +214,9 @@ pub fn write_maybe_relocatable<T: Into<MaybeRelocatable>>(
 pub enum SyscallExecutionError {
     #[error("Internal Error: {0}")]
     InternalError(Box<str>),
-    #[error("Invalid address domain: {address_domain:?}")]
+    #[error("Invalid address domain: {}", display_felt(.address_domain))]
     InvalidAddressDomain { address_domain: Felt252 },
-    #[error("Invalid syscall input: {input:?}; {info}")]
+    #[error("Invalid syscall input: {}; {info}", display_felt(.input))]
     InvalidSyscallInput { input: Felt252, info: String },
     #[error("Syscall error.")]
     SyscallError { error_data: Vec<Felt252> },
@@ -168,6 +224,24 @@ pub enum SyscallExecutionError {
     OutOfGas { remaining_gas: u64 },
     #[error("BlockifierSyscallError: {0}")]
     BlockifierSyscallError(BlockifierSyscallError),
+    #[error("Failed to read storage for contract {}: {source}", display_felt(.contract))]
+    StorageRead { contract: Felt252, key: Felt252, source: StorageError },
+    #[error("Inconsistent storage value (expected {}, got {})", display_felt(.expected), display_felt(.actual))]
+    InconsistentStorageValue { expected: Felt252, actual: Felt252 },
+    #[error("No state entry for contract {} in the state changes dictionary at {dict_ptr}", .contract_address.to_hex_string())]
+    MissingStateEntry { contract_address: Felt252, dict_ptr: Relocatable },
+    #[error("Requested contract address {} is unavailable for deployment", .contract_address.to_hex_string())]
+    ContractAddressUnavailable { contract_address: Felt252 },
+    #[error("Entry point {} not found in contract", .selector.to_hex_string())]
+    EntryPointNotFound { selector: Felt252 },
+    #[error("Too many live secp points, the limit is {limit}")]
+    TooManySecpPoints { limit: usize },
+    #[error("The block hash provider has no hash for block {block_number}")]
+    BlockHashNotFound { block_number: u64 },
+    #[error("Constructor calldata of {len} elements exceeds the limit of {max_len}")]
+    ConstructorCalldataTooLong { len: usize, max_len: usize },
+    #[error("no active transaction context for {syscall}")]
+    NoActiveTransaction { syscall: &'static str },
 }
 
 impl From<MemoryError> for SyscallExecutionError {
@@ -178,7 +252,17 @@ impl From<MemoryError> for SyscallExecutionError {
 
 impl From<SyscallExecutionError> for HintError {
     fn from(error: SyscallExecutionError) -> Self {
-        HintError::CustomHint(format!("SyscallExecution error: {}", error).into())
+        match error {
+            SyscallExecutionError::InconsistentStorageValue { .. } => {
+                HintError::AssertionFailed(error.to_string().into_boxed_str())
+            }
+            SyscallExecutionError::StorageRead { .. }
+            | SyscallExecutionError::MissingStateEntry { .. }
+            | SyscallExecutionError::NoActiveTransaction { .. } => {
+                HintError::CustomHint(error.to_string().into_boxed_str())
+            }
+            _ => HintError::CustomHint(format!("SyscallExecution error: {}", error).into()),
+        }
     }
 }
 
@@ -249,6 +333,106 @@ pub fn write_segment(vm: &mut VirtualMachine, ptr: &mut Relocatable, segment: Re
     Ok(())
 }
 
+/// Error reported in strict mode by both syscall handlers for the syscalls they do not implement,
+/// see `OsSyscallHandlerWrapper::set_strict_syscalls`.
+pub fn unimplemented_syscall(selector: SyscallSelector) -> HintError {
+    HintError::CustomHint(format!("unimplemented syscall: {:?}", selector).into_boxed_str())
+}
+
+/// Name of the entry point that handles the calls to selectors a contract does not define.
+pub const DEFAULT_ENTRY_POINT_NAME: &str = "__default__";
+
+/// Selector of the `__default__` entry point of a Cairo 1 class.
+pub fn default_entry_point_selector() -> BigUint {
+    starknet_keccak(DEFAULT_ENTRY_POINT_NAME.as_bytes())
+}
+
+/// Returns the entry point of `contract_class` called for `selector`: the one of this selector if
+/// the class defines it, the `__default__` entry point otherwise.
+pub fn resolve_entry_point(
+    contract_class: &CasmContractClass,
+    entry_point_type: EntryPointType,
+    selector: Felt252,
+) -> SyscallResult<&CasmContractEntryPoint> {
+    let entry_points = match entry_point_type {
+        EntryPointType::External => &contract_class.entry_points_by_type.external,
+        EntryPointType::L1Handler => &contract_class.entry_points_by_type.l1_handler,
+        EntryPointType::Constructor => &contract_class.entry_points_by_type.constructor,
+    };
+    let find_entry_point =
+        |selector: &BigUint| entry_points.iter().find(|entry_point| &entry_point.selector == selector);
+
+    find_entry_point(&selector.to_biguint())
+        .or_else(|| find_entry_point(&default_entry_point_selector()))
+        .ok_or(SyscallExecutionError::EntryPointNotFound { selector })
+}
+
+/// Gas charged by the syscalls of the new syscall handler.
+///
+/// Every syscall has a fixed cost, the base cost pre-charged by the compiler included. Some
+/// syscalls also charge per item of their request. The default costs are the ones of the
+/// Starknet version supported by the OS, other versions can be tracked by overriding them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyscallGasCosts {
+    syscall_costs: HashMap<SyscallSelector, u64>,
+    /// Gas charged for every round of the keccak syscall.
+    pub keccak_round: u64,
+    /// Gas charged for every data word of an emitted event.
+    pub emit_event_data_word: u64,
+}
+
+impl SyscallGasCosts {
+    /// Fixed cost of `selector`, `None` if the syscall is not supported.
+    pub fn syscall_cost(&self, selector: SyscallSelector) -> Option<u64> {
+        self.syscall_costs.get(&selector).copied()
+    }
+
+    pub fn with_syscall_cost(mut self, selector: SyscallSelector, gas_cost: u64) -> Self {
+        self.syscall_costs.insert(selector, gas_cost);
+        self
+    }
+}
+
+impl Default for SyscallGasCosts {
+    fn default() -> Self {
+        let syscall_costs = HashMap::from([
+            (SyscallSelector::CallContract, CALL_CONTRACT_GAS_COST),
+            (SyscallSelector::DelegateL1Handler, CALL_CONTRACT_GAS_COST),
+            (SyscallSelector::Deploy, DEPLOY_GAS_COST),
+            (SyscallSelector::EmitEvent, EMIT_EVENT_GAS_COST),
+            (SyscallSelector::GetBlockHash, GET_BLOCK_HASH_GAS_COST),
+            (SyscallSelector::GetBlockNumber, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetCallerAddress, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetClassHashAt, GET_CLASS_HASH_AT_GAS_COST),
+            (SyscallSelector::GetContractAddress, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetBlockTimestamp, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetSequencerAddress, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetExecutionInfo, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetTxInfo, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::GetTxSignature, GET_EXECUTION_INFO_GAS_COST),
+            (SyscallSelector::Keccak, KECCAK_GAS_COST),
+            (SyscallSelector::LibraryCall, LIBRARY_CALL_GAS_COST),
+            (SyscallSelector::LibraryCallL1Handler, LIBRARY_CALL_GAS_COST),
+            (SyscallSelector::ReplaceClass, REPLACE_CLASS_GAS_COST),
+            (SyscallSelector::Secp256k1Add, SECP256K1_ADD_GAS_COST),
+            (SyscallSelector::Secp256k1GetPointFromX, SECP256K1_GET_POINT_FROM_X_GAS_COST),
+            (SyscallSelector::Secp256k1GetXy, SECP256K1_GET_XY_GAS_COST),
+            (SyscallSelector::Secp256k1Mul, SECP256K1_MUL_GAS_COST),
+            (SyscallSelector::Secp256k1New, SECP256K1_NEW_GAS_COST),
+            (SyscallSelector::Secp256r1Add, SECP256R1_ADD_GAS_COST),
+            (SyscallSelector::Secp256r1GetPointFromX, SECP256R1_GET_POINT_FROM_X_GAS_COST),
+            (SyscallSelector::Secp256r1GetXy, SECP256R1_GET_XY_GAS_COST),
+            (SyscallSelector::Secp256r1Mul, SECP256R1_MUL_GAS_COST),
+            (SyscallSelector::Secp256r1New, SECP256R1_NEW_GAS_COST),
+            (SyscallSelector::SendMessageToL1, SEND_MESSAGE_TO_L1_GAS_COST),
+            (SyscallSelector::Sha256ProcessBlock, SHA256_PROCESS_BLOCK_GAS_COST),
+            (SyscallSelector::StorageRead, STORAGE_READ_GAS_COST),
+            (SyscallSelector::StorageWrite, STORAGE_WRITE_GAS_COST),
+        ]);
+        Self { syscall_costs, keccak_round: KECCAK_ROUND_COST_GAS_COST, emit_event_data_word: 0 }
+    }
+}
+
 #[allow(async_fn_in_trait)]
 pub trait SyscallHandler<PCS>
 where
@@ -257,6 +441,14 @@ where
     type Request;
     type Response;
     fn read_request(vm: &VirtualMachine, ptr: &mut Relocatable) -> SyscallResult<Self::Request>;
+    /// Gas charged on top of the fixed cost of the syscall, depending on the request.
+    fn variable_gas_cost(_request: &Self::Request, _gas_costs: &SyscallGasCosts) -> SyscallResult<u64> {
+        Ok(0)
+    }
+    /// The salient arguments of the request, as recorded in the syscall transcript.
+    fn summarize(_request: &Self::Request) -> String {
+        String::new()
+    }
     async fn execute(
         request: Self::Request,
         vm: &mut VirtualMachine,
@@ -295,13 +487,14 @@ pub async fn run_handler<SH, PCS>(
     vm: &mut VirtualMachine,
     exec_wrapper: &mut ExecutionHelperWrapper<PCS>,
     syscall_gas_cost: u64,
+    gas_costs: &SyscallGasCosts,
 ) -> Result<(), HintError>
 where
     SH: SyscallHandler<PCS>,
     PCS: PerContractStorage + 'static,
 {
     // Refund `SYSCALL_BASE_GAS_COST` as it was pre-charged.
-    let required_gas = syscall_gas_cost - SYSCALL_BASE_GAS_COST;
+    let required_gas = syscall_gas_cost.saturating_sub(SYSCALL_BASE_GAS_COST);
 
     let gas_counter = felt_from_ptr(vm, syscall_ptr)?;
     let gas_counter = gas_counter.to_u64().ok_or_else(|| SyscallExecutionError::InvalidSyscallInput {
@@ -316,25 +509,115 @@ where
         return Ok(());
     }
 
+    // The selector comes right before the gas counter in the request header.
+    let selector = SyscallSelector::try_from(vm.get_integer((*syscall_ptr - 2)?)?.into_owned())?;
     let request = SH::read_request(vm, syscall_ptr)?;
+    exec_wrapper.execution_helper.write().await.syscall_transcript.record(selector, SH::summarize(&request));
 
-    // Execute.
     let mut remaining_gas = gas_counter - required_gas;
+    let variable_gas_cost = SH::variable_gas_cost(&request, gas_costs)?;
+    if remaining_gas < variable_gas_cost {
+        let out_of_gas_error = Felt252::from_hex(OUT_OF_GAS_ERROR).unwrap();
+        write_failure(remaining_gas, vec![out_of_gas_error], vm, syscall_ptr)?;
+        exec_wrapper.execution_helper.write().await.syscall_gas_consumed += required_gas;
+        return Ok(());
+    }
+    remaining_gas -= variable_gas_cost;
+
+    // Execute.
 
     let syscall_result = SH::execute(request, vm, exec_wrapper, &mut remaining_gas).await;
 
-    match syscall_result {
+    let remaining_gas = match syscall_result {
         Ok(response) => {
             write_felt(vm, syscall_ptr, Felt252::from(remaining_gas))?;
             // 0 to indicate success.
             write_felt(vm, syscall_ptr, Felt252::ZERO)?;
-            SH::write_response(response, vm, syscall_ptr)?
+            SH::write_response(response, vm, syscall_ptr)?;
+            remaining_gas
         }
         Err(SyscallExecutionError::SyscallError { error_data: data }) => {
             write_failure(remaining_gas, data, vm, syscall_ptr)?;
+            remaining_gas
+        }
+        Err(SyscallExecutionError::OutOfGas { remaining_gas }) => {
+            let out_of_gas_error = Felt252::from_hex(OUT_OF_GAS_ERROR).unwrap();
+            write_failure(remaining_gas, vec![out_of_gas_error], vm, syscall_ptr)?;
+            remaining_gas
         }
         Err(error) => return Err(error.into()),
     };
+    exec_wrapper.execution_helper.write().await.syscall_gas_consumed += gas_counter.saturating_sub(remaining_gas);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syscall_selector_round_trip() {
+        for (selector, bytes) in KNOWN_SYSCALL_SELECTORS {
+            let raw_selector = Felt252::from(selector);
+            assert_eq!(raw_selector, Felt252::from_bytes_be_slice(bytes));
+            assert_eq!(SyscallSelector::try_from(raw_selector).unwrap(), selector);
+        }
+    }
+
+    #[test]
+    fn test_unknown_syscall_selector() {
+        let raw_selector = Felt252::from_bytes_be_slice(b"StorageReadV2");
+        let error = SyscallSelector::try_from(raw_selector).unwrap_err();
+        assert!(
+            matches!(&error, HintError::CustomHint(message)
+                if message.as_ref() == format!("Unknown syscall selector: {}", raw_selector.to_hex_string())),
+            "unexpected error: {error}"
+        );
+        assert!(SyscallSelector::try_from(Felt252::ZERO).is_err());
+    }
+
+    fn entry_point(selector: BigUint, offset: usize) -> CasmContractEntryPoint {
+        CasmContractEntryPoint { selector, offset, builtins: vec![] }
+    }
+
+    fn contract_class_with_external_entry_points(entry_points: Vec<CasmContractEntryPoint>) -> CasmContractClass {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        let mut contract_class: CasmContractClass = serde_json::from_slice(casm_bytes).unwrap();
+        contract_class.entry_points_by_type.external = entry_points;
+        contract_class
+    }
+
+    #[test]
+    fn test_resolve_entry_point() {
+        let selector = Felt252::from(0x10);
+        let contract_class = contract_class_with_external_entry_points(vec![
+            entry_point(BigUint::from(0x10u32), 1),
+            entry_point(default_entry_point_selector(), 2),
+        ]);
+
+        let entry_point = resolve_entry_point(&contract_class, EntryPointType::External, selector).unwrap();
+        assert_eq!(entry_point.offset, 1);
+
+        // Selectors without an entry point of their own are handled by `__default__`
+        let entry_point = resolve_entry_point(&contract_class, EntryPointType::External, Felt252::from(0x20)).unwrap();
+        assert_eq!(entry_point.offset, 2);
+    }
+
+    #[test]
+    fn test_resolve_entry_point_not_found() {
+        let contract_class = contract_class_with_external_entry_points(vec![entry_point(BigUint::from(0x10u32), 1)]);
+
+        let result = resolve_entry_point(&contract_class, EntryPointType::External, Felt252::from(0x20));
+        assert!(matches!(
+            result,
+            Err(SyscallExecutionError::EntryPointNotFound { selector }) if selector == Felt252::from(0x20)
+        ));
+
+        // The entry point exists, but not for this entry point type
+        assert!(resolve_entry_point(&contract_class, EntryPointType::L1Handler, Felt252::from(0x10)).is_err());
+    }
+}
@@ -1,11 +1,9 @@
-use core::panic;
 use std::any::Any;
 use std::collections::hash_map::IntoIter;
 use std::collections::HashMap;
 use std::rc::Rc;
 
 use blockifier::context::BlockContext;
-use cairo_vm::hint_processor::builtin_hint_processor::dict_manager::Dictionary;
 use cairo_vm::hint_processor::builtin_hint_processor::hint_utils::{
     get_ptr_from_var_name, get_relocatable_from_var_name, insert_value_from_var_name, insert_value_into_ap,
 };
@@ -21,6 +19,7 @@ use starknet_os_types::casm_contract_class::GenericCasmContractClass;
 use starknet_os_types::chain_id::chain_id_to_felt;
 
 use crate::cairo_types::structs::{CompiledClass, CompiledClassFact};
+use crate::hints::execution::set_state_entry;
 use crate::hints::vars;
 use crate::io::classes::write_class;
 use crate::io::input::StarknetOsInput;
@@ -280,18 +279,7 @@ pub fn get_block_mapping(
 ) -> Result<(), HintError> {
     let key = get_constant(vars::constants::BLOCK_HASH_CONTRACT_ADDRESS, constants)?;
     let dict_ptr = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
-    let val = match exec_scopes.get_dict_manager()?.borrow().get_tracker(dict_ptr)?.data.clone() {
-        Dictionary::SimpleDictionary(dict) => dict
-            .get(&MaybeRelocatable::Int(*key))
-            .ok_or_else(|| {
-                HintError::CustomHint("State changes dictionary shouldn't be None".to_string().into_boxed_str())
-            })?
-            .clone(),
-        Dictionary::DefaultDictionary { dict: _d, default_value: _v } => {
-            panic!("State changes dict shouldn't be a default dict")
-        }
-    };
-    insert_value_from_var_name(vars::ids::STATE_ENTRY, val, vm, ids_data, ap_tracking)
+    set_state_entry(dict_ptr, *key, vm, exec_scopes, ids_data, ap_tracking)
 }
 
 pub const ELEMENTS_GE_10: &str = "memory[ap] = to_felt_or_relocatable(ids.elements_end - ids.elements >= 10)";
@@ -15,12 +15,12 @@ use indoc::indoc;
 
 use crate::cairo_types::contract_class::ContractClassComponentHashes;
 use crate::cairo_types::structs::ExecutionContext;
-use crate::execution::helper::ExecutionHelperWrapper;
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
+use crate::hints::execution::start_tx_in_scope;
 use crate::hints::vars;
 use crate::io::InternalTransaction;
 use crate::starknet::starknet_storage::PerContractStorage;
-use crate::utils::execute_coroutine;
+use crate::utils::{execute_coroutine, get_syscall_handler};
 
 pub const START_TX_VALIDATE_DECLARE_EXECUTION_CONTEXT: &str = indoc! {r#"
     execution_helper.start_tx(
@@ -36,14 +36,11 @@ pub async fn start_tx_validate_declare_execution_context_async<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let execution_helper: ExecutionHelperWrapper<PCS> = exec_scopes.get(vars::scopes::EXECUTION_HELPER)?;
     let execution_context_ptr =
         get_relocatable_from_var_name(vars::ids::VALIDATE_DECLARE_EXECUTION_CONTEXT, vm, ids_data, ap_tracking)?;
     let deprecated_tx_info_ptr = (execution_context_ptr + ExecutionContext::deprecated_tx_info_offset())?;
 
-    execution_helper.start_tx(Some(deprecated_tx_info_ptr)).await;
-
-    Ok(())
+    start_tx_in_scope::<PCS>(exec_scopes, deprecated_tx_info_ptr).await
 }
 
 pub fn start_tx_validate_declare_execution_context<PCS>(
@@ -72,7 +69,8 @@ where
 {
     let sha256_ptr = get_ptr_from_var_name(vars::ids::SHA256_PTR, vm, ids_data, ap_tracking)?;
 
-    let syscall_handler: OsSyscallHandlerWrapper<PCS> = exec_scopes.get(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler: OsSyscallHandlerWrapper<PCS> =
+        get_syscall_handler(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     syscall_handler.set_sha256_segment(sha256_ptr).await;
 
     Ok(())
@@ -1,5 +1,6 @@
 use std::any::Any;
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::vec::IntoIter;
@@ -23,12 +24,13 @@ use num_bigint::BigUint;
 use num_traits::ToPrimitive;
 
 use crate::cairo_types::new_syscalls;
-use crate::cairo_types::structs::{EntryPointReturnValues, ExecutionContext};
-use crate::cairo_types::syscalls::{CallContractResponse, StorageRead, StorageReadRequest, StorageWrite, TxInfo};
+use crate::cairo_types::structs::{EntryPointReturnValues, ExecutionContext, StateEntry};
+use crate::cairo_types::syscalls::{CallContractResponse, SyscallRequestReader, TxInfo};
 use crate::execution::deprecated_syscall_handler::DeprecatedOsSyscallHandlerWrapper;
 use crate::execution::helper::ExecutionHelperWrapper;
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
-use crate::execution::syscall_handler_utils::SyscallSelector;
+use crate::execution::syscall_handler_utils::{SyscallExecutionError, SyscallResult};
+use crate::hints::syscalls::{check_syscall_ptr_initialized, os_logger_enter_syscall};
 use crate::hints::types::{PatriciaSkipValidationRunner, Preimage};
 use crate::hints::vars;
 use crate::io::input::StarknetOsInput;
@@ -37,7 +39,10 @@ use crate::starknet::core::os::transaction_hash::create_resource_bounds_list;
 use crate::starknet::starknet_storage::{PerContractStorage, StorageLeaf};
 use crate::starkware_utils::commitment_tree::base_types::DescentMap;
 use crate::starkware_utils::commitment_tree::update_tree::{DecodeNodeCase, TreeUpdate, UpdateTree};
-use crate::utils::{custom_hint_error, execute_coroutine, get_constant};
+use crate::utils::{
+    assert_memory_ranges_equal, custom_hint_error, execute_coroutine, get_constant, get_syscall_handler,
+    get_variable_from_root_exec_scope, set_variable_in_root_exec_scope,
+};
 
 pub const LOAD_NEXT_TX: &str = indoc! {r#"
         tx = next(transactions)
@@ -198,8 +203,10 @@ pub fn enter_scope_deprecated_syscall_handler<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let dep_sys =
-        exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::DEPRECATED_SYSCALL_HANDLER)?;
+    let dep_sys = get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(
+        exec_scopes,
+        vars::scopes::DEPRECATED_SYSCALL_HANDLER,
+    )?;
     let deprecated_syscall_handler: Box<dyn Any> = Box::new(dep_sys);
     exec_scopes
         .enter_scope(HashMap::from_iter([(String::from(vars::scopes::SYSCALL_HANDLER), deprecated_syscall_handler)]));
@@ -217,13 +224,15 @@ pub fn enter_scope_syscall_handler<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let sys = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let sys = get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_handler: Box<dyn Any> = Box::new(sys);
     exec_scopes.enter_scope(HashMap::from_iter([(String::from(vars::scopes::SYSCALL_HANDLER), syscall_handler)]));
     Ok(())
 }
 
-fn set_state_entry(
+/// Writes `dict[key]` to `ids.state_entry`, following the semantics of `dict_read` for default
+/// dictionaries.
+pub(crate) fn set_state_entry(
     dict_ptr: Relocatable,
     key: Felt252,
     vm: &mut VirtualMachine,
@@ -233,16 +242,80 @@ fn set_state_entry(
 ) -> Result<(), HintError> {
     let val = match exec_scopes.get_dict_manager()?.borrow().get_tracker(dict_ptr)?.data.clone() {
         Dictionary::SimpleDictionary(dict) => dict.get(&MaybeRelocatable::Int(key)).cloned(),
-        Dictionary::DefaultDictionary { dict: _d, default_value: _v } => {
-            return Err(custom_hint_error("State changes dictionary should not be a default dict"));
+        Dictionary::DefaultDictionary { dict, default_value } => {
+            Some(dict.get(&MaybeRelocatable::Int(key)).cloned().unwrap_or(default_value))
         }
     };
-    let val = val.ok_or(custom_hint_error("State changes dictionary should not be None"))?;
+    let val = val.ok_or(SyscallExecutionError::MissingStateEntry { contract_address: key, dict_ptr })?;
 
     insert_value_from_var_name(vars::ids::STATE_ENTRY, val, vm, ids_data, ap_tracking)?;
     Ok(())
 }
 
+/// Returns the (contract address, state entry) pairs set in the `contract_state_changes`
+/// dictionary at `dict_ptr`, sorted by contract address. The default value of default
+/// dictionaries is not included.
+pub fn iter_contract_state_changes(
+    exec_scopes: &ExecutionScopes,
+    dict_ptr: Relocatable,
+) -> Result<IntoIter<(Felt252, MaybeRelocatable)>, HintError> {
+    let dict = match exec_scopes.get_dict_manager()?.borrow().get_tracker(dict_ptr)?.data.clone() {
+        Dictionary::SimpleDictionary(dict) => dict,
+        Dictionary::DefaultDictionary { dict, .. } => dict,
+    };
+
+    let mut state_changes = dict
+        .into_iter()
+        .map(|(key, state_entry)| match key {
+            MaybeRelocatable::Int(contract_address) => Ok((contract_address, state_entry)),
+            MaybeRelocatable::RelocatableValue(_) => Err(custom_hint_error(format!(
+                "Invalid contract address {} in the state changes dictionary at {}",
+                key, dict_ptr
+            ))),
+        })
+        .collect::<Result<Vec<_>, HintError>>()?;
+    state_changes.sort_by_key(|(contract_address, _)| *contract_address);
+
+    Ok(state_changes.into_iter())
+}
+
+/// Serializes the `contract_state_changes` dictionary at `dict_ptr` as
+/// `{ contract_address: { class_hash, nonce, storage_root } }`, with hex-encoded felts.
+///
+/// The state entries only point to the storage changes of the contracts, their storage roots are
+/// taken from `storage_roots`, e.g. the updated roots returned by `compute_storage_commitments`,
+/// and are `null` for the contracts missing from it.
+pub fn contract_state_changes_to_json(
+    vm: &VirtualMachine,
+    exec_scopes: &ExecutionScopes,
+    dict_ptr: Relocatable,
+    storage_roots: &HashMap<Felt252, Felt252>,
+) -> Result<serde_json::Value, HintError> {
+    let mut contracts = serde_json::Map::new();
+    for (contract_address, state_entry) in iter_contract_state_changes(exec_scopes, dict_ptr)? {
+        let state_entry = state_entry.get_relocatable().ok_or_else(|| {
+            custom_hint_error(format!(
+                "Invalid state entry {} for contract {} in the state changes dictionary at {}",
+                state_entry,
+                contract_address.to_hex_string(),
+                dict_ptr
+            ))
+        })?;
+        let class_hash = vm.get_integer((state_entry + StateEntry::class_hash_offset())?)?;
+        let nonce = vm.get_integer((state_entry + StateEntry::nonce_offset())?)?;
+        let storage_root = storage_roots.get(&contract_address).map(|storage_root| storage_root.to_hex_string());
+        contracts.insert(
+            contract_address.to_hex_string(),
+            serde_json::json!({
+                "class_hash": class_hash.to_hex_string(),
+                "nonce": nonce.to_hex_string(),
+                "storage_root": storage_root,
+            }),
+        );
+    }
+    Ok(serde_json::Value::Object(contracts))
+}
+
 pub const GET_CONTRACT_ADDRESS_STATE_ENTRY: &str = indoc! {r#"
     # Fetch a state_entry in this hint and validate it in the update at the end
     # of this function.
@@ -288,18 +361,25 @@ pub fn set_state_entry_to_account_contract_address(
     Ok(())
 }
 
-fn get_state_entry_and_set_new_state_entry(
+/// Sets `ids.state_entry` and `ids.new_state_entry`, and records the access. The new state entry
+/// is taken from the segments reserved with `reserve_state_entry_segments`, if any are left.
+async fn get_state_entry_and_set_new_state_entry<PCS>(
+    execution_helper: &ExecutionHelperWrapper<PCS>,
     dict_ptr: Relocatable,
     key: Felt252,
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-) -> Result<(), HintError> {
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
     set_state_entry(dict_ptr, key, vm, exec_scopes, ids_data, ap_tracking)?;
 
-    let new_segment = vm.add_memory_segment();
+    let new_segment = execution_helper.take_state_entry_segment(vm).await;
     insert_value_from_var_name(vars::ids::NEW_STATE_ENTRY, new_segment, vm, ids_data, ap_tracking)?;
+    execution_helper.record_state_entry_access(key, new_segment).await;
 
     Ok(())
 }
@@ -311,19 +391,29 @@ pub const GET_BLOCK_HASH_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY: &
 	ids.new_state_entry = segments.add()"#
 };
 
-pub fn get_block_hash_contract_address_state_entry_and_set_new_state_entry(
+pub fn get_block_hash_contract_address_state_entry_and_set_new_state_entry<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
     let dict_ptr = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
-    let key = get_constant(vars::constants::BLOCK_HASH_CONTRACT_ADDRESS, constants)?;
-
-    get_state_entry_and_set_new_state_entry(dict_ptr, *key, vm, exec_scopes, ids_data, ap_tracking)?;
+    let key = *get_constant(vars::constants::BLOCK_HASH_CONTRACT_ADDRESS, constants)?;
 
-    Ok(())
+    let execution_helper: ExecutionHelperWrapper<PCS> = exec_scopes.get(vars::scopes::EXECUTION_HELPER)?;
+    execute_coroutine(get_state_entry_and_set_new_state_entry(
+        &execution_helper,
+        dict_ptr,
+        key,
+        vm,
+        exec_scopes,
+        ids_data,
+        ap_tracking,
+    ))?
 }
 
 pub const GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY: &str = indoc! {r#"
@@ -340,19 +430,29 @@ pub const GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY_2: &str = ind
 	ids.new_state_entry = segments.add()"#
 };
 
-pub fn get_contract_address_state_entry_and_set_new_state_entry(
+pub fn get_contract_address_state_entry_and_set_new_state_entry<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
     let dict_ptr = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
     let key = get_integer_from_var_name(vars::ids::CONTRACT_ADDRESS, vm, ids_data, ap_tracking)?;
 
-    get_state_entry_and_set_new_state_entry(dict_ptr, key, vm, exec_scopes, ids_data, ap_tracking)?;
-
-    Ok(())
+    let execution_helper: ExecutionHelperWrapper<PCS> = exec_scopes.get(vars::scopes::EXECUTION_HELPER)?;
+    execute_coroutine(get_state_entry_and_set_new_state_entry(
+        &execution_helper,
+        dict_ptr,
+        key,
+        vm,
+        exec_scopes,
+        ids_data,
+        ap_tracking,
+    ))?
 }
 
 pub const CHECK_IS_DEPRECATED: &str =
@@ -443,10 +543,13 @@ where
     let component_hashes: Box<dyn Any> = Box::new(os_input.declared_class_hash_to_component_hashes.clone());
     let execution_helper: Box<dyn Any> =
         Box::new(exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?);
-    let deprecated_syscall_handler: Box<dyn Any> =
-        Box::new(exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::DEPRECATED_SYSCALL_HANDLER)?);
+    let deprecated_syscall_handler: Box<dyn Any> = Box::new(get_syscall_handler::<
+        DeprecatedOsSyscallHandlerWrapper<PCS>,
+    >(
+        exec_scopes, vars::scopes::DEPRECATED_SYSCALL_HANDLER
+    )?);
     let syscall_handler: Box<dyn Any> =
-        Box::new(exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?);
+        Box::new(get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?);
     let dict_manager: Box<dyn Any> = Box::new(exec_scopes.get_dict_manager()?);
     exec_scopes.enter_scope(HashMap::from_iter([
         (String::from(vars::scopes::DEPRECATED_CLASS_HASHES), deprecated_class_hashes),
@@ -466,6 +569,9 @@ where
     PCS: PerContractStorage + 'static,
 {
     let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    if execution_helper.execution_helper.read().await.validate_event_ordering {
+        execution_helper.validate_event_ordering().await?;
+    }
     execution_helper.end_tx().await;
     Ok(())
 }
@@ -880,14 +986,48 @@ where
     PCS: PerContractStorage + 'static,
 {
     let tx_execution_context = get_ptr_from_var_name(vars::ids::TX_EXECUTION_CONTEXT, vm, ids_data, ap_tracking)?;
-    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
-
     let tx_info_ptr = vm.get_relocatable((tx_execution_context + ExecutionContext::deprecated_tx_info_offset())?)?;
 
+    start_tx_in_scope::<PCS>(exec_scopes, tx_info_ptr).await
+}
+
+/// Starts the transaction whose deprecated tx info is at `tx_info_ptr` on the execution helper in
+/// scope, increments the nonce of its sender and resets the per-transaction state of the syscall
+/// handler in scope, if any. Shared by all the hints that start a transaction.
+pub(crate) async fn start_tx_in_scope<PCS>(
+    exec_scopes: &ExecutionScopes,
+    tx_info_ptr: Relocatable,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
     execution_helper.start_tx(Some(tx_info_ptr)).await;
+    increment_tx_nonce(exec_scopes, &execution_helper).await;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        syscall_handler.reset_for_new_transaction().await;
+    }
     Ok(())
 }
 
+/// Increments the nonce of the account sending the transaction in scope, if it has one.
+async fn increment_tx_nonce<PCS>(exec_scopes: &ExecutionScopes, execution_helper: &ExecutionHelperWrapper<PCS>)
+where
+    PCS: PerContractStorage + 'static,
+{
+    let account = match exec_scopes.get_ref::<InternalTransaction>(vars::scopes::TX) {
+        Ok(tx) if tx.nonce.is_some() => match tx.r#type.as_str() {
+            "INVOKE_FUNCTION" | "DECLARE" => tx.sender_address.or(tx.contract_address),
+            "DEPLOY_ACCOUNT" => tx.contract_address,
+            _ => None,
+        },
+        _ => None,
+    };
+    if let Some(account) = account {
+        execution_helper.increment_nonce(account).await;
+    }
+}
+
 pub fn start_tx<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -999,7 +1139,8 @@ where
     // }
 
     let syscall_ptr_end = vm.get_relocatable((return_values_ptr + EntryPointReturnValues::syscall_ptr_offset())?)?;
-    let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     syscall_handler.validate_and_discard_syscall_ptr(syscall_ptr_end).await?;
     execution_helper.exit_call().await;
 
@@ -1019,25 +1160,123 @@ where
     execute_coroutine(check_execution_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking))?
 }
 
-fn assert_memory_ranges_equal(
-    vm: &VirtualMachine,
-    expected_ptr: Relocatable,
-    expected_size: usize,
-    actual_ptr: Relocatable,
-    actual_size: usize,
-) -> Result<(), HintError> {
-    let expected = vm.get_range(expected_ptr, expected_size);
-    let actual = vm.get_range(actual_ptr, actual_size);
-
-    if expected != actual {
+/// Fails early with a focused error when the expected and actual retdata differ in size, which is
+/// the most common reason for a return value mismatch.
+fn check_retdata_size(expected_size: usize, actual_size: usize) -> Result<(), HintError> {
+    if expected_size != actual_size {
         return Err(HintError::AssertionFailed(
-            format!("Return value mismatch expected={expected:?}, actual={actual:?}.").into_boxed_str(),
+            format!("retdata size mismatch: expected {expected_size}, actual {actual_size}").into_boxed_str(),
         ));
     }
-
     Ok(())
 }
 
+/// A return value mismatch found by a `check_*` hint while collecting mismatches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub hint: &'static str,
+    pub message: String,
+}
+
+/// The mismatches found so far, see `collect_mismatches`.
+pub type MismatchCollector = Rc<RefCell<Vec<Mismatch>>>;
+
+/// Makes `check_syscall_response`, `check_new_syscall_response` and `check_new_deploy_response`
+/// record their mismatches in the returned collector and let the run continue, instead of failing
+/// on the first one. Other errors, e.g. invalid memory accesses, still fail the hints.
+pub fn collect_mismatches(exec_scopes: &mut ExecutionScopes) -> MismatchCollector {
+    let collector = MismatchCollector::default();
+    set_variable_in_root_exec_scope(exec_scopes, vars::scopes::MISMATCH_COLLECTOR, collector.clone());
+    collector
+}
+
+/// The raw expected and actual ranges of a failed return value check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryRangeDump {
+    pub expected: Vec<Option<MaybeRelocatable>>,
+    pub actual: Vec<Option<MaybeRelocatable>>,
+}
+
+/// The ranges dumped so far by hint code, in failure order, see `dump_memory_ranges`.
+pub type MemoryRangeDumps = Rc<RefCell<HashMap<&'static str, Vec<MemoryRangeDump>>>>;
+
+/// Makes `check_syscall_response`, `check_new_syscall_response` and `check_new_deploy_response`
+/// dump the expected and actual ranges of their failed checks in the returned buffer, to inspect
+/// them after the run. The ranges are not read unless this is enabled.
+pub fn dump_memory_ranges(exec_scopes: &mut ExecutionScopes) -> MemoryRangeDumps {
+    let dumps = MemoryRangeDumps::default();
+    set_variable_in_root_exec_scope(exec_scopes, vars::scopes::MEMORY_RANGE_DUMPS, dumps.clone());
+    dumps
+}
+
+/// Dumps the `(ptr, size)` ranges checked by `hint_code` if the check failed and ranges are dumped.
+fn dump_failed_ranges(
+    vm: &VirtualMachine,
+    exec_scopes: &ExecutionScopes,
+    hint_code: &'static str,
+    expected: (Relocatable, usize),
+    actual: (Relocatable, usize),
+    result: &Result<(), HintError>,
+) {
+    if !matches!(result, Err(HintError::AssertionFailed(_))) {
+        return;
+    }
+    if let Ok(dumps) =
+        get_variable_from_root_exec_scope::<MemoryRangeDumps>(exec_scopes, vars::scopes::MEMORY_RANGE_DUMPS)
+    {
+        let read_range = |(ptr, size): (Relocatable, usize)| -> Vec<Option<MaybeRelocatable>> {
+            vm.get_range(ptr, size).into_iter().map(|value| value.map(|value| value.into_owned())).collect()
+        };
+        let dump = MemoryRangeDump { expected: read_range(expected), actual: read_range(actual) };
+        dumps.borrow_mut().entry(hint_code).or_default().push(dump);
+    }
+}
+
+/// Default maximal retdata size accepted by the syscall response checks, far above the retdata of
+/// actual calls.
+pub const MAX_RETDATA_SIZE: usize = 1 << 20;
+
+/// Overrides the maximal retdata size accepted by `check_syscall_response`,
+/// `check_new_syscall_response` and `check_new_deploy_response`, `MAX_RETDATA_SIZE` by default.
+pub fn set_max_retdata_size(exec_scopes: &mut ExecutionScopes, max_retdata_size: usize) {
+    set_variable_in_root_exec_scope(exec_scopes, vars::scopes::MAX_RETDATA_SIZE, max_retdata_size);
+}
+
+fn max_retdata_size(exec_scopes: &ExecutionScopes) -> usize {
+    get_variable_from_root_exec_scope(exec_scopes, vars::scopes::MAX_RETDATA_SIZE).unwrap_or(MAX_RETDATA_SIZE)
+}
+
+/// Converts a retdata size read from memory, rejecting sizes above `max_retdata_size`: such sizes
+/// come from corrupted memory and would make the checks fetch absurd ranges.
+fn retdata_size_to_usize(retdata_size: &Felt252, max_retdata_size: usize) -> Result<usize, HintError> {
+    match retdata_size.to_usize() {
+        Some(retdata_size) if retdata_size <= max_retdata_size => Ok(retdata_size),
+        _ => Err(custom_hint_error(format!("retdata size {retdata_size} exceeds maximum {max_retdata_size}"))),
+    }
+}
+
+/// Records the assertion failure of `hint` if mismatches are collected, returns it otherwise.
+fn check_or_collect(
+    exec_scopes: &ExecutionScopes,
+    hint: &'static str,
+    result: Result<(), HintError>,
+) -> Result<(), HintError> {
+    match result {
+        Err(HintError::AssertionFailed(message)) => {
+            match get_variable_from_root_exec_scope::<MismatchCollector>(exec_scopes, vars::scopes::MISMATCH_COLLECTOR)
+            {
+                Ok(collector) => {
+                    log::warn!("{hint}: {message}");
+                    collector.borrow_mut().push(Mismatch { hint, message: message.into_string() });
+                    Ok(())
+                }
+                Err(_) => Err(HintError::AssertionFailed(message)),
+            }
+        }
+        result => result,
+    }
+}
+
 pub const CHECK_SYSCALL_RESPONSE: &str = indoc! {r#"
 	# Check that the actual return value matches the expected one.
 	expected = memory.get_range(
@@ -1050,23 +1289,31 @@ pub const CHECK_SYSCALL_RESPONSE: &str = indoc! {r#"
 
 pub fn check_syscall_response(
     vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
+    exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let call_response_ptr = get_ptr_from_var_name(vars::ids::CALL_RESPONSE, vm, ids_data, ap_tracking)?;
-    let call_response_retdata = vm.get_relocatable((call_response_ptr + CallContractResponse::retdata_offset())?)?;
-    let call_response_retdata_size =
-        felt_to_usize(vm.get_integer((call_response_ptr + CallContractResponse::retdata_size_offset())?)?.as_ref())?;
+    let (call_response_retdata, call_response_retdata_size) =
+        call_contract_response_retdata(vm, call_response_ptr, max_retdata_size(exec_scopes))?;
 
     let retdata = get_ptr_from_var_name(vars::ids::RETDATA, vm, ids_data, ap_tracking)?;
-    let retdata_size =
-        felt_to_usize(get_integer_from_var_name(vars::ids::RETDATA_SIZE, vm, ids_data, ap_tracking)?.as_ref())?;
-
-    assert_memory_ranges_equal(vm, call_response_retdata, call_response_retdata_size, retdata, retdata_size)?;
+    let retdata_size = get_integer_from_var_name(vars::ids::RETDATA_SIZE, vm, ids_data, ap_tracking)?;
+    let retdata_size = retdata_size_to_usize(&retdata_size, max_retdata_size(exec_scopes))?;
 
-    Ok(())
+    let result = check_retdata_size(call_response_retdata_size, retdata_size).and_then(|_| {
+        assert_memory_ranges_equal(vm, call_response_retdata, call_response_retdata_size, retdata, retdata_size)
+    });
+    dump_failed_ranges(
+        vm,
+        exec_scopes,
+        CHECK_SYSCALL_RESPONSE,
+        (call_response_retdata, call_response_retdata_size),
+        (retdata, retdata_size),
+        &result,
+    );
+    check_or_collect(exec_scopes, "check_syscall_response", result)
 }
 
 pub const CHECK_NEW_SYSCALL_RESPONSE: &str = indoc! {r#"
@@ -1082,25 +1329,30 @@ pub const CHECK_NEW_SYSCALL_RESPONSE: &str = indoc! {r#"
 
 pub fn check_new_syscall_response(
     vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
+    exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let response_ptr = get_ptr_from_var_name(vars::ids::RESPONSE, vm, ids_data, ap_tracking)?;
-    let response_retdata_start =
-        vm.get_relocatable((response_ptr + new_syscalls::CallContractResponse::retdata_start_offset())?)?;
-    let response_retdata_end =
-        vm.get_relocatable((response_ptr + new_syscalls::CallContractResponse::retdata_end_offset())?)?;
-    let response_retdata_size = (response_retdata_end - response_retdata_start)?;
+    let (response_retdata_start, response_retdata_size) = new_call_contract_response_retdata(vm, response_ptr)?;
 
     let retdata = get_ptr_from_var_name(vars::ids::RETDATA, vm, ids_data, ap_tracking)?;
-    let retdata_size =
-        felt_to_usize(get_integer_from_var_name(vars::ids::RETDATA_SIZE, vm, ids_data, ap_tracking)?.as_ref())?;
-
-    assert_memory_ranges_equal(vm, response_retdata_start, response_retdata_size, retdata, retdata_size)?;
+    let retdata_size = get_integer_from_var_name(vars::ids::RETDATA_SIZE, vm, ids_data, ap_tracking)?;
+    let retdata_size = retdata_size_to_usize(&retdata_size, max_retdata_size(exec_scopes))?;
 
-    Ok(())
+    let result = check_retdata_size(response_retdata_size, retdata_size).and_then(|_| {
+        assert_memory_ranges_equal(vm, response_retdata_start, response_retdata_size, retdata, retdata_size)
+    });
+    dump_failed_ranges(
+        vm,
+        exec_scopes,
+        CHECK_NEW_SYSCALL_RESPONSE,
+        (response_retdata_start, response_retdata_size),
+        (retdata, retdata_size),
+        &result,
+    );
+    check_or_collect(exec_scopes, "check_new_syscall_response", result)
 }
 
 pub const CHECK_NEW_DEPLOY_RESPONSE: &str = indoc! {r#"
@@ -1115,13 +1367,83 @@ pub const CHECK_NEW_DEPLOY_RESPONSE: &str = indoc! {r#"
 
 pub fn check_new_deploy_response(
     vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
+    exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError> {
     let response_ptr = get_ptr_from_var_name(vars::ids::RESPONSE, vm, ids_data, ap_tracking)?;
 
+    // Name the deployed contract in the errors, when the response holds its address
+    let context = match vm.get_integer((response_ptr + new_syscalls::DeployResponse::contract_address_offset())?) {
+        Ok(contract_address) => format!("Constructor retdata of contract {}", contract_address.to_hex_string()),
+        Err(_) => "Constructor retdata".to_string(),
+    };
+    let add_context = |error| match error {
+        HintError::AssertionFailed(message) => {
+            HintError::AssertionFailed(format!("{context}: {message}").into_boxed_str())
+        }
+        error => error,
+    };
+
+    let (constructor_retdata_start, response_retdata_size) = match new_deploy_response_retdata(vm, response_ptr) {
+        Ok(Some(range)) => range,
+        Ok(None) => {
+            log::warn!("retdata_start is 0, not a relocatable, ignoring add_relocation_rule()");
+            return Ok(());
+        }
+        Err(error) => return check_or_collect(exec_scopes, "check_new_deploy_response", Err(add_context(error))),
+    };
+
+    let retdata = get_ptr_from_var_name(vars::ids::RETDATA, vm, ids_data, ap_tracking)?;
+    let retdata_size = get_integer_from_var_name(vars::ids::RETDATA_SIZE, vm, ids_data, ap_tracking)?;
+    let retdata_size = retdata_size_to_usize(&retdata_size, max_retdata_size(exec_scopes))?;
+
+    let result = check_retdata_size(response_retdata_size, retdata_size)
+        .and_then(|_| {
+            assert_memory_ranges_equal(vm, constructor_retdata_start, response_retdata_size, retdata, retdata_size)
+        })
+        .map_err(add_context);
+    dump_failed_ranges(
+        vm,
+        exec_scopes,
+        CHECK_NEW_DEPLOY_RESPONSE,
+        (constructor_retdata_start, response_retdata_size),
+        (retdata, retdata_size),
+        &result,
+    );
+    check_or_collect(exec_scopes, "check_new_deploy_response", result)
+}
+
+/// Start and size of the retdata of the (deprecated) `CallContractResponse` at `response_ptr`.
+fn call_contract_response_retdata(
+    vm: &VirtualMachine,
+    response_ptr: Relocatable,
+    max_retdata_size: usize,
+) -> Result<(Relocatable, usize), HintError> {
+    let retdata = vm.get_relocatable((response_ptr + CallContractResponse::retdata_offset())?)?;
+    let retdata_size = vm.get_integer((response_ptr + CallContractResponse::retdata_size_offset())?)?;
+    let retdata_size = retdata_size_to_usize(&retdata_size, max_retdata_size)?;
+    Ok((retdata, retdata_size))
+}
+
+/// Start and size of the retdata of the `CallContractResponse` at `response_ptr`.
+fn new_call_contract_response_retdata(
+    vm: &VirtualMachine,
+    response_ptr: Relocatable,
+) -> Result<(Relocatable, usize), HintError> {
+    let retdata_start =
+        vm.get_relocatable((response_ptr + new_syscalls::CallContractResponse::retdata_start_offset())?)?;
+    let retdata_end = vm.get_relocatable((response_ptr + new_syscalls::CallContractResponse::retdata_end_offset())?)?;
+    Ok((retdata_start, (retdata_end - retdata_start)?))
+}
+
+/// Start and size of the constructor retdata of the `DeployResponse` at `response_ptr`, `None` if
+/// the constructor did not run.
+fn new_deploy_response_retdata(
+    vm: &VirtualMachine,
+    response_ptr: Relocatable,
+) -> Result<Option<(Relocatable, usize)>, HintError> {
     // Bugfix:
     // If constructor_retdata_start is a Int(0) instead of a Relocatable, we need to do nothing. This
     // can happen sometimes when a constructor is called but not defined in the class'es entry
@@ -1138,23 +1460,57 @@ pub fn check_new_deploy_response(
         .ok_or(HintError::VariableNotInScopeError("retdata".to_string().into_boxed_str()))?;
     let zero = MaybeRelocatable::Int(Felt252::ZERO);
     if maybe_retdata_start == zero {
-        log::warn!("retdata_start is 0, not a relocatable, ignoring add_relocation_rule()");
-        return Ok(());
+        return Ok(None);
     }
 
-    let constructor_retdata_start =
-        vm.get_relocatable((response_ptr + new_syscalls::DeployResponse::constructor_retdata_start_offset())?)?;
+    let constructor_retdata_start = vm.get_relocatable(retdata_start_key)?;
     let constructor_retdata_end =
         vm.get_relocatable((response_ptr + new_syscalls::DeployResponse::constructor_retdata_end_offset())?)?;
-    let response_retdata_size = (constructor_retdata_end - constructor_retdata_start)?;
+    if constructor_retdata_end.segment_index != constructor_retdata_start.segment_index
+        || constructor_retdata_end.offset < constructor_retdata_start.offset
+    {
+        return Err(HintError::AssertionFailed(
+            format!(
+                "invalid constructor retdata range, start={constructor_retdata_start}, end={constructor_retdata_end}"
+            )
+            .into_boxed_str(),
+        ));
+    }
+    Ok(Some((constructor_retdata_start, (constructor_retdata_end - constructor_retdata_start)?)))
+}
 
-    let retdata = get_ptr_from_var_name(vars::ids::RETDATA, vm, ids_data, ap_tracking)?;
-    let retdata_size =
-        felt_to_usize(get_integer_from_var_name(vars::ids::RETDATA_SIZE, vm, ids_data, ap_tracking)?.as_ref())?;
+fn read_felts(vm: &VirtualMachine, ptr: Relocatable, size: usize) -> Result<Vec<Felt252>, HintError> {
+    Ok(vm.get_integer_range(ptr, size)?.into_iter().map(|felt| felt.into_owned()).collect())
+}
 
-    assert_memory_ranges_equal(vm, constructor_retdata_start, response_retdata_size, retdata, retdata_size)?;
+/// Returns the retdata of the (deprecated) `CallContractResponse` at `response_ptr`.
+pub fn read_call_contract_response_retdata(
+    vm: &VirtualMachine,
+    response_ptr: Relocatable,
+) -> Result<Vec<Felt252>, HintError> {
+    let (retdata, retdata_size) = call_contract_response_retdata(vm, response_ptr, MAX_RETDATA_SIZE)?;
+    read_felts(vm, retdata, retdata_size)
+}
 
-    Ok(())
+/// Returns the retdata of the `CallContractResponse` at `response_ptr`.
+pub fn read_new_call_contract_response_retdata(
+    vm: &VirtualMachine,
+    response_ptr: Relocatable,
+) -> Result<Vec<Felt252>, HintError> {
+    let (retdata_start, retdata_size) = new_call_contract_response_retdata(vm, response_ptr)?;
+    read_felts(vm, retdata_start, retdata_size)
+}
+
+/// Returns the constructor retdata of the `DeployResponse` at `response_ptr`, empty if the
+/// constructor did not run.
+pub fn read_new_deploy_response_retdata(
+    vm: &VirtualMachine,
+    response_ptr: Relocatable,
+) -> Result<Vec<Felt252>, HintError> {
+    match new_deploy_response_retdata(vm, response_ptr)? {
+        Some((retdata_start, retdata_size)) => read_felts(vm, retdata_start, retdata_size),
+        None => Ok(vec![]),
+    }
 }
 
 pub const LOG_ENTER_SYSCALL: &str = indoc! {r#"
@@ -1175,17 +1531,17 @@ pub const LOG_ENTER_SYSCALL: &str = indoc! {r#"
     )"#
 };
 
-pub fn log_enter_syscall(
-    _vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
+pub fn log_enter_syscall<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
+    ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    let selector = get_integer_from_var_name(vars::ids::SELECTOR, _vm, ids_data, _ap_tracking)?;
-    log::trace!("entering syscall: {:?} execution", SyscallSelector::try_from(selector)?);
-    // TODO: implement logging
-    Ok(())
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    os_logger_enter_syscall::<PCS>(false, vm, exec_scopes, ids_data, ap_tracking)
 }
 
 pub const INITIAL_GE_REQUIRED_GAS: &str = "memory[ap] = to_felt_or_relocatable(ids.initial_gas >= ids.required_gas)";
@@ -1262,22 +1618,36 @@ where
     let mut execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
 
     let contract_address = get_integer_from_var_name(vars::ids::CONTRACT_ADDRESS, vm, ids_data, ap_tracking)?;
+    let ids_value = get_integer_from_var_name(vars::ids::VALUE, vm, ids_data, ap_tracking)?;
+
+    let value = read_and_check_storage_value(&mut execution_helper, contract_address, key, ids_value).await?;
+
+    exec_scopes.insert_value(vars::scopes::VALUE, value);
+
+    Ok(())
+}
 
+/// Reads `key` from the storage of `contract_address` and checks it against the value
+/// computed by the Cairo code.
+async fn read_and_check_storage_value<PCS>(
+    execution_helper: &mut ExecutionHelperWrapper<PCS>,
+    contract_address: Felt252,
+    key: Felt252,
+    expected_value: Felt252,
+) -> SyscallResult<Felt252>
+where
+    PCS: PerContractStorage + 'static,
+{
     let value = execution_helper
         .read_storage_for_address(contract_address, key)
         .await
-        .map_err(|e| custom_hint_error(format!("Failed to read storage for contract {}: {e}", contract_address)))?;
+        .map_err(|source| SyscallExecutionError::StorageRead { contract: contract_address, key, source })?;
 
-    let ids_value = get_integer_from_var_name(vars::ids::VALUE, vm, ids_data, ap_tracking)?;
-    if ids_value != value {
-        return Err(HintError::AssertionFailed(
-            format!("Inconsistent storage value (expected {}, got {})", ids_value, value).into_boxed_str(),
-        ));
+    if expected_value != value {
+        return Err(SyscallExecutionError::InconsistentStorageValue { expected: expected_value, actual: value });
     }
 
-    exec_scopes.insert_value(vars::scopes::VALUE, value);
-
-    Ok(())
+    Ok(value)
 }
 
 pub const ADD_RELOCATION_RULE: &str = "memory.add_relocation_rule(src_ptr=ids.src_ptr, dest_ptr=ids.dest_ptr)";
@@ -1569,7 +1939,8 @@ where
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     // ids.prev_value = storage.read(key=ids.syscall_ptr.address)
-    let storage_write_address = vm.get_integer((syscall_ptr + StorageWrite::address_offset())?)?.into_owned();
+    let storage_write_request = SyscallRequestReader::new(syscall_ptr).storage_write_request();
+    let storage_write_address = storage_write_request.address(vm)?;
     let prev_value = execution_helper
         .read_storage_for_address(contract_address, storage_write_address)
         .await
@@ -1577,7 +1948,7 @@ where
     insert_value_from_var_name(vars::ids::PREV_VALUE, prev_value, vm, ids_data, ap_tracking)?;
 
     // storage.write(key=ids.syscall_ptr.address, value=ids.syscall_ptr.value)
-    let storage_write_value = vm.get_integer((syscall_ptr + StorageWrite::value_offset())?)?.into_owned();
+    let storage_write_value = storage_write_request.value(vm)?;
     execution_helper
         .write_storage_for_address(contract_address, storage_write_address, storage_write_value)
         .await
@@ -1585,15 +1956,15 @@ where
 
     let contract_state_changes = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
     get_state_entry_and_set_new_state_entry(
+        &execution_helper,
         contract_state_changes,
         contract_address,
         vm,
         exec_scopes,
         ids_data,
         ap_tracking,
-    )?;
-
-    Ok(())
+    )
+    .await
 }
 
 pub fn write_syscall_result_deprecated<PCS>(
@@ -1649,15 +2020,15 @@ where
 
     let contract_state_changes = get_ptr_from_var_name(vars::ids::CONTRACT_STATE_CHANGES, vm, ids_data, ap_tracking)?;
     get_state_entry_and_set_new_state_entry(
+        &execution_helper,
         contract_state_changes,
         contract_address,
         vm,
         exec_scopes,
         ids_data,
         ap_tracking,
-    )?;
-
-    Ok(())
+    )
+    .await
 }
 
 pub fn write_syscall_result<PCS>(
@@ -1814,8 +2185,10 @@ where
     PCS: PerContractStorage + 'static,
 {
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
-    let offset = StorageRead::request_offset() + StorageReadRequest::address_offset();
-    let key = vm.get_integer((syscall_ptr + offset)?)?.into_owned();
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    check_syscall_ptr_initialized(vm, Some(execute_coroutine(syscall_handler.syscall_ptr())?), syscall_ptr)?;
+    let key = SyscallRequestReader::new(syscall_ptr).storage_read_request().address(vm)?;
 
     execute_coroutine(cache_contract_storage::<PCS>(key, vm, exec_scopes, ids_data, ap_tracking))?
 }
@@ -1914,6 +2287,7 @@ mod tests {
     use std::cell::RefCell;
     use std::rc::Rc;
 
+    use assert_matches::assert_matches;
     use blockifier::context::BlockContext;
     use cairo_vm::hint_processor::builtin_hint_processor::dict_manager::DictManager;
     use cairo_vm::types::relocatable::Relocatable;
@@ -2019,6 +2393,49 @@ mod tests {
             .expect("Hint should not fail");
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_read_and_check_storage_value_errors(
+        #[future] execution_helper_with_storage: EHW,
+        contract_address: Felt252,
+    ) {
+        let mut execution_helper_with_storage = execution_helper_with_storage.await;
+        let key = Felt252::from(42);
+
+        let value = read_and_check_storage_value(
+            &mut execution_helper_with_storage,
+            contract_address,
+            key,
+            Felt252::from(8000),
+        )
+        .await
+        .unwrap();
+        assert_eq!(value, Felt252::from(8000));
+
+        // No storage is available for this contract
+        let unknown_contract = contract_address + Felt252::ONE;
+        let error = read_and_check_storage_value(&mut execution_helper_with_storage, unknown_contract, key, value)
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SyscallExecutionError::StorageRead { contract, key: error_key, .. }
+                if contract == unknown_contract && error_key == key
+        );
+
+        let error =
+            read_and_check_storage_value(&mut execution_helper_with_storage, contract_address, key, Felt252::from(1))
+                .await
+                .unwrap_err();
+        assert_matches!(
+            error,
+            SyscallExecutionError::InconsistentStorageValue { expected, actual }
+                if expected == Felt252::from(1) && actual == Felt252::from(8000)
+        );
+        assert_matches!(HintError::from(error), HintError::AssertionFailed(message)
+            if message.as_ref() == "Inconsistent storage value (expected 0x1, got 0x1f40)");
+    }
+
     #[test]
     fn test_enter_scope_new_node() {
         let preimage: Preimage = HashMap::new();
@@ -2136,6 +2553,572 @@ mod tests {
         assert_eq!(state_entry, Felt252::from(123));
     }
 
+    #[test]
+    fn test_get_contract_address_state_entry_missing_key() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(3);
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        let ids_data = HashMap::from([
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_dict(&mut vm, HashMap::from([(Felt252::from(1).into(), MaybeRelocatable::from(123))]))
+            .unwrap();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+
+        let missing_address = Felt252::from(0xabc);
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_STATE_CHANGES,
+            contract_state_changes.clone(),
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, missing_address, &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+
+        let error = get_contract_address_state_entry(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants)
+            .unwrap_err();
+        let dict_ptr = contract_state_changes.get_relocatable().unwrap();
+        assert_matches!(error, HintError::CustomHint(message)
+            if message.as_ref() == format!("No state entry for contract 0xabc in the state changes dictionary at {dict_ptr}"));
+    }
+
+    #[test]
+    fn test_iter_contract_state_changes() {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes: ExecutionScopes = Default::default();
+
+        let state_entries: Vec<_> =
+            (1..=3_usize).map(|i| (Felt252::from(i), MaybeRelocatable::from(((i + 10) as isize, 0)))).collect();
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_default_dict(
+                &mut vm,
+                &MaybeRelocatable::from(0),
+                Some(state_entries.iter().map(|(address, entry)| ((*address).into(), entry.clone())).collect()),
+            )
+            .unwrap()
+            .get_relocatable()
+            .unwrap();
+
+        // The dict manager is not in scope yet
+        assert!(iter_contract_state_changes(&exec_scopes, contract_state_changes).is_err());
+
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+        let state_changes: Vec<_> =
+            iter_contract_state_changes(&exec_scopes, contract_state_changes).unwrap().collect();
+        assert_eq!(state_changes, state_entries);
+
+        // No dictionary is tracked at this address
+        assert!(iter_contract_state_changes(&exec_scopes, Relocatable::from((100, 0))).is_err());
+    }
+
+    #[test]
+    fn test_contract_state_changes_to_json() {
+        let mut vm = VirtualMachine::new(false);
+        let state_entries: Vec<_> = [(0x100, 0xc1a55, 1), (0x200, 0xc1a56, 0)]
+            .into_iter()
+            .map(|(contract_address, class_hash, nonce)| {
+                let state_entry = vm.add_memory_segment();
+                let storage_ptr = vm.add_memory_segment();
+                vm.load_data(
+                    state_entry,
+                    &[Felt252::from(class_hash).into(), storage_ptr.into(), Felt252::from(nonce).into()],
+                )
+                .unwrap();
+                (MaybeRelocatable::from(Felt252::from(contract_address)), MaybeRelocatable::from(state_entry))
+            })
+            .collect();
+
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_default_dict(&mut vm, &MaybeRelocatable::from(0), Some(state_entries.into_iter().collect()))
+            .unwrap()
+            .get_relocatable()
+            .unwrap();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+
+        let storage_roots = HashMap::from([(Felt252::from(0x100), Felt252::from(0x5707))]);
+        let json = contract_state_changes_to_json(&vm, &exec_scopes, contract_state_changes, &storage_roots).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "0x100": { "class_hash": "0xc1a55", "nonce": "0x1", "storage_root": "0x5707" },
+                "0x200": { "class_hash": "0xc1a56", "nonce": "0x0", "storage_root": null },
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_contract_address_state_entry_default_dict() {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(3);
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        let ids_data = HashMap::from([
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        let stored_address = Felt252::from(1);
+        let missing_address = Felt252::from(2);
+
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_default_dict(
+                &mut vm,
+                &MaybeRelocatable::from(0),
+                Some(HashMap::from([(stored_address.into(), MaybeRelocatable::from(123))])),
+            )
+            .unwrap();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_STATE_CHANGES,
+            contract_state_changes,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+
+        // A missing key resolves to the default state entry
+        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, missing_address, &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+        get_contract_address_state_entry(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        let state_entry = get_integer_from_var_name(vars::ids::STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(state_entry, Felt252::ZERO);
+
+        // Keys present in the dictionary are returned as-is, in a fresh frame since memory is write-once
+        vm.set_fp(6);
+        insert_value_from_var_name(
+            vars::ids::CONTRACT_STATE_CHANGES,
+            contract_state_changes,
+            &mut vm,
+            &ids_data,
+            &ap_tracking,
+        )
+        .unwrap();
+        insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, stored_address, &mut vm, &ids_data, &ap_tracking)
+            .unwrap();
+        get_contract_address_state_entry(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        let state_entry = get_integer_from_var_name(vars::ids::STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(state_entry, Felt252::from(123));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_state_entry_access_order(execution_helper: EHW) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        let ids_data = HashMap::from([
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-4)),
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::NEW_STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        let first_address = Felt252::from(2);
+        let second_address = Felt252::from(1);
+
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_dict(
+                &mut vm,
+                HashMap::from([
+                    (first_address.into(), MaybeRelocatable::from(10)),
+                    (second_address.into(), MaybeRelocatable::from(20)),
+                ]),
+            )
+            .unwrap();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper.clone());
+
+        let mut new_state_entries = vec![];
+        for (i, contract_address) in [first_address, second_address].into_iter().enumerate() {
+            // Fresh frame for each fetch since memory is write-once
+            vm.set_fp(4 * (i + 1));
+            insert_value_from_var_name(
+                vars::ids::CONTRACT_STATE_CHANGES,
+                contract_state_changes,
+                &mut vm,
+                &ids_data,
+                &ap_tracking,
+            )
+            .unwrap();
+            insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, &mut vm, &ids_data, &ap_tracking)
+                .unwrap();
+            get_contract_address_state_entry_and_set_new_state_entry::<PCS>(
+                &mut vm,
+                &mut exec_scopes,
+                &ids_data,
+                &ap_tracking,
+                &constants,
+            )
+            .unwrap();
+            new_state_entries
+                .push(get_ptr_from_var_name(vars::ids::NEW_STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap());
+        }
+
+        assert_eq!(
+            execution_helper.state_entry_access_order().await,
+            vec![(first_address, new_state_entries[0]), (second_address, new_state_entries[1])]
+        );
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_reserved_state_entry_segments(execution_helper: EHW) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        let ids_data = HashMap::from([
+            (vars::ids::CONTRACT_ADDRESS.to_string(), HintReference::new_simple(-4)),
+            (vars::ids::CONTRACT_STATE_CHANGES.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::STATE_ENTRY.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::NEW_STATE_ENTRY.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        let contract_addresses = [Felt252::from(1), Felt252::from(2), Felt252::from(3)];
+        let mut dict_manager = DictManager::new();
+        let contract_state_changes = dict_manager
+            .new_dict(
+                &mut vm,
+                contract_addresses.iter().map(|address| ((*address).into(), MaybeRelocatable::from(10))).collect(),
+            )
+            .unwrap();
+        let mut exec_scopes: ExecutionScopes = Default::default();
+        exec_scopes.insert_value(vars::scopes::DICT_MANAGER, Rc::new(RefCell::new(dict_manager)));
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, execution_helper.clone());
+
+        execution_helper.reserve_state_entry_segments(&mut vm, 2).await;
+        let reserved_segments: Vec<_> =
+            execution_helper.execution_helper.read().await.state_entry_segment_pool.iter().copied().collect();
+        assert_eq!(reserved_segments.len(), 2);
+
+        let mut new_state_entries = vec![];
+        for (i, contract_address) in contract_addresses.into_iter().enumerate() {
+            // Fresh frame for each fetch since memory is write-once
+            vm.set_fp(4 * (i + 1));
+            insert_value_from_var_name(
+                vars::ids::CONTRACT_STATE_CHANGES,
+                contract_state_changes,
+                &mut vm,
+                &ids_data,
+                &ap_tracking,
+            )
+            .unwrap();
+            insert_value_from_var_name(vars::ids::CONTRACT_ADDRESS, contract_address, &mut vm, &ids_data, &ap_tracking)
+                .unwrap();
+            get_contract_address_state_entry_and_set_new_state_entry::<PCS>(
+                &mut vm,
+                &mut exec_scopes,
+                &ids_data,
+                &ap_tracking,
+                &constants,
+            )
+            .unwrap();
+            new_state_entries
+                .push(get_ptr_from_var_name(vars::ids::NEW_STATE_ENTRY, &vm, &ids_data, &ap_tracking).unwrap());
+        }
+
+        // The reserved segments are used first, then new ones are allocated
+        assert_eq!(new_state_entries[..2], reserved_segments[..]);
+        assert!(!reserved_segments.contains(&new_state_entries[2]));
+        assert!(execution_helper.execution_helper.read().await.state_entry_segment_pool.is_empty());
+    }
+
+    #[test]
+    fn test_read_response_retdata() {
+        let mut vm = VirtualMachine::new(false);
+        let values = [Felt252::from(7), Felt252::from(8), Felt252::from(9)];
+        let retdata = vm.add_memory_segment();
+        vm.load_data(retdata, &values.iter().map(MaybeRelocatable::from).collect()).unwrap();
+        let retdata_end = (retdata + values.len()).unwrap();
+
+        let response = vm.add_memory_segment();
+        vm.insert_value((response + CallContractResponse::retdata_size_offset()).unwrap(), Felt252::THREE).unwrap();
+        vm.insert_value((response + CallContractResponse::retdata_offset()).unwrap(), retdata).unwrap();
+        assert_eq!(read_call_contract_response_retdata(&vm, response).unwrap(), values);
+
+        let response = vm.add_memory_segment();
+        vm.insert_value((response + new_syscalls::CallContractResponse::retdata_start_offset()).unwrap(), retdata)
+            .unwrap();
+        vm.insert_value((response + new_syscalls::CallContractResponse::retdata_end_offset()).unwrap(), retdata_end)
+            .unwrap();
+        assert_eq!(read_new_call_contract_response_retdata(&vm, response).unwrap(), values);
+
+        let response = vm.add_memory_segment();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_start_offset()).unwrap(),
+            retdata,
+        )
+        .unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_end_offset()).unwrap(),
+            retdata_end,
+        )
+        .unwrap();
+        assert_eq!(read_new_deploy_response_retdata(&vm, response).unwrap(), values);
+
+        // No constructor ran
+        let response = vm.add_memory_segment();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_start_offset()).unwrap(),
+            Felt252::ZERO,
+        )
+        .unwrap();
+        assert_eq!(read_new_deploy_response_retdata(&vm, response).unwrap(), vec![]);
+    }
+
+    /// Sets up `ids.<response_var>`, `ids.retdata` and `ids.retdata_size` for the syscall response
+    /// checks. The actual retdata is `[1, 2, 3]` but `ids.retdata_size` only covers 2 elements.
+    /// Returns the VM, the references and the pointers to the (empty) response and to the retdata.
+    fn setup_retdata_size_mismatch(
+        response_var: &str,
+    ) -> (VirtualMachine, HashMap<String, HintReference>, Relocatable, Relocatable) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(3);
+
+        let ap_tracking = ApTracking::new();
+        let ids_data = HashMap::from([
+            (response_var.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::RETDATA.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::RETDATA_SIZE.to_string(), HintReference::new_simple(-1)),
+        ]);
+
+        let retdata = vm.add_memory_segment();
+        vm.load_data(retdata, &[Felt252::ONE.into(), Felt252::TWO.into(), Felt252::THREE.into()]).unwrap();
+        let response = vm.add_memory_segment();
+
+        insert_value_from_var_name(response_var, response, &mut vm, &ids_data, &ap_tracking).unwrap();
+        insert_value_from_var_name(vars::ids::RETDATA, retdata, &mut vm, &ids_data, &ap_tracking).unwrap();
+        insert_value_from_var_name(vars::ids::RETDATA_SIZE, Felt252::TWO, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        (vm, ids_data, response, retdata)
+    }
+
+    #[test]
+    fn test_check_syscall_response_size_mismatch() {
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::CALL_RESPONSE);
+        vm.insert_value((response + CallContractResponse::retdata_size_offset()).unwrap(), Felt252::THREE).unwrap();
+        vm.insert_value((response + CallContractResponse::retdata_offset()).unwrap(), retdata).unwrap();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        let error = check_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+            .unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message)
+            if message.as_ref() == "retdata size mismatch: expected 3, actual 2");
+    }
+
+    #[test]
+    fn test_check_syscall_response_max_retdata_size() {
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::CALL_RESPONSE);
+        vm.insert_value((response + CallContractResponse::retdata_offset()).unwrap(), retdata).unwrap();
+        let mut exec_scopes = ExecutionScopes::new();
+
+        // A corrupted size does not fit in the default maximum
+        let retdata_size_ptr = (response + CallContractResponse::retdata_size_offset()).unwrap();
+        vm.insert_value(retdata_size_ptr, Felt252::MAX).unwrap();
+        let error = check_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+            .unwrap_err();
+        assert_matches!(error, HintError::CustomHint(message)
+            if message.as_ref() == format!("retdata size {} exceeds maximum {}", Felt252::MAX, MAX_RETDATA_SIZE));
+
+        // Sizes within the maximum are checked as usual
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::CALL_RESPONSE);
+        vm.insert_value((response + CallContractResponse::retdata_size_offset()).unwrap(), Felt252::TWO).unwrap();
+        vm.insert_value((response + CallContractResponse::retdata_offset()).unwrap(), retdata).unwrap();
+        set_max_retdata_size(&mut exec_scopes, 2);
+        check_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new()).unwrap();
+
+        set_max_retdata_size(&mut exec_scopes, 1);
+        let error = check_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+            .unwrap_err();
+        assert_matches!(error, HintError::CustomHint(message) if message.as_ref() == "retdata size 2 exceeds maximum 1");
+    }
+
+    #[test]
+    fn test_check_new_syscall_response_size_mismatch() {
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::RESPONSE);
+        let retdata_end = (retdata + 3).unwrap();
+        vm.insert_value((response + new_syscalls::CallContractResponse::retdata_start_offset()).unwrap(), retdata)
+            .unwrap();
+        vm.insert_value((response + new_syscalls::CallContractResponse::retdata_end_offset()).unwrap(), retdata_end)
+            .unwrap();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        let error =
+            check_new_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+                .unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message)
+            if message.as_ref() == "retdata size mismatch: expected 3, actual 2");
+    }
+
+    #[test]
+    fn test_check_new_deploy_response_size_mismatch() {
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::RESPONSE);
+        let retdata_end = (retdata + 3).unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_start_offset()).unwrap(),
+            retdata,
+        )
+        .unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_end_offset()).unwrap(),
+            retdata_end,
+        )
+        .unwrap();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        let error =
+            check_new_deploy_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+                .unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message)
+            if message.as_ref() == "Constructor retdata: retdata size mismatch: expected 3, actual 2");
+    }
+
+    #[test]
+    fn test_check_new_deploy_response_inverted_range() {
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::RESPONSE);
+        let retdata_start = (retdata + 3).unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::contract_address_offset()).unwrap(),
+            Felt252::from(0xdead),
+        )
+        .unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_start_offset()).unwrap(),
+            retdata_start,
+        )
+        .unwrap();
+        vm.insert_value((response + new_syscalls::DeployResponse::constructor_retdata_end_offset()).unwrap(), retdata)
+            .unwrap();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        let error =
+            check_new_deploy_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+                .unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message)
+            if message.as_ref() == format!("Constructor retdata of contract 0xdead: invalid constructor retdata range, start={retdata_start}, end={retdata}"));
+    }
+
+    #[test]
+    fn test_check_new_deploy_response_content_mismatch() {
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::RESPONSE);
+        // Same size as the actual retdata, shifted by one element
+        let retdata_start = (retdata + 1).unwrap();
+        let retdata_end = (retdata + 3).unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::contract_address_offset()).unwrap(),
+            Felt252::from(0xdead),
+        )
+        .unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_start_offset()).unwrap(),
+            retdata_start,
+        )
+        .unwrap();
+        vm.insert_value(
+            (response + new_syscalls::DeployResponse::constructor_retdata_end_offset()).unwrap(),
+            retdata_end,
+        )
+        .unwrap();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        let error =
+            check_new_deploy_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+                .unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message)
+            if message.starts_with("Constructor retdata of contract 0xdead: Return value mismatch"));
+    }
+
+    #[test]
+    fn test_check_responses_collect_mismatches() {
+        let mut exec_scopes = ExecutionScopes::new();
+        let collector = collect_mismatches(&mut exec_scopes);
+        // Collected mismatches survive nested scopes
+        exec_scopes.enter_scope(HashMap::new());
+
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::CALL_RESPONSE);
+        vm.insert_value((response + CallContractResponse::retdata_size_offset()).unwrap(), Felt252::THREE).unwrap();
+        vm.insert_value((response + CallContractResponse::retdata_offset()).unwrap(), retdata).unwrap();
+        check_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new()).unwrap();
+
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::RESPONSE);
+        let retdata_end = (retdata + 3).unwrap();
+        vm.insert_value((response + new_syscalls::CallContractResponse::retdata_start_offset()).unwrap(), retdata)
+            .unwrap();
+        vm.insert_value((response + new_syscalls::CallContractResponse::retdata_end_offset()).unwrap(), retdata_end)
+            .unwrap();
+        check_new_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new()).unwrap();
+
+        assert_eq!(
+            *collector.borrow(),
+            vec![
+                Mismatch {
+                    hint: "check_syscall_response",
+                    message: "retdata size mismatch: expected 3, actual 2".to_string()
+                },
+                Mismatch {
+                    hint: "check_new_syscall_response",
+                    message: "retdata size mismatch: expected 3, actual 2".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_responses_dump_memory_ranges() {
+        let mut exec_scopes = ExecutionScopes::new();
+        let dumps = dump_memory_ranges(&mut exec_scopes);
+
+        let (mut vm, ids_data, response, retdata) = setup_retdata_size_mismatch(vars::ids::CALL_RESPONSE);
+        vm.insert_value((response + CallContractResponse::retdata_size_offset()).unwrap(), Felt252::THREE).unwrap();
+        vm.insert_value((response + CallContractResponse::retdata_offset()).unwrap(), retdata).unwrap();
+        let error = check_syscall_response(&mut vm, &mut exec_scopes, &ids_data, &ApTracking::new(), &HashMap::new())
+            .unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(_));
+
+        let felts = |values: &[u64]| -> Vec<Option<MaybeRelocatable>> {
+            values.iter().map(|value| Some(Felt252::from(*value).into())).collect()
+        };
+        assert_eq!(
+            *dumps.borrow(),
+            HashMap::from([(
+                CHECK_SYSCALL_RESPONSE,
+                vec![MemoryRangeDump { expected: felts(&[1, 2, 3]), actual: felts(&[1, 2]) }]
+            )])
+        );
+    }
+
     #[test]
     fn test_set_fp_plus_4_to_tx_nonce() {
         let mut vm = VirtualMachine::new(false);
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 
 use cairo_lang_casm::hints::{Hint, StarknetHint};
@@ -24,11 +25,10 @@ use num_bigint::BigInt;
 
 use crate::execution::execute_syscalls;
 use crate::execution::helper::ExecutionHelperWrapper;
-use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
 use crate::hints::block_context::is_leaf;
 use crate::io::input::StarknetOsInput;
 use crate::starknet::starknet_storage::PerContractStorage;
-use crate::utils::execute_coroutine;
+use crate::utils::{custom_hint_error, execute_coroutine};
 
 pub mod block_context;
 mod bls_field;
@@ -43,6 +43,7 @@ mod kzg;
 mod os;
 mod output;
 mod patricia;
+pub mod registry;
 mod secp;
 pub mod state;
 pub mod syscalls;
@@ -133,16 +134,16 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(execution::FETCH_RESULT.into(), execution::fetch_result);
     hints.insert(execution::GEN_CLASS_HASH_ARG.into(), execution::gen_class_hash_arg);
     hints.insert(execution::GEN_SIGNATURE_ARG.into(), execution::gen_signature_arg);
-    hints.insert(execution::GET_BLOCK_HASH_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY.into(), execution::get_block_hash_contract_address_state_entry_and_set_new_state_entry);
+    hints.insert(execution::GET_BLOCK_HASH_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY.into(), execution::get_block_hash_contract_address_state_entry_and_set_new_state_entry::<PCS>);
     hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY.into(), execution::get_contract_address_state_entry);
-    hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY.into(), execution::get_contract_address_state_entry_and_set_new_state_entry);
-    hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY_2.into(), execution::get_contract_address_state_entry_and_set_new_state_entry);
+    hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY.into(), execution::get_contract_address_state_entry_and_set_new_state_entry::<PCS>);
+    hints.insert(execution::GET_CONTRACT_ADDRESS_STATE_ENTRY_AND_SET_NEW_STATE_ENTRY_2.into(), execution::get_contract_address_state_entry_and_set_new_state_entry::<PCS>);
     hints.insert(execution::GET_OLD_BLOCK_NUMBER_AND_HASH.into(), execution::get_old_block_number_and_hash::<PCS>);
     hints.insert(execution::INITIAL_GE_REQUIRED_GAS.into(), execution::initial_ge_required_gas);
     hints.insert(execution::IS_DEPRECATED.into(), execution::is_deprecated);
     hints.insert(execution::IS_REVERTED.into(), execution::is_reverted::<PCS>);
     hints.insert(execution::LOAD_NEXT_TX.into(), execution::load_next_tx);
-    hints.insert(execution::LOG_ENTER_SYSCALL.into(), execution::log_enter_syscall);
+    hints.insert(execution::LOG_ENTER_SYSCALL.into(), execution::log_enter_syscall::<PCS>);
     hints.insert(execution::OS_CONTEXT_SEGMENTS.into(), execution::os_context_segments);
     hints.insert(execution::PREPARE_CONSTRUCTOR_EXECUTION.into(), execution::prepare_constructor_execution);
     hints.insert(execution::RESOURCE_BOUNDS.into(), execution::resource_bounds);
@@ -199,49 +200,63 @@ fn hints<PCS>() -> HashMap<String, HintImpl> where
     hints.insert(syscalls::DELEGATE_L1_HANDLER.into(), syscalls::delegate_l1_handler::<PCS>);
     hints.insert(syscalls::DEPLOY.into(), syscalls::deploy::<PCS>);
     hints.insert(syscalls::EMIT_EVENT.into(), syscalls::emit_event::<PCS>);
-    hints.insert(syscalls::EXIT_CALL_CONTRACT_SYSCALL.into(), syscalls::exit_call_contract_syscall);
-    hints.insert(syscalls::EXIT_DELEGATE_CALL_SYSCALL.into(), syscalls::exit_delegate_call_syscall);
-    hints.insert(syscalls::EXIT_DELEGATE_L1_HANDLER_SYSCALL.into(), syscalls::exit_delegate_l1_handler_syscall);
-    hints.insert(syscalls::EXIT_DEPLOY_SYSCALL.into(), syscalls::exit_deploy_syscall);
-    hints.insert(syscalls::EXIT_EMIT_EVENT_SYSCALL.into(), syscalls::exit_emit_event_syscall);
-    hints.insert(syscalls::EXIT_GET_BLOCK_HASH_SYSCALL.into(), syscalls::exit_get_block_hash_syscall);
-    hints.insert(syscalls::EXIT_GET_BLOCK_NUMBER_SYSCALL.into(), syscalls::exit_get_block_number_syscall);
-    hints.insert(syscalls::EXIT_GET_BLOCK_TIMESTAMP_SYSCALL.into(), syscalls::exit_get_block_timestamp_syscall);
-    hints.insert(syscalls::EXIT_GET_CALLER_ADDRESS_SYSCALL.into(), syscalls::exit_get_caller_address_syscall);
-    hints.insert(syscalls::EXIT_GET_CONTRACT_ADDRESS_SYSCALL.into(), syscalls::exit_get_contract_address_syscall);
-    hints.insert(syscalls::EXIT_GET_EXECUTION_INFO_SYSCALL.into(), syscalls::exit_get_execution_info_syscall);
-    hints.insert(syscalls::EXIT_GET_SEQUENCER_ADDRESS_SYSCALL.into(), syscalls::exit_get_sequencer_address_syscall);
-    hints.insert(syscalls::EXIT_GET_TX_INFO_SYSCALL.into(), syscalls::exit_get_tx_info_syscall);
-    hints.insert(syscalls::EXIT_GET_TX_SIGNATURE_SYSCALL.into(), syscalls::exit_get_tx_signature_syscall);
-    hints.insert(syscalls::EXIT_KECCAK_SYSCALL.into(), syscalls::exit_keccak_syscall);
-    hints.insert(syscalls::EXIT_LIBRARY_CALL_L1_HANDLER_SYSCALL.into(), syscalls::exit_library_call_l1_handler_syscall);
-    hints.insert(syscalls::EXIT_LIBRARY_CALL_SYSCALL.into(), syscalls::exit_library_call_syscall);
-    hints.insert(syscalls::EXIT_REPLACE_CLASS_SYSCALL.into(), syscalls::exit_replace_class_syscall);
-    hints.insert(syscalls::EXIT_SECP256K1_ADD_SYSCALL.into(), syscalls::exit_sha256_process_block_syscall);
-    hints.insert(syscalls::EXIT_SHA256_PROCESS_BLOCK_SYSCALL.into(), syscalls::exit_secp256k1_add_syscall);
-    hints.insert(syscalls::EXIT_SECP256K1_GET_POINT_FROM_X_SYSCALL.into(), syscalls::exit_secp256k1_get_point_from_x_syscall);
-    hints.insert(syscalls::EXIT_SECP256K1_GET_XY_SYSCALL.into(), syscalls::exit_secp256k1_get_xy_syscall);
-    hints.insert(syscalls::EXIT_SECP256K1_MUL_SYSCALL.into(), syscalls::exit_secp256k1_mul_syscall);
-    hints.insert(syscalls::EXIT_SECP256K1_NEW_SYSCALL.into(), syscalls::exit_secp256k1_new_syscall);
-    hints.insert(syscalls::EXIT_SECP256R1_ADD_SYSCALL.into(), syscalls::exit_secp256r1_add_syscall);
-    hints.insert(syscalls::EXIT_SECP256R1_GET_POINT_FROM_X_SYSCALL.into(), syscalls::exit_secp256r1_get_point_from_x_syscall);
-    hints.insert(syscalls::EXIT_SECP256R1_GET_XY_SYSCALL.into(), syscalls::exit_secp256r1_get_xy_syscall);
-    hints.insert(syscalls::EXIT_SECP256R1_MUL_SYSCALL.into(), syscalls::exit_secp256r1_mul_syscall);
-    hints.insert(syscalls::EXIT_SECP256R1_NEW_SYSCALL.into(), syscalls::exit_secp256r1_new_syscall);
-    hints.insert(syscalls::EXIT_SEND_MESSAGE_TO_L1_SYSCALL.into(), syscalls::exit_send_message_to_l1_syscall);
-    hints.insert(syscalls::EXIT_STORAGE_READ_SYSCALL.into(), syscalls::exit_storage_read_syscall);
-    hints.insert(syscalls::EXIT_STORAGE_WRITE_SYSCALL.into(), syscalls::exit_storage_write_syscall);
+    hints.insert(syscalls::EXIT_CALL_CONTRACT_SYSCALL.into(), syscalls::exit_call_contract_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_DELEGATE_CALL_SYSCALL.into(), syscalls::exit_delegate_call_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_DELEGATE_L1_HANDLER_SYSCALL.into(), syscalls::exit_delegate_l1_handler_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_DEPLOY_SYSCALL.into(), syscalls::exit_deploy_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_EMIT_EVENT_SYSCALL.into(), syscalls::exit_emit_event_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_BLOCK_HASH_SYSCALL.into(), syscalls::exit_get_block_hash_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_BLOCK_NUMBER_SYSCALL.into(), syscalls::exit_get_block_number_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_BLOCK_TIMESTAMP_SYSCALL.into(), syscalls::exit_get_block_timestamp_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_CALLER_ADDRESS_SYSCALL.into(), syscalls::exit_get_caller_address_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_CLASS_HASH_AT_SYSCALL.into(), syscalls::exit_get_class_hash_at_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_CONTRACT_ADDRESS_SYSCALL.into(), syscalls::exit_get_contract_address_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_EXECUTION_INFO_SYSCALL.into(), syscalls::exit_get_execution_info_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_SEQUENCER_ADDRESS_SYSCALL.into(), syscalls::exit_get_sequencer_address_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_TX_INFO_SYSCALL.into(), syscalls::exit_get_tx_info_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_GET_TX_SIGNATURE_SYSCALL.into(), syscalls::exit_get_tx_signature_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_KECCAK_SYSCALL.into(), syscalls::exit_keccak_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_LIBRARY_CALL_L1_HANDLER_SYSCALL.into(), syscalls::exit_library_call_l1_handler_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_LIBRARY_CALL_SYSCALL.into(), syscalls::exit_library_call_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_REPLACE_CLASS_SYSCALL.into(), syscalls::exit_replace_class_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256K1_ADD_SYSCALL.into(), syscalls::exit_secp256k1_add_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SHA256_PROCESS_BLOCK_SYSCALL.into(), syscalls::exit_sha256_process_block_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256K1_GET_POINT_FROM_X_SYSCALL.into(), syscalls::exit_secp256k1_get_point_from_x_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256K1_GET_XY_SYSCALL.into(), syscalls::exit_secp256k1_get_xy_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256K1_MUL_SYSCALL.into(), syscalls::exit_secp256k1_mul_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256K1_NEW_SYSCALL.into(), syscalls::exit_secp256k1_new_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256R1_ADD_SYSCALL.into(), syscalls::exit_secp256r1_add_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256R1_GET_POINT_FROM_X_SYSCALL.into(), syscalls::exit_secp256r1_get_point_from_x_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256R1_GET_XY_SYSCALL.into(), syscalls::exit_secp256r1_get_xy_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256R1_MUL_SYSCALL.into(), syscalls::exit_secp256r1_mul_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SECP256R1_NEW_SYSCALL.into(), syscalls::exit_secp256r1_new_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_SEND_MESSAGE_TO_L1_SYSCALL.into(), syscalls::exit_send_message_to_l1_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_STORAGE_READ_SYSCALL.into(), syscalls::exit_storage_read_syscall::<PCS>);
+    hints.insert(syscalls::EXIT_STORAGE_WRITE_SYSCALL.into(), syscalls::exit_storage_write_syscall::<PCS>);
+    hints.insert(syscalls::GET_BLOCK_HASH.into(), syscalls::get_block_hash::<PCS>);
+    hints.insert(syscalls::GET_EXECUTION_INFO.into(), syscalls::get_execution_info::<PCS>);
     hints.insert(syscalls::GET_BLOCK_NUMBER.into(), syscalls::get_block_number::<PCS>);
     hints.insert(syscalls::GET_BLOCK_TIMESTAMP.into(), syscalls::get_block_timestamp::<PCS>);
     hints.insert(syscalls::GET_CALLER_ADDRESS.into(), syscalls::get_caller_address::<PCS>);
+    hints.insert(syscalls::GET_CLASS_HASH_AT.into(), syscalls::get_class_hash_at::<PCS>);
     hints.insert(syscalls::GET_CONTRACT_ADDRESS.into(), syscalls::get_contract_address::<PCS>);
     hints.insert(syscalls::GET_SEQUENCER_ADDRESS.into(), syscalls::get_sequencer_address::<PCS>);
     hints.insert(syscalls::GET_TX_INFO.into(), syscalls::get_tx_info::<PCS>);
     hints.insert(syscalls::GET_TX_SIGNATURE.into(), syscalls::get_tx_signature::<PCS>);
+    hints.insert(syscalls::KECCAK.into(), syscalls::keccak::<PCS>);
     hints.insert(syscalls::LIBRARY.into(), syscalls::library_call::<PCS>);
     hints.insert(syscalls::LIBRARY_CALL_L1_HANDLER.into(), syscalls::library_call_l1_handler::<PCS>);
-    hints.insert(syscalls::OS_LOGGER_ENTER_SYSCALL_PREPRARE_EXIT_SYSCALL.into(), syscalls::os_logger_enter_syscall_preprare_exit_syscall);
+    hints.insert(syscalls::OS_LOGGER_ENTER_SYSCALL_PREPRARE_EXIT_SYSCALL.into(), syscalls::os_logger_enter_syscall_preprare_exit_syscall::<PCS>);
     hints.insert(syscalls::REPLACE_CLASS.into(), syscalls::replace_class::<PCS>);
+    hints.insert(syscalls::SECP256K1_ADD.into(), syscalls::secp256k1_add::<PCS>);
+    hints.insert(syscalls::SECP256K1_GET_XY.into(), syscalls::secp256k1_get_xy::<PCS>);
+    hints.insert(syscalls::SECP256K1_MUL.into(), syscalls::secp256k1_mul::<PCS>);
+    hints.insert(syscalls::SECP256K1_NEW.into(), syscalls::secp256k1_new::<PCS>);
+    hints.insert(syscalls::SECP256R1_ADD.into(), syscalls::secp256r1_add::<PCS>);
+    hints.insert(syscalls::SECP256R1_GET_POINT_FROM_X.into(), syscalls::secp256r1_get_point_from_x::<PCS>);
+    hints.insert(syscalls::SECP256R1_GET_XY.into(), syscalls::secp256r1_get_xy::<PCS>);
+    hints.insert(syscalls::SECP256R1_MUL.into(), syscalls::secp256r1_mul::<PCS>);
+    hints.insert(syscalls::SECP256R1_NEW.into(), syscalls::secp256r1_new::<PCS>);
     hints.insert(syscalls::SEND_MESSAGE_TO_L1.into(), syscalls::send_message_to_l1::<PCS>);
     hints.insert(syscalls::SET_SYSCALL_PTR.into(), syscalls::set_syscall_ptr::<PCS>);
     hints.insert(syscalls::STORAGE_READ.into(), syscalls::storage_read::<PCS>);
@@ -280,6 +295,8 @@ where
     hints: HashMap<String, HintImpl>,
     extensive_hints: HashMap<String, ExtensiveHintImpl>,
     run_resources: RunResources,
+    // Whether a panicking hint fails the run with an error instead of unwinding, see `run_hint`
+    catch_panics: bool,
     _phantom: PhantomData<PCS>,
 }
 
@@ -309,7 +326,16 @@ where
     PCS: PerContractStorage + 'static,
 {
     fn default() -> Self {
-        let hints = hints::<PCS>();
+        Self::with_hints(hints::<PCS>(), false)
+    }
+}
+
+impl<PCS> SnosHintProcessor<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    /// Creates a hint processor running `hints` instead of the default set, see `SyscallHintRegistry`.
+    fn with_hints(hints: HashMap<String, HintImpl>, catch_panics: bool) -> Self {
         let extensive_hints = EXTENSIVE_HINTS.into_iter().map(|(h, i)| (h.to_string(), i)).collect();
         Self {
             builtin_hint_proc: BuiltinHintProcessor::new_empty(),
@@ -317,11 +343,32 @@ where
             hints,
             extensive_hints,
             run_resources: Default::default(),
+            catch_panics,
             _phantom: Default::default(),
         }
     }
 }
 
+/// Runs `hint`. If `catch_panics` is set, a panic is turned into an error carrying the panic
+/// message and `hint_code` so that the run fails cleanly.
+fn run_hint<T>(
+    catch_panics: bool,
+    hint_code: &str,
+    hint: impl FnOnce() -> Result<T, HintError>,
+) -> Result<T, HintError> {
+    if !catch_panics {
+        return hint();
+    }
+    panic::catch_unwind(AssertUnwindSafe(hint)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        Err(custom_hint_error(format!("Hint panicked: {message}\nHint code: {hint_code}")))
+    })
+}
+
 // from blockifier/cairo-vm:
 fn get_ptr_from_res_operand(vm: &mut VirtualMachine, res: &ResOperand) -> Result<Relocatable, HintError> {
     let (cell, base_offset) = match res {
@@ -385,13 +432,16 @@ where
         if let Some(hpd) = hint_data.downcast_ref::<HintProcessorData>() {
             let hint_code = hpd.code.as_str();
             if let Some(hint_impl) = self.hints.get(hint_code) {
-                return hint_impl(vm, exec_scopes, &hpd.ids_data, &hpd.ap_tracking, constants)
-                    .map(|_| HintExtension::default());
+                return run_hint(self.catch_panics, hint_code, || {
+                    hint_impl(vm, exec_scopes, &hpd.ids_data, &hpd.ap_tracking, constants)
+                })
+                .map(|_| HintExtension::default());
             }
 
             if let Some(hint_impl) = self.extensive_hints.get(hint_code) {
-                let r = hint_impl(self, vm, exec_scopes, &hpd.ids_data, &hpd.ap_tracking);
-                return r;
+                return run_hint(self.catch_panics, hint_code, || {
+                    hint_impl(self, vm, exec_scopes, &hpd.ids_data, &hpd.ap_tracking)
+                });
             }
 
             return self
@@ -403,10 +453,7 @@ where
         if let Some(hint) = hint_data.downcast_ref::<Hint>() {
             if let Hint::Starknet(StarknetHint::SystemCall { system }) = hint {
                 let syscall_ptr = get_ptr_from_res_operand(vm, system)?;
-                // TODO: need to be generic here
-                let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
-
-                return execute_coroutine(syscall_handler.execute_syscall(vm, syscall_ptr))?
+                return syscalls::execute_syscall::<PCS>(vm, exec_scopes, syscall_ptr)
                     .map(|_| HintExtension::default());
             } else {
                 return self.cairo1_builtin_hint_proc.execute(vm, exec_scopes, hint).map(|_| HintExtension::default());
@@ -622,10 +669,7 @@ where
     let deprecated_tx_info_ptr =
         get_relocatable_from_var_name(vars::ids::DEPRECATED_TX_INFO, vm, ids_data, ap_tracking)?;
 
-    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
-    execution_helper.start_tx(Some(deprecated_tx_info_ptr)).await;
-
-    Ok(())
+    execution::start_tx_in_scope::<PCS>(exec_scopes, deprecated_tx_info_ptr).await
 }
 
 pub fn start_tx<PCS>(
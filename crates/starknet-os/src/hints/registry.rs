@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use cairo_vm::vm::errors::hint_errors::HintError;
+
+use crate::hints::{hints, HintImpl, SnosHintProcessor};
+use crate::starknet::starknet_storage::PerContractStorage;
+
+/// Returns the code of the hint that executes the syscall named `name`, e.g. `storage_read`.
+fn syscall_hint_code(name: &str) -> String {
+    format!("syscall_handler.{}(segments=segments, syscall_ptr=ids.syscall_ptr)", name)
+}
+
+/// Builds a `SnosHintProcessor` from the default set of hints, with some of them replaced or
+/// removed.
+///
+/// Hints are designated either by their full hint code or, for syscall hints, by the name of the
+/// syscall (`storage_read`, `emit_event`, ...).
+pub struct SyscallHintRegistry<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    hints: HashMap<String, HintImpl>,
+    catch_panics: bool,
+    _phantom: PhantomData<PCS>,
+}
+
+impl<PCS> Default for SyscallHintRegistry<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    fn default() -> Self {
+        Self { hints: hints::<PCS>(), catch_panics: false, _phantom: Default::default() }
+    }
+}
+
+impl<PCS> SyscallHintRegistry<PCS>
+where
+    PCS: PerContractStorage + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn resolve(&self, name: &str) -> Result<String, HintError> {
+        if self.hints.contains_key(name) {
+            return Ok(name.to_string());
+        }
+        let hint_code = syscall_hint_code(name);
+        if self.hints.contains_key(&hint_code) {
+            return Ok(hint_code);
+        }
+        Err(HintError::UnknownHint(name.to_string().into_boxed_str()))
+    }
+
+    /// Replaces the implementation of the hint designated by `name`.
+    pub fn r#override(mut self, name: &str, hint_impl: HintImpl) -> Result<Self, HintError> {
+        let hint_code = self.resolve(name)?;
+        self.hints.insert(hint_code, hint_impl);
+        Ok(self)
+    }
+
+    /// Removes the hint designated by `name`. Running the OS will fail if it reaches this hint.
+    pub fn disable(mut self, name: &str) -> Result<Self, HintError> {
+        let hint_code = self.resolve(name)?;
+        self.hints.remove(&hint_code);
+        Ok(self)
+    }
+
+    /// Makes a panicking hint fail the run with a `HintError::CustomHint` naming the hint, instead
+    /// of unwinding through the VM.
+    pub fn catch_panics(mut self) -> Self {
+        self.catch_panics = true;
+        self
+    }
+
+    pub fn build(self) -> SnosHintProcessor<PCS> {
+        SnosHintProcessor::with_hints(self.hints, self.catch_panics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData;
+    use cairo_vm::hint_processor::hint_processor_definition::{HintProcessorLogic, HintReference};
+    use cairo_vm::serde::deserialize_program::ApTracking;
+    use cairo_vm::types::exec_scope::ExecutionScopes;
+    use cairo_vm::vm::vm_core::VirtualMachine;
+    use cairo_vm::Felt252;
+
+    use super::*;
+    use crate::crypto::pedersen::PedersenHash;
+    use crate::hints::syscalls;
+    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::storage::dict_storage::DictStorage;
+
+    #[allow(clippy::upper_case_acronyms)]
+    type PCS = OsSingleStarknetStorage<DictStorage, PedersenHash>;
+
+    static STORAGE_READ_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_storage_read(
+        _vm: &mut VirtualMachine,
+        _exec_scopes: &mut ExecutionScopes,
+        _ids_data: &HashMap<String, HintReference>,
+        _ap_tracking: &ApTracking,
+        _constants: &HashMap<String, Felt252>,
+    ) -> Result<(), HintError> {
+        STORAGE_READ_CALLS.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    #[test]
+    fn test_override_storage_read() {
+        let mut hint_processor =
+            SyscallHintRegistry::<PCS>::new().r#override("storage_read", counting_storage_read).unwrap().build();
+
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        let hint_data: Box<dyn core::any::Any> =
+            Box::new(HintProcessorData::new_default(syscalls::STORAGE_READ.to_string(), HashMap::new()));
+
+        for _ in 0..2 {
+            hint_processor.execute_hint_extensive(&mut vm, &mut exec_scopes, &hint_data, &HashMap::new()).unwrap();
+        }
+
+        assert_eq!(STORAGE_READ_CALLS.load(Ordering::SeqCst), 2);
+    }
+
+    fn panicking_storage_read(
+        _vm: &mut VirtualMachine,
+        _exec_scopes: &mut ExecutionScopes,
+        _ids_data: &HashMap<String, HintReference>,
+        _ap_tracking: &ApTracking,
+        _constants: &HashMap<String, Felt252>,
+    ) -> Result<(), HintError> {
+        panic!("storage is gone")
+    }
+
+    #[test]
+    fn test_catch_panics() {
+        let mut hint_processor = SyscallHintRegistry::<PCS>::new()
+            .r#override("storage_read", panicking_storage_read)
+            .unwrap()
+            .catch_panics()
+            .build();
+
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        let hint_data: Box<dyn core::any::Any> =
+            Box::new(HintProcessorData::new_default(syscalls::STORAGE_READ.to_string(), HashMap::new()));
+
+        let result = hint_processor.execute_hint_extensive(&mut vm, &mut exec_scopes, &hint_data, &HashMap::new());
+        match result {
+            Err(HintError::CustomHint(message)) => {
+                assert!(message.contains("storage is gone"), "{message}");
+                assert!(message.contains(syscalls::STORAGE_READ), "{message}");
+            }
+            other => panic!("expected the panic to surface as an error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_disable_hint() {
+        let hint_processor = SyscallHintRegistry::<PCS>::new().disable(syscalls::EMIT_EVENT).unwrap().build();
+
+        let hints = hint_processor.hints();
+        assert!(!hints.contains(syscalls::EMIT_EVENT));
+        assert!(hints.contains(syscalls::STORAGE_READ));
+    }
+
+    #[test]
+    fn test_unknown_hint() {
+        let result = SyscallHintRegistry::<PCS>::new().r#override("not_a_syscall", counting_storage_read);
+        assert!(matches!(result, Err(HintError::UnknownHint(_))));
+
+        let result = SyscallHintRegistry::<PCS>::new().disable("not_a_syscall");
+        assert!(matches!(result, Err(HintError::UnknownHint(_))));
+    }
+}
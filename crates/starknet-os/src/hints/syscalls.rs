@@ -1,19 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
-use cairo_vm::hint_processor::builtin_hint_processor::hint_utils::{get_ptr_from_var_name, insert_value_from_var_name};
+use cairo_vm::hint_processor::builtin_hint_processor::hint_utils::{
+    get_integer_from_var_name, get_ptr_from_var_name, insert_value_from_var_name,
+};
 use cairo_vm::hint_processor::hint_processor_definition::HintReference;
 use cairo_vm::serde::deserialize_program::ApTracking;
 use cairo_vm::types::exec_scope::ExecutionScopes;
+use cairo_vm::types::relocatable::{MaybeRelocatable, Relocatable};
 use cairo_vm::vm::errors::hint_errors::HintError;
 use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::Felt252;
 use indoc::indoc;
 
 use crate::execution::deprecated_syscall_handler::DeprecatedOsSyscallHandlerWrapper;
+use crate::execution::helper::ExecutionHelperWrapper;
 use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
+use crate::execution::syscall_handler_backend::SyscallHandlerBackend;
+use crate::execution::syscall_handler_utils::SyscallSelector;
 use crate::hints::vars;
 use crate::starknet::starknet_storage::PerContractStorage;
-use crate::utils::execute_coroutine;
+use crate::utils::{execute_coroutine, get_syscall_handler};
 
 pub const CALL_CONTRACT: &str = "syscall_handler.call_contract(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
@@ -26,7 +33,8 @@ pub async fn call_contract_async<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     syscall_handler.call_contract(syscall_ptr, vm).await?;
@@ -58,10 +66,11 @@ pub async fn delegate_call_async<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
-    syscall_handler.storage_write(syscall_ptr).await?;
+    syscall_handler.delegate_call(syscall_ptr, vm).await?;
 
     Ok(())
 }
@@ -82,6 +91,7 @@ where
 pub const DELEGATE_L1_HANDLER: &str =
     "syscall_handler.delegate_l1_handler(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn delegate_l1_handler<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -92,7 +102,13 @@ pub fn delegate_l1_handler<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.delegate_l1_handler(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.delegate_l1_handler(syscall_ptr, vm))?
@@ -100,6 +116,7 @@ where
 
 pub const DEPLOY: &str = "syscall_handler.deploy(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn deploy<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -110,7 +127,13 @@ pub fn deploy<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.deploy(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.deploy(syscall_ptr, vm))?
@@ -118,24 +141,89 @@ where
 
 pub const EMIT_EVENT: &str = "syscall_handler.emit_event(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// The deprecated and new syscall handlers share the same hint code, dispatch on the handler
+/// that is in scope.
 pub fn emit_event<PCS>(
-    _vm: &mut VirtualMachine,
+    vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError>
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
-    syscall_handler.emit_event();
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.emit_event(vm, syscall_ptr))?;
+    }
 
-    Ok(())
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    execute_coroutine(syscall_handler.emit_event())?
+}
+
+pub const GET_BLOCK_HASH: &str = "syscall_handler.get_block_hash(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn get_block_hash<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.get_block_hash(vm, syscall_ptr))?
+}
+
+pub const GET_CLASS_HASH_AT: &str = "syscall_handler.get_class_hash_at(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn get_class_hash_at<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.get_class_hash_at(vm, syscall_ptr))?
+}
+
+pub const GET_EXECUTION_INFO: &str =
+    "syscall_handler.get_execution_info(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn get_execution_info<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.get_execution_info(vm, syscall_ptr))?
 }
 
 pub const GET_BLOCK_NUMBER: &str = "syscall_handler.get_block_number(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_block_number<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -146,7 +234,13 @@ pub fn get_block_number<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_block_number(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.get_block_number(syscall_ptr, vm))?
@@ -155,6 +249,7 @@ where
 pub const GET_BLOCK_TIMESTAMP: &str =
     "syscall_handler.get_block_timestamp(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_block_timestamp<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -165,7 +260,13 @@ pub fn get_block_timestamp<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_block_timestamp(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.get_block_timestamp(syscall_ptr, vm))?
@@ -183,7 +284,8 @@ pub async fn get_caller_address_async<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     syscall_handler.get_caller_address(syscall_ptr, vm).await;
@@ -191,6 +293,7 @@ where
     Ok(())
 }
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_caller_address<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -201,12 +304,18 @@ pub fn get_caller_address<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_caller_address(vm, syscall_ptr))?;
+    }
+
     execute_coroutine(get_caller_address_async::<PCS>(vm, exec_scopes, ids_data, ap_tracking))?
 }
 
 pub const GET_CONTRACT_ADDRESS: &str =
     "syscall_handler.get_contract_address(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_contract_address<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -217,7 +326,13 @@ pub fn get_contract_address<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_contract_address(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.get_contract_address(syscall_ptr, vm))?
@@ -226,6 +341,7 @@ where
 pub const GET_SEQUENCER_ADDRESS: &str =
     "syscall_handler.get_sequencer_address(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_sequencer_address<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -236,7 +352,13 @@ pub fn get_sequencer_address<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_sequencer_address(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.get_sequencer_address(syscall_ptr, vm))?
@@ -244,6 +366,7 @@ where
 
 pub const GET_TX_INFO: &str = "syscall_handler.get_tx_info(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_tx_info<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -254,7 +377,13 @@ pub fn get_tx_info<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_tx_info(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.get_tx_info(syscall_ptr, vm))?
@@ -262,6 +391,7 @@ where
 
 pub const GET_TX_SIGNATURE: &str = "syscall_handler.get_tx_signature(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn get_tx_signature<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -272,14 +402,40 @@ pub fn get_tx_signature<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.get_tx_signature(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.get_tx_signature(syscall_ptr, vm))?
 }
 
+pub const KECCAK: &str = "syscall_handler.keccak(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn keccak<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.keccak(vm, syscall_ptr))?
+}
+
 pub const LIBRARY: &str = "syscall_handler.library_call(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn library_call<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
@@ -290,7 +446,13 @@ pub fn library_call<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.library_call(vm, syscall_ptr))?;
+    }
+
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.library_call(syscall_ptr, vm))?
@@ -309,7 +471,8 @@ pub fn library_call_l1_handler<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     execute_coroutine(syscall_handler.library_call_l1_handler(syscall_ptr, vm))?
@@ -317,41 +480,223 @@ where
 
 pub const REPLACE_CLASS: &str = "syscall_handler.replace_class(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn replace_class<PCS>(
-    _vm: &mut VirtualMachine,
+    vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError>
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.replace_class(vm, syscall_ptr))?;
+    }
 
-    syscall_handler.replace_class();
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
 
-    Ok(())
+    execute_coroutine(syscall_handler.replace_class())?
+}
+
+pub const SECP256K1_ADD: &str = "syscall_handler.secp256k1_add(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256k1_add<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256k1_add(vm, syscall_ptr))?
+}
+
+pub const SECP256K1_GET_XY: &str = "syscall_handler.secp256k1_get_xy(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256k1_get_xy<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256k1_get_xy(vm, syscall_ptr))?
+}
+
+pub const SECP256K1_MUL: &str = "syscall_handler.secp256k1_mul(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256k1_mul<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256k1_mul(vm, syscall_ptr))?
+}
+
+pub const SECP256K1_NEW: &str = "syscall_handler.secp256k1_new(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256k1_new<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256k1_new(vm, syscall_ptr))?
+}
+
+pub const SECP256R1_ADD: &str = "syscall_handler.secp256r1_add(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256r1_add<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256r1_add(vm, syscall_ptr))?
+}
+
+pub const SECP256R1_GET_POINT_FROM_X: &str =
+    "syscall_handler.secp256r1_get_point_from_x(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256r1_get_point_from_x<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256r1_get_point_from_x(vm, syscall_ptr))?
+}
+
+pub const SECP256R1_GET_XY: &str = "syscall_handler.secp256r1_get_xy(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256r1_get_xy<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256r1_get_xy(vm, syscall_ptr))?
+}
+
+pub const SECP256R1_MUL: &str = "syscall_handler.secp256r1_mul(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256r1_mul<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256r1_mul(vm, syscall_ptr))?
+}
+
+pub const SECP256R1_NEW: &str = "syscall_handler.secp256r1_new(segments=segments, syscall_ptr=ids.syscall_ptr)";
+
+pub fn secp256r1_new<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler =
+        get_syscall_handler::<OsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+
+    execute_coroutine(syscall_handler.secp256r1_new(vm, syscall_ptr))?
 }
 
 pub const SEND_MESSAGE_TO_L1: &str =
     "syscall_handler.send_message_to_l1(segments=segments, syscall_ptr=ids.syscall_ptr)";
 
+/// Dispatches on the syscall handler in scope, see `emit_event`.
 pub fn send_message_to_l1<PCS>(
-    _vm: &mut VirtualMachine,
+    vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
     _constants: &HashMap<String, Felt252>,
 ) -> Result<(), HintError>
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+        return execute_coroutine(syscall_handler.send_message_to_l1(vm, syscall_ptr))?;
+    }
 
-    syscall_handler.send_message_to_l1();
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
 
-    Ok(())
+    execute_coroutine(syscall_handler.send_message_to_l1())?
 }
 
 pub const STORAGE_READ: &str = "syscall_handler.storage_read(segments=segments, syscall_ptr=ids.syscall_ptr)";
@@ -365,8 +710,10 @@ pub async fn storage_read_async<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
+    check_syscall_ptr_initialized(vm, Some(syscall_handler.syscall_ptr().await), syscall_ptr)?;
 
     syscall_handler.storage_read(syscall_ptr, vm).await?;
 
@@ -397,7 +744,8 @@ pub async fn storage_write_async<PCS>(
 where
     PCS: PerContractStorage + 'static,
 {
-    let syscall_handler = exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)?;
+    let syscall_handler =
+        get_syscall_handler::<DeprecatedOsSyscallHandlerWrapper<PCS>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
     let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, vm, ids_data, ap_tracking)?;
 
     syscall_handler.storage_write(syscall_ptr).await?;
@@ -441,9 +789,83 @@ where
     insert_value_from_var_name(vars::ids::OS_CONTEXT, os_context, vm, ids_data, ap_tracking)?;
     insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, vm, ids_data, ap_tracking)?;
 
-    let syscall_handler: OsSyscallHandlerWrapper<PCS> = exec_scopes.get(vars::scopes::SYSCALL_HANDLER)?;
-    execute_coroutine(syscall_handler.set_syscall_ptr(syscall_ptr))?;
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        if execute_coroutine(syscall_handler.os_context_prologue())? {
+            write_builtin_ptrs_prologue(vm, os_context)?;
+        }
+    }
+
+    let syscall_handler = syscall_handler_backend::<PCS>(exec_scopes)?;
+    syscall_handler.set_syscall_ptr(syscall_ptr)?;
+
+    Ok(())
+}
+
+/// Writes a pointer to the start of each builtin segment at `os_context`, in the order of the
+/// builtin runners of the VM.
+fn write_builtin_ptrs_prologue(vm: &mut VirtualMachine, os_context: Relocatable) -> Result<(), HintError> {
+    let builtin_ptrs: Vec<MaybeRelocatable> =
+        vm.get_builtin_runners().iter().map(|runner| Relocatable::from((runner.base() as isize, 0)).into()).collect();
+    vm.load_data(os_context, &builtin_ptrs)?;
+    Ok(())
+}
+
+/// Fetches the syscall handler installed in the execution scopes, whichever its kind.
+///
+/// Other backends than the deprecated and new syscall handlers can be installed as an
+/// `Rc<dyn SyscallHandlerBackend>`.
+pub fn syscall_handler_backend<PCS>(exec_scopes: &ExecutionScopes) -> Result<Box<dyn SyscallHandlerBackend>, HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+        return Ok(Box::new(syscall_handler));
+    }
+    if let Ok(syscall_handler) =
+        exec_scopes.get::<DeprecatedOsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER)
+    {
+        return Ok(Box::new(syscall_handler));
+    }
+    let syscall_handler =
+        get_syscall_handler::<Rc<dyn SyscallHandlerBackend>>(exec_scopes, vars::scopes::SYSCALL_HANDLER)?;
+    Ok(Box::new(syscall_handler))
+}
+
+/// Executes the syscall located at `syscall_ptr` with the syscall handler in scope.
+pub fn execute_syscall<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    syscall_ptr: Relocatable,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let syscall_handler = syscall_handler_backend::<PCS>(exec_scopes)?;
+    check_syscall_ptr_initialized(vm, syscall_handler.syscall_ptr()?, syscall_ptr)?;
+    let selector = SyscallSelector::try_from(vm.get_integer(syscall_ptr)?.into_owned())?;
+    syscall_handler.execute(selector, syscall_ptr, vm)
+}
 
+/// Checks that `set_syscall_ptr` established the syscall pointer of the handler and that the
+/// request at `request_ptr` lies in an existing segment, instead of failing later on with a
+/// confusing VM error.
+pub(crate) fn check_syscall_ptr_initialized(
+    vm: &VirtualMachine,
+    handler_syscall_ptr: Option<Relocatable>,
+    request_ptr: Relocatable,
+) -> Result<(), HintError> {
+    if handler_syscall_ptr.is_none() {
+        return Err(HintError::CustomHint(
+            "syscall_ptr not initialized: set_syscall_ptr must run before any syscall".to_string().into_boxed_str(),
+        ));
+    }
+    let segment_exists = usize::try_from(request_ptr.segment_index)
+        .is_ok_and(|segment_index| segment_index < vm.segments.num_segments());
+    if !segment_exists {
+        return Err(HintError::CustomHint(
+            format!("syscall_ptr not initialized: {request_ptr} does not point to a valid segment").into_boxed_str(),
+        ));
+    }
     Ok(())
 }
 
@@ -464,393 +886,625 @@ pub const OS_LOGGER_ENTER_SYSCALL_PREPRARE_EXIT_SYSCALL: &str = indoc! {r#"
             selector=selector,
         )"#
 };
-pub fn os_logger_enter_syscall_preprare_exit_syscall(
-    _vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
-    _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    // TODO: os_logger enter/exit calls
-    Ok(())
-}
-
-pub fn exit_syscall(
-    _selector_name: &str,
-    _vm: &mut VirtualMachine,
-    _exec_scopes: &mut ExecutionScopes,
-    _ids_data: &HashMap<String, HintReference>,
-    _ap_tracking: &ApTracking,
-    _constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    // TODO: add logging
-    Ok(())
-}
-pub const EXIT_CALL_CONTRACT_SYSCALL: &str = "exit_syscall(selector=ids.CALL_CONTRACT_SELECTOR)";
-pub fn exit_call_contract_syscall(
+pub fn os_logger_enter_syscall_preprare_exit_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("CALL_CONTRACT_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    os_logger_enter_syscall::<PCS>(true, vm, exec_scopes, ids_data, ap_tracking)
+}
+
+/// Records the syscall identified by `ids.selector` on the OS logger of the execution helper.
+/// Syscalls that dispatch a contract call also count towards the call depth of the syscall handler.
+pub(crate) fn os_logger_enter_syscall<PCS>(
+    deprecated: bool,
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let selector = get_integer_from_var_name(vars::ids::SELECTOR, vm, ids_data, ap_tracking)?;
+    let selector = SyscallSelector::try_from(selector)?;
+    let builtin_ptrs = get_ptr_from_var_name(vars::ids::BUILTIN_PTRS, vm, ids_data, ap_tracking)?;
+    let range_check_ptr = get_ptr_from_var_name(vars::ids::RANGE_CHECK_PTR, vm, ids_data, ap_tracking)?;
+    let n_steps = vm.get_current_step();
+
+    if selector.dispatches_contract_call() {
+        if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+            execute_coroutine(syscall_handler.enter_contract_call())??;
+        }
+    }
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let mut eh_ref = execute_coroutine(execution_helper.execution_helper.write())?;
+    eh_ref.os_logger.enter_syscall(selector, deprecated, n_steps, builtin_ptrs, range_check_ptr)
+}
+
+pub fn exit_syscall<PCS>(
+    selector: SyscallSelector,
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    _constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    let builtin_ptrs = get_ptr_from_var_name(vars::ids::BUILTIN_PTRS, vm, ids_data, ap_tracking)?;
+    let range_check_ptr = get_ptr_from_var_name(vars::ids::RANGE_CHECK_PTR, vm, ids_data, ap_tracking)?;
+    let n_steps = vm.get_current_step();
+
+    let execution_helper = exec_scopes.get::<ExecutionHelperWrapper<PCS>>(vars::scopes::EXECUTION_HELPER)?;
+    let mut eh_ref = execute_coroutine(execution_helper.execution_helper.write())?;
+    let builtin_deltas = match eh_ref.os_logger.entered_builtin_ptrs() {
+        Some(entry_builtin_ptrs) => builtin_ptr_deltas(vm, entry_builtin_ptrs, builtin_ptrs)?,
+        None => HashMap::new(),
+    };
+    eh_ref.os_logger.exit_syscall(selector, n_steps, builtin_ptrs, range_check_ptr, &builtin_deltas)?;
+
+    if selector.dispatches_contract_call() {
+        if let Ok(syscall_handler) = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER) {
+            execute_coroutine(syscall_handler.exit_contract_call())??;
+            if matches!(
+                selector,
+                SyscallSelector::CallContract | SyscallSelector::DelegateL1Handler | SyscallSelector::LibraryCall
+            ) {
+                eh_ref.exit_call_context()?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of cells by which each builtin pointer advanced between the builtin pointers
+/// at `entry_builtin_ptrs` and `exit_builtin_ptrs`, keyed by builtin segment.
+fn builtin_ptr_deltas(
+    vm: &VirtualMachine,
+    entry_builtin_ptrs: Relocatable,
+    exit_builtin_ptrs: Relocatable,
+) -> Result<HashMap<isize, usize>, HintError> {
+    let builtin_segments: HashSet<isize> =
+        vm.get_builtin_runners().iter().map(|runner| runner.base() as isize).collect();
+
+    // The builtin pointers hold at most one pointer per builtin.
+    let mut deltas = HashMap::new();
+    for offset in 0..builtin_segments.len() {
+        let entry_ptr = vm.get_relocatable((entry_builtin_ptrs + offset)?);
+        let exit_ptr = vm.get_relocatable((exit_builtin_ptrs + offset)?);
+        if let (Ok(entry_ptr), Ok(exit_ptr)) = (entry_ptr, exit_ptr) {
+            if entry_ptr.segment_index == exit_ptr.segment_index && builtin_segments.contains(&entry_ptr.segment_index)
+            {
+                let delta = (exit_ptr - entry_ptr)?;
+                deltas.entry(entry_ptr.segment_index).or_insert(delta);
+            }
+        }
+    }
+    Ok(deltas)
+}
+
+pub const EXIT_CALL_CONTRACT_SYSCALL: &str = "exit_syscall(selector=ids.CALL_CONTRACT_SELECTOR)";
+pub fn exit_call_contract_syscall<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::CallContract, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 
 pub const EXIT_DELEGATE_CALL_SYSCALL: &str = "exit_syscall(selector=ids.DELEGATE_CALL_SELECTOR)";
-pub fn exit_delegate_call_syscall(
+pub fn exit_delegate_call_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("DELEGATE_CALL_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::DelegateCall, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_DELEGATE_L1_HANDLER_SYSCALL: &str = "exit_syscall(selector=ids.DELEGATE_L1_HANDLER_SELECTOR)";
-pub fn exit_delegate_l1_handler_syscall(
+pub fn exit_delegate_l1_handler_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("DELEGATE_L1_HANDLER_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::DelegateL1Handler, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_DEPLOY_SYSCALL: &str = "exit_syscall(selector=ids.DEPLOY_SELECTOR)";
-pub fn exit_deploy_syscall(
+pub fn exit_deploy_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("DEPLOY_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Deploy, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_EMIT_EVENT_SYSCALL: &str = "exit_syscall(selector=ids.EMIT_EVENT_SELECTOR)";
 
-pub fn exit_emit_event_syscall(
+pub fn exit_emit_event_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("EMIT_EVENT_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::EmitEvent, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_BLOCK_HASH_SYSCALL: &str = "exit_syscall(selector=ids.GET_BLOCK_HASH_SELECTOR)";
 
-pub fn exit_get_block_hash_syscall(
+pub fn exit_get_block_hash_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_BLOCK_HASH_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetBlockHash, vm, exec_scopes, ids_data, ap_tracking, constants)
+}
+
+pub const EXIT_GET_CLASS_HASH_AT_SYSCALL: &str = "exit_syscall(selector=ids.GET_CLASS_HASH_AT_SELECTOR)";
+
+pub fn exit_get_class_hash_at_syscall<PCS>(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+    constants: &HashMap<String, Felt252>,
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetClassHashAt, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 
 pub const EXIT_GET_BLOCK_NUMBER_SYSCALL: &str = "exit_syscall(selector=ids.GET_BLOCK_NUMBER_SELECTOR)";
-pub fn exit_get_block_number_syscall(
+pub fn exit_get_block_number_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_BLOCK_NUMBER_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetBlockNumber, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 
 pub const EXIT_GET_BLOCK_TIMESTAMP_SYSCALL: &str = "exit_syscall(selector=ids.GET_BLOCK_TIMESTAMP_SELECTOR)";
 
-pub fn exit_get_block_timestamp_syscall(
+pub fn exit_get_block_timestamp_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_BLOCK_TIMESTAMP_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetBlockTimestamp, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_CALLER_ADDRESS_SYSCALL: &str = "exit_syscall(selector=ids.GET_CALLER_ADDRESS_SELECTOR)";
 
-pub fn exit_get_caller_address_syscall(
+pub fn exit_get_caller_address_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_CALLER_ADDRESS_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetCallerAddress, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_CONTRACT_ADDRESS_SYSCALL: &str = "exit_syscall(selector=ids.GET_CONTRACT_ADDRESS_SELECTOR)";
 
-pub fn exit_get_contract_address_syscall(
+pub fn exit_get_contract_address_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_CONTRACT_ADDRESS_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetContractAddress, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_EXECUTION_INFO_SYSCALL: &str = "exit_syscall(selector=ids.GET_EXECUTION_INFO_SELECTOR)";
 
-pub fn exit_get_execution_info_syscall(
+pub fn exit_get_execution_info_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_EXECUTION_INFO_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetExecutionInfo, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_SEQUENCER_ADDRESS_SYSCALL: &str = "exit_syscall(selector=ids.GET_SEQUENCER_ADDRESS_SELECTOR)";
 
-pub fn exit_get_sequencer_address_syscall(
+pub fn exit_get_sequencer_address_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_SEQUENCER_ADDRESS_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetSequencerAddress, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_TX_INFO_SYSCALL: &str = "exit_syscall(selector=ids.GET_TX_INFO_SELECTOR)";
 
-pub fn exit_get_tx_info_syscall(
+pub fn exit_get_tx_info_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_TX_INFO_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetTxInfo, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_GET_TX_SIGNATURE_SYSCALL: &str = "exit_syscall(selector=ids.GET_TX_SIGNATURE_SELECTOR)";
 
-pub fn exit_get_tx_signature_syscall(
+pub fn exit_get_tx_signature_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("GET_TX_SIGNATURE_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::GetTxSignature, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_KECCAK_SYSCALL: &str = "exit_syscall(selector=ids.KECCAK_SELECTOR)";
 
-pub fn exit_keccak_syscall(
+pub fn exit_keccak_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("KECCAK_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Keccak, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_LIBRARY_CALL_L1_HANDLER_SYSCALL: &str = "exit_syscall(selector=ids.LIBRARY_CALL_L1_HANDLER_SELECTOR)";
 
-pub fn exit_library_call_l1_handler_syscall(
+pub fn exit_library_call_l1_handler_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("LIBRARY_CALL_L1_HANDLER_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::LibraryCallL1Handler, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_LIBRARY_CALL_SYSCALL: &str = "exit_syscall(selector=ids.LIBRARY_CALL_SELECTOR)";
 
-pub fn exit_library_call_syscall(
+pub fn exit_library_call_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("LIBRARY_CALL_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::LibraryCall, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_REPLACE_CLASS_SYSCALL: &str = "exit_syscall(selector=ids.REPLACE_CLASS_SELECTOR)";
 
-pub fn exit_replace_class_syscall(
+pub fn exit_replace_class_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("REPLACE_CLASS_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::ReplaceClass, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SHA256_PROCESS_BLOCK_SYSCALL: &str = "exit_syscall(selector=ids.SHA256_PROCESS_BLOCK_SELECTOR)";
 
-pub fn exit_sha256_process_block_syscall(
+pub fn exit_sha256_process_block_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SHA256_PROCESS_BLOCK_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Sha256ProcessBlock, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256K1_ADD_SYSCALL: &str = "exit_syscall(selector=ids.SECP256K1_ADD_SELECTOR)";
 
-pub fn exit_secp256k1_add_syscall(
+pub fn exit_secp256k1_add_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256K1_ADD_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256k1Add, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256K1_GET_POINT_FROM_X_SYSCALL: &str =
     "exit_syscall(selector=ids.SECP256K1_GET_POINT_FROM_X_SELECTOR)";
 
-pub fn exit_secp256k1_get_point_from_x_syscall(
+pub fn exit_secp256k1_get_point_from_x_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256K1_GET_POINT_FROM_X_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256k1GetPointFromX, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256K1_GET_XY_SYSCALL: &str = "exit_syscall(selector=ids.SECP256K1_GET_XY_SELECTOR)";
 
-pub fn exit_secp256k1_get_xy_syscall(
+pub fn exit_secp256k1_get_xy_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256K1_GET_XY_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256k1GetXy, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256K1_MUL_SYSCALL: &str = "exit_syscall(selector=ids.SECP256K1_MUL_SELECTOR)";
 
-pub fn exit_secp256k1_mul_syscall(
+pub fn exit_secp256k1_mul_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256K1_MUL_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256k1Mul, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256K1_NEW_SYSCALL: &str = "exit_syscall(selector=ids.SECP256K1_NEW_SELECTOR)";
 
-pub fn exit_secp256k1_new_syscall(
+pub fn exit_secp256k1_new_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256K1_NEW_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256k1New, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256R1_ADD_SYSCALL: &str = "exit_syscall(selector=ids.SECP256R1_ADD_SELECTOR)";
 
-pub fn exit_secp256r1_add_syscall(
+pub fn exit_secp256r1_add_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256R1_ADD_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256r1Add, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256R1_GET_POINT_FROM_X_SYSCALL: &str =
     "exit_syscall(selector=ids.SECP256R1_GET_POINT_FROM_X_SELECTOR)";
 
-pub fn exit_secp256r1_get_point_from_x_syscall(
+pub fn exit_secp256r1_get_point_from_x_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256R1_GET_POINT_FROM_X_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256r1GetPointFromX, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256R1_GET_XY_SYSCALL: &str = "exit_syscall(selector=ids.SECP256R1_GET_XY_SELECTOR)";
 
-pub fn exit_secp256r1_get_xy_syscall(
+pub fn exit_secp256r1_get_xy_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256R1_GET_XY_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256r1GetXy, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256R1_MUL_SYSCALL: &str = "exit_syscall(selector=ids.SECP256R1_MUL_SELECTOR)";
 
-pub fn exit_secp256r1_mul_syscall(
+pub fn exit_secp256r1_mul_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256R1_MUL_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256r1Mul, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SECP256R1_NEW_SYSCALL: &str = "exit_syscall(selector=ids.SECP256R1_NEW_SELECTOR)";
 
-pub fn exit_secp256r1_new_syscall(
+pub fn exit_secp256r1_new_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SECP256R1_NEW_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::Secp256r1New, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_SEND_MESSAGE_TO_L1_SYSCALL: &str = "exit_syscall(selector=ids.SEND_MESSAGE_TO_L1_SELECTOR)";
 
-pub fn exit_send_message_to_l1_syscall(
+pub fn exit_send_message_to_l1_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("SEND_MESSAGE_TO_L1_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::SendMessageToL1, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_STORAGE_READ_SYSCALL: &str = "exit_syscall(selector=ids.STORAGE_READ_SELECTOR)";
 
-pub fn exit_storage_read_syscall(
+pub fn exit_storage_read_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("STORAGE_READ_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::StorageRead, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 pub const EXIT_STORAGE_WRITE_SYSCALL: &str = "exit_syscall(selector=ids.STORAGE_WRITE_SELECTOR)";
 
-pub fn exit_storage_write_syscall(
+pub fn exit_storage_write_syscall<PCS>(
     vm: &mut VirtualMachine,
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
     constants: &HashMap<String, Felt252>,
-) -> Result<(), HintError> {
-    exit_syscall("STORAGE_WRITE_SELECTOR", vm, exec_scopes, ids_data, ap_tracking, constants)
+) -> Result<(), HintError>
+where
+    PCS: PerContractStorage + 'static,
+{
+    exit_syscall::<PCS>(SyscallSelector::StorageWrite, vm, exec_scopes, ids_data, ap_tracking, constants)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
     use blockifier::context::BlockContext;
-    use cairo_vm::types::relocatable::Relocatable;
+    use blockifier::execution::call_info::{CallInfo, Retdata};
+    use blockifier::execution::contract_class::ContractClass;
+    use blockifier::execution::entry_point::CallType;
+    use blockifier::execution::entry_point_execution::CallResult;
+    use blockifier::transaction::objects::{
+        CommonAccountFields, CurrentTransactionInfo, DeprecatedTransactionInfo, TransactionInfo,
+    };
+    use cairo_vm::vm::runners::builtin_runner::{BuiltinRunner, HashBuiltinRunner, OutputBuiltinRunner};
+    use num_bigint::BigUint;
+    use num_traits::ToPrimitive;
     use rstest::{fixture, rstest};
+    use starknet_api::block::{BlockNumber, BlockTimestamp};
+    use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+    use starknet_api::data_availability::DataAvailabilityMode;
+    use starknet_api::transaction::{
+        AccountDeploymentData, PaymasterData, Resource, ResourceBoundsMapping, Tip, TransactionHash,
+        TransactionSignature, TransactionVersion,
+    };
+    use starknet_os_types::casm_contract_class::GenericCasmContractClass;
+    use starknet_os_types::chain_id::chain_id_to_felt;
 
     use super::*;
+    use crate::cairo_types::new_syscalls::{BlockInfo, ExecutionInfo, ResourceBounds, TxInfo};
+    use crate::cairo_types::syscalls::{
+        CallContract, CallContractRequest, CallContractResponse, EcCoordinate, GetSequencerAddress,
+        GetSequencerAddressResponse,
+    };
+    use crate::config::BLOCK_HASH_CONTRACT_ADDRESS;
     use crate::crypto::pedersen::PedersenHash;
-    use crate::execution::helper::ContractStorageMap;
+    use crate::execution::helper::{
+        ContractStorageMap, L1ToL2Message, L2ToL1Message, OrderedEvent, RunReport, StarknetVersion,
+        SyscallTranscriptEntry,
+    };
+    use crate::execution::syscall_handler::{calculate_deployed_contract_address, DeployRequest};
+    use crate::execution::syscall_handler_utils::{SyscallGasCosts, KNOWN_SYSCALL_SELECTORS};
     use crate::hints::tests::tests::{block_context, old_block_number_and_hash};
-    use crate::starknet::starknet_storage::OsSingleStarknetStorage;
+    use crate::hints::HintImpl;
+    use crate::starknet::starknet_storage::{OsSingleStarknetStorage, StorageLeaf};
+    use crate::starkware_utils::commitment_tree::base_types::Height;
+    use crate::starkware_utils::commitment_tree::binary_fact_tree::BinaryFactTree;
+    use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
     use crate::storage::dict_storage::DictStorage;
+    use crate::storage::storage::{FactFetchingContext, HashFunctionType};
     use crate::ExecutionHelperWrapper;
 
     #[allow(clippy::upper_case_acronyms)]
@@ -860,10 +1514,18 @@ mod tests {
 
     #[fixture]
     fn exec_scopes(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) -> ExecutionScopes {
+        exec_scopes_with_gas_costs(&block_context, old_block_number_and_hash, SyscallGasCosts::default())
+    }
+
+    fn exec_scopes_with_gas_costs(
+        block_context: &BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        gas_costs: SyscallGasCosts,
+    ) -> ExecutionScopes {
         let execution_infos = vec![];
         let exec_helper =
-            EHW::new(ContractStorageMap::default(), execution_infos, &block_context, None, old_block_number_and_hash);
-        let syscall_handler = OsSyscallHandlerWrapper::new(exec_helper);
+            EHW::new(ContractStorageMap::default(), execution_infos, block_context, None, old_block_number_and_hash);
+        let syscall_handler = OsSyscallHandlerWrapper::with_gas_costs(exec_helper, gas_costs);
 
         let mut exec_scopes = ExecutionScopes::new();
         exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, syscall_handler);
@@ -899,4 +1561,2552 @@ mod tests {
             exec_scopes.get(vars::scopes::SYSCALL_HANDLER).unwrap();
         assert_eq!(syscall_handler.syscall_ptr().await, Some(syscall_ptr));
     }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_set_syscall_ptr_os_context_prologue(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+
+        let ids_data = HashMap::from([
+            (vars::ids::OS_CONTEXT.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1)),
+        ]);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+
+        let mut output_builtin_runner = OutputBuiltinRunner::new(true);
+        output_builtin_runner.initialize_segments(&mut vm.segments);
+        let mut pedersen_builtin_runner = HashBuiltinRunner::new(Some(32), true);
+        pedersen_builtin_runner.initialize_segments(&mut vm.segments);
+        vm.builtin_runners.push(BuiltinRunner::Output(output_builtin_runner));
+        vm.builtin_runners.push(BuiltinRunner::Hash(pedersen_builtin_runner));
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        syscall_handler.set_os_context_prologue(true).await;
+
+        let ap_tracking = ApTracking::new();
+        set_syscall_ptr::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        let os_context = get_ptr_from_var_name(vars::ids::OS_CONTEXT, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(os_context, Relocatable::from((4, 0)));
+        assert_eq!(vm.get_relocatable(os_context).unwrap(), Relocatable::from((2, 0)));
+        assert_eq!(vm.get_relocatable((os_context + 1usize).unwrap()).unwrap(), Relocatable::from((3, 0)));
+        assert!(vm.get_maybe(&(os_context + 2usize).unwrap()).is_none());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_storage_read_before_set_syscall_ptr(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_ptr = vm.add_memory_segment();
+        vm.insert_value(syscall_ptr, Felt252::from_bytes_be_slice(b"StorageRead")).unwrap();
+
+        let result = execute_syscall::<PCS>(&mut vm, &mut exec_scopes, syscall_ptr);
+        match result {
+            Err(HintError::CustomHint(message)) => {
+                assert!(message.starts_with("syscall_ptr not initialized"), "{message}")
+            }
+            other => panic!("expected an uninitialized syscall_ptr error, got {other:?}"),
+        }
+
+        // Once the pointer is set, a request outside of the existing segments is rejected as well.
+        let syscall_handler: OsSyscallHandlerWrapper<PCS> = exec_scopes.get(vars::scopes::SYSCALL_HANDLER).unwrap();
+        syscall_handler.set_syscall_ptr(syscall_ptr).await.unwrap();
+        let result = execute_syscall::<PCS>(&mut vm, &mut exec_scopes, Relocatable::from((5, 0)));
+        match result {
+            Err(HintError::CustomHint(message)) => {
+                assert!(message.starts_with("syscall_ptr not initialized"), "{message}")
+            }
+            other => panic!("expected an uninitialized syscall_ptr error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_call_contract_without_syscall_handler() {
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+
+        let result =
+            call_contract::<PCS>(&mut vm, &mut exec_scopes, &HashMap::new(), &ApTracking::new(), &HashMap::new());
+        match result {
+            Err(HintError::CustomHint(message)) => {
+                assert!(message.starts_with("syscall handler not installed in execution scopes"), "{message}")
+            }
+            other => panic!("expected a missing syscall handler error, got {other:?}"),
+        }
+
+        // The hints that work with any kind of syscall handler report it the same way
+        let result = execute_syscall::<PCS>(&mut vm, &mut exec_scopes, Relocatable::from((0, 0)));
+        match result {
+            Err(HintError::CustomHint(message)) => {
+                assert!(message.starts_with("syscall handler not installed in execution scopes"), "{message}")
+            }
+            other => panic!("expected a missing syscall handler error, got {other:?}"),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_delegate_call(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        // The delegated contract address, as found in the CallContractRequest struct.
+        let delegated_address = Felt252::from(0x1234);
+        vm.insert_value((syscall_ptr + CallContractRequest::contract_address_offset()).unwrap(), delegated_address)
+            .unwrap();
+
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash);
+        let call_results = vec![CallResult {
+            failed: false,
+            retdata: Retdata(vec![Felt252::THREE, Felt252::TWO, Felt252::ONE]),
+            gas_consumed: 1,
+        }];
+        exec_helper.execution_helper.write().await.result_iter = call_results.into_iter();
+        exec_helper.execution_helper.write().await.execute_code_read_iter = vec![Felt252::from(42)].into_iter();
+
+        let syscall_handler = DeprecatedOsSyscallHandlerWrapper::new(
+            exec_helper.clone(),
+            syscall_ptr,
+            block_context.block_info().clone(),
+        );
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, syscall_handler);
+
+        delegate_call::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        let retdata_size = vm
+            .get_integer(
+                (syscall_ptr + CallContract::response_offset() + CallContractResponse::retdata_size_offset()).unwrap(),
+            )
+            .unwrap()
+            .into_owned();
+        assert_eq!(retdata_size, Felt252::THREE);
+        let retdata = vm
+            .get_relocatable(
+                (syscall_ptr + CallContract::response_offset() + CallContractResponse::retdata_offset()).unwrap(),
+            )
+            .unwrap();
+        let retdata = vm.get_integer_range(retdata, 3).unwrap();
+        assert_eq!(
+            retdata.into_iter().map(|felt| felt.into_owned()).collect::<Vec<_>>(),
+            vec![Felt252::THREE, Felt252::TWO, Felt252::ONE]
+        );
+
+        // The delegate call must not touch storage: no storage read was consumed and no storage
+        // was created for any contract.
+        let eh_ref = exec_helper.execution_helper.read().await;
+        assert_eq!(eh_ref.execute_code_read_iter.clone().collect::<Vec<_>>(), vec![Felt252::from(42)]);
+        assert!(eh_ref.storage_by_address.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_delegate_l1_handler(block_context: BlockContext, old_block_number_and_hash: (Felt252, Felt252)) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        let message = L1ToL2Message {
+            from_address: Felt252::from(0xabcd),
+            to_address: Felt252::from(0x1234),
+            selector: Felt252::from(0x5678),
+            payload: vec![Felt252::ONE, Felt252::TWO],
+        };
+        let calldata: Vec<MaybeRelocatable> =
+            [message.from_address, Felt252::ONE, Felt252::TWO].into_iter().map(MaybeRelocatable::from).collect();
+        let calldata_ptr = vm.add_memory_segment();
+        vm.load_data(calldata_ptr, &calldata).unwrap();
+        vm.insert_value((syscall_ptr + CallContractRequest::contract_address_offset()).unwrap(), message.to_address)
+            .unwrap();
+        vm.insert_value((syscall_ptr + CallContractRequest::function_selector_offset()).unwrap(), message.selector)
+            .unwrap();
+        vm.insert_value((syscall_ptr + CallContractRequest::calldata_size_offset()).unwrap(), Felt252::THREE).unwrap();
+        vm.insert_value((syscall_ptr + CallContractRequest::calldata_offset()).unwrap(), calldata_ptr).unwrap();
+
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash)
+                .with_l1_message_validation();
+        let call_results = vec![CallResult { failed: false, retdata: Retdata(vec![]), gas_consumed: 1 }];
+        exec_helper.execution_helper.write().await.result_iter = call_results.into_iter();
+
+        let syscall_handler = DeprecatedOsSyscallHandlerWrapper::new(
+            exec_helper.clone(),
+            syscall_ptr,
+            block_context.block_info().clone(),
+        );
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, syscall_handler);
+
+        // The message was never sent from L1
+        let result = delegate_l1_handler::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        assert!(result.is_err());
+        assert!(exec_helper.consumed_l1_messages().await.is_empty());
+
+        exec_helper.register_l1_to_l2_message(message.clone()).await;
+        delegate_l1_handler::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        assert_eq!(exec_helper.consumed_l1_messages().await, vec![message.clone()]);
+
+        // Replaying the same message must fail
+        let result = delegate_l1_handler::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        assert!(result.is_err());
+        assert_eq!(exec_helper.consumed_l1_messages().await, vec![message]);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_delegate_l1_handler_new_syscall(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        {
+            let mut eh_ref = exec_wrapper.execution_helper.write().await;
+            eh_ref.validate_l1_messages = true;
+            eh_ref.result_iter =
+                vec![CallResult { failed: false, retdata: Retdata(vec![]), gas_consumed: 1 }].into_iter();
+        }
+
+        let message = L1ToL2Message {
+            from_address: Felt252::from(0xabcd),
+            to_address: Felt252::from(0x1234),
+            selector: Felt252::from(0x5678),
+            payload: vec![Felt252::ONE, Felt252::TWO],
+        };
+        let (calldata_start, calldata_end) = write_felts(&mut vm, &[message.from_address, Felt252::ONE, Felt252::TWO]);
+        let request = [message.to_address.into(), message.selector.into(), calldata_start.into(), calldata_end.into()];
+
+        // The message was never sent from L1
+        let error =
+            try_run_syscall_hint(&mut vm, &mut exec_scopes, delegate_l1_handler::<PCS>, b"DelegateL1Handler", &request)
+                .unwrap_err()
+                .to_string();
+        assert!(error.contains("No pending L1 message"), "{error}");
+        assert!(exec_wrapper.consumed_l1_messages().await.is_empty());
+
+        exec_wrapper.register_l1_to_l2_message(message.clone()).await;
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, delegate_l1_handler::<PCS>, b"DelegateL1Handler", &request, 1);
+        assert_eq!(response, vec![Felt252::ZERO.into()]);
+        assert_eq!(exec_wrapper.consumed_l1_messages().await, vec![message]);
+    }
+
+    #[rstest]
+    #[case::historical_block(-5, Felt252::from(0xabc))]
+    #[case::oldest_accessible_block(0, Felt252::from(0xdef))]
+    #[case::disallowed_window(1, Felt252::ZERO)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_block_hash(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] offset_from_old_block: i64,
+        #[case] expected_block_hash: Felt252,
+    ) {
+        let (old_block_number, _) = old_block_number_and_hash;
+
+        // The block hash contract maps block numbers to block hashes. The hash of the most recent
+        // block is present in storage but falls within the disallowed window.
+        let mut ffc = FactFetchingContext::<_, PedersenHash>::new(DictStorage::default());
+        let tree = PatriciaTree::empty_tree(&mut ffc, Height(251), StorageLeaf::empty()).await.unwrap();
+        let mut block_hash_storage = OsSingleStarknetStorage::new(tree.clone(), tree, &[], ffc).await.unwrap();
+        block_hash_storage.write((old_block_number - Felt252::from(5)).to_biguint(), Felt252::from(0xabc));
+        block_hash_storage.write(old_block_number.to_biguint(), Felt252::from(0xdef));
+        block_hash_storage.write((old_block_number + Felt252::ONE).to_biguint(), Felt252::from(0x123));
+        let contract_storage_map =
+            ContractStorageMap::from([(Felt252::from(BLOCK_HASH_CONTRACT_ADDRESS), block_hash_storage)]);
+
+        let exec_helper = EHW::new(contract_storage_map, vec![], &block_context, None, old_block_number_and_hash);
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, OsSyscallHandlerWrapper::new(exec_helper));
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        // Request: selector, gas, block number.
+        let requested_block_number = old_block_number + Felt252::from(offset_from_old_block);
+        vm.insert_value(syscall_ptr, Felt252::from_bytes_be_slice(b"GetBlockHash")).unwrap();
+        vm.insert_value((syscall_ptr + 1usize).unwrap(), Felt252::from(1_000_000)).unwrap();
+        vm.insert_value((syscall_ptr + 2usize).unwrap(), requested_block_number).unwrap();
+
+        get_block_hash::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        // Response: remaining gas, failure flag, block hash.
+        let failure_flag = vm.get_integer((syscall_ptr + 4usize).unwrap()).unwrap().into_owned();
+        assert_eq!(failure_flag, Felt252::ZERO);
+        let block_hash = vm.get_integer((syscall_ptr + 5usize).unwrap()).unwrap().into_owned();
+        assert_eq!(block_hash, expected_block_hash);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_block_hash_with_provider(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let (old_block_number, _) = old_block_number_and_hash;
+        let old_block_number = old_block_number.to_u64().unwrap();
+        let consulted_blocks = Rc::new(RefCell::new(vec![]));
+        let consulted_blocks_clone = consulted_blocks.clone();
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash)
+                .with_block_hash_provider(move |block_number| {
+                    consulted_blocks_clone.borrow_mut().push(block_number);
+                    (block_number == old_block_number - 5).then_some(Felt252::from(0xabc))
+                });
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, OsSyscallHandlerWrapper::new(exec_helper));
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let request = [Felt252::from(old_block_number - 5).into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_block_hash::<PCS>, b"GetBlockHash", &request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::from(0xabc).into()]);
+
+        // Blocks within the disallowed window resolve to zero without consulting the provider
+        let request = [Felt252::from(old_block_number + 1).into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_block_hash::<PCS>, b"GetBlockHash", &request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::ZERO.into()]);
+
+        let request = [Felt252::from(old_block_number).into()];
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, get_block_hash::<PCS>, b"GetBlockHash", &request);
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains(&format!("no hash for block {old_block_number}")), "{error}");
+
+        assert_eq!(*consulted_blocks.borrow(), vec![old_block_number - 5, old_block_number]);
+    }
+
+    #[rstest]
+    #[case::accessible(5, Felt252::from(0xabc))]
+    #[case::disallowed_window(4, Felt252::ZERO)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_block_hash_lower_bound(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] blocks_before_current: u64,
+        #[case] expected_block_hash: Felt252,
+    ) {
+        let current_block_number = block_context.block_info().block_number.0;
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash)
+                .with_block_hash_lower_bound(5)
+                .with_block_hash_provider(|_| Some(Felt252::from(0xabc)));
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, OsSyscallHandlerWrapper::new(exec_helper));
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // Within the default window of `STORED_BLOCK_HASH_BUFFER` blocks either way
+        let request = [Felt252::from(current_block_number - blocks_before_current).into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_block_hash::<PCS>, b"GetBlockHash", &request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), expected_block_hash.into()]);
+    }
+
+    /// Writes `values` in a new segment and returns the start and end pointers of the segment.
+    fn write_felts(vm: &mut VirtualMachine, values: &[Felt252]) -> (Relocatable, Relocatable) {
+        let start = vm.add_memory_segment();
+        for (i, value) in values.iter().enumerate() {
+            vm.insert_value((start + i).unwrap(), *value).unwrap();
+        }
+        (start, (start + values.len()).unwrap())
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_execution_info(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // Execution info of a v3 invoke, as built by the OS when entering the call.
+        let block_number = Felt252::from(1_000_000);
+        let block_timestamp = Felt252::from(1_704_067_200);
+        let sequencer_address = Felt252::from(0x1000);
+        let (block_info_ptr, _) = write_felts(&mut vm, &[block_number, block_timestamp, sequencer_address]);
+
+        let l1_gas_bounds = [Felt252::from_bytes_be_slice(b"L1_GAS"), Felt252::from(5_000), Felt252::from(10)];
+        let l2_gas_bounds = [Felt252::from_bytes_be_slice(b"L2_GAS"), Felt252::ZERO, Felt252::ZERO];
+        let (resource_bounds_start, resource_bounds_end) =
+            write_felts(&mut vm, &[l1_gas_bounds, l2_gas_bounds].concat());
+        let (signature_start, signature_end) = write_felts(&mut vm, &[Felt252::from(0x5151), Felt252::from(0x5252)]);
+        let (paymaster_data_start, paymaster_data_end) = write_felts(&mut vm, &[]);
+        let (account_deployment_data_start, account_deployment_data_end) = write_felts(&mut vm, &[]);
+
+        let version = Felt252::THREE;
+        let account_contract_address = Felt252::from(0x2000);
+        let transaction_hash = Felt252::from(0xabcdef);
+        let chain_id = Felt252::from_bytes_be_slice(b"SN_SEPOLIA");
+        let nonce = Felt252::from(7);
+        let tip = Felt252::from(42);
+        let tx_info: Vec<MaybeRelocatable> = vec![
+            version.into(),
+            account_contract_address.into(),
+            // max_fee is not used by v3 transactions
+            Felt252::ZERO.into(),
+            signature_start.into(),
+            signature_end.into(),
+            transaction_hash.into(),
+            chain_id.into(),
+            nonce.into(),
+            resource_bounds_start.into(),
+            resource_bounds_end.into(),
+            tip.into(),
+            paymaster_data_start.into(),
+            paymaster_data_end.into(),
+            // L1 data availability for both nonce and fee
+            Felt252::ZERO.into(),
+            Felt252::ZERO.into(),
+            account_deployment_data_start.into(),
+            account_deployment_data_end.into(),
+        ];
+        assert_eq!(tx_info.len(), TxInfo::cairo_size());
+        let tx_info_ptr = vm.add_memory_segment();
+        vm.load_data(tx_info_ptr, &tx_info).unwrap();
+
+        let caller_address = Felt252::from(0x3000);
+        let contract_address = Felt252::from(0x4000);
+        let entry_point_selector = Felt252::from(0x5000);
+        let execution_info: Vec<MaybeRelocatable> = vec![
+            block_info_ptr.into(),
+            tx_info_ptr.into(),
+            caller_address.into(),
+            contract_address.into(),
+            entry_point_selector.into(),
+        ];
+        let execution_info_ptr = vm.add_memory_segment();
+        vm.load_data(execution_info_ptr, &execution_info).unwrap();
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        // No call info is needed to hand out the execution info, set the pointer as `enter_call` does.
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.call_execution_info_ptr = Some(execution_info_ptr);
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        // Request: selector, gas.
+        vm.insert_value(syscall_ptr, Felt252::from_bytes_be_slice(b"GetExecutionInfo")).unwrap();
+        vm.insert_value((syscall_ptr + 1usize).unwrap(), Felt252::from(1_000_000)).unwrap();
+
+        get_execution_info::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        // Response: remaining gas, failure flag, execution info pointer.
+        let failure_flag = vm.get_integer((syscall_ptr + 3usize).unwrap()).unwrap().into_owned();
+        assert_eq!(failure_flag, Felt252::ZERO);
+        let response_ptr = vm.get_relocatable((syscall_ptr + 4usize).unwrap()).unwrap();
+        assert_eq!(response_ptr, execution_info_ptr);
+
+        let get_felt = |vm: &VirtualMachine, ptr: Relocatable, offset: usize| {
+            vm.get_integer((ptr + offset).unwrap()).unwrap().into_owned()
+        };
+        let get_ptr =
+            |vm: &VirtualMachine, ptr: Relocatable, offset: usize| vm.get_relocatable((ptr + offset).unwrap()).unwrap();
+
+        assert_eq!(get_felt(&vm, response_ptr, ExecutionInfo::caller_address_offset()), caller_address);
+        assert_eq!(get_felt(&vm, response_ptr, ExecutionInfo::contract_address_offset()), contract_address);
+        assert_eq!(get_felt(&vm, response_ptr, ExecutionInfo::selector_offset()), entry_point_selector);
+
+        let block_info = get_ptr(&vm, response_ptr, ExecutionInfo::block_info_offset());
+        assert_eq!(get_felt(&vm, block_info, BlockInfo::block_number_offset()), block_number);
+        assert_eq!(get_felt(&vm, block_info, BlockInfo::block_timestamp_offset()), block_timestamp);
+        assert_eq!(get_felt(&vm, block_info, BlockInfo::sequencer_address_offset()), sequencer_address);
+
+        let tx_info = get_ptr(&vm, response_ptr, ExecutionInfo::tx_info_offset());
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::version_offset()), version);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::account_contract_address_offset()), account_contract_address);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::max_fee_offset()), Felt252::ZERO);
+        assert_eq!(get_ptr(&vm, tx_info, TxInfo::signature_start_offset()), signature_start);
+        assert_eq!(get_ptr(&vm, tx_info, TxInfo::signature_end_offset()), signature_end);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::transaction_hash_offset()), transaction_hash);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::chain_id_offset()), chain_id);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::nonce_offset()), nonce);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::tip_offset()), tip);
+        assert_eq!(get_ptr(&vm, tx_info, TxInfo::paymaster_data_start_offset()), paymaster_data_start);
+        assert_eq!(get_ptr(&vm, tx_info, TxInfo::account_deployment_data_end_offset()), account_deployment_data_end);
+
+        let bounds_start = get_ptr(&vm, tx_info, TxInfo::resource_bounds_start_offset());
+        let bounds_end = get_ptr(&vm, tx_info, TxInfo::resource_bounds_end_offset());
+        assert_eq!((bounds_end - bounds_start).unwrap(), 2 * ResourceBounds::cairo_size());
+        let l2_gas = (bounds_start + ResourceBounds::cairo_size()).unwrap();
+        assert_eq!(get_felt(&vm, bounds_start, ResourceBounds::resource_offset()), l1_gas_bounds[0]);
+        assert_eq!(get_felt(&vm, bounds_start, ResourceBounds::max_amount_offset()), l1_gas_bounds[1]);
+        assert_eq!(get_felt(&vm, bounds_start, ResourceBounds::max_price_per_unit_offset()), l1_gas_bounds[2]);
+        assert_eq!(get_felt(&vm, l2_gas, ResourceBounds::resource_offset()), l2_gas_bounds[0]);
+    }
+
+    #[rstest]
+    #[case::v1(StarknetVersion::V1, TxInfo::resource_bounds_start_offset())]
+    #[case::v2(StarknetVersion::V2, TxInfo::resource_bounds_start_offset())]
+    #[case::v3(StarknetVersion::V3, TxInfo::cairo_size())]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_execution_info_version(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] starknet_version: StarknetVersion,
+        #[case] tx_info_len: usize,
+    ) {
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash)
+                .with_starknet_version(starknet_version);
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, OsSyscallHandlerWrapper::new(exec_helper.clone()));
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // Only the shape matters, fill the tx info with distinct felts
+        let tx_info: Vec<_> = (1..=TxInfo::cairo_size()).map(Felt252::from).collect();
+        let (tx_info_ptr, _) = write_felts(&mut vm, &tx_info);
+        let (block_info_ptr, _) = write_felts(&mut vm, &[Felt252::from(1000), Felt252::from(2000), Felt252::from(3)]);
+        let execution_info: Vec<MaybeRelocatable> = vec![
+            block_info_ptr.into(),
+            tx_info_ptr.into(),
+            Felt252::from(0x3000).into(),
+            Felt252::from(0x4000).into(),
+            Felt252::from(0x5000).into(),
+        ];
+        let execution_info_ptr = vm.add_memory_segment();
+        vm.load_data(execution_info_ptr, &execution_info).unwrap();
+        exec_helper.execution_helper.write().await.call_execution_info_ptr = Some(execution_info_ptr);
+
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_execution_info::<PCS>, b"GetExecutionInfo", &[], 2);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        let response_ptr = response[1].get_relocatable().unwrap();
+        assert_eq!(response_ptr == execution_info_ptr, starknet_version == StarknetVersion::V3);
+
+        let response_execution_info = vm.get_continuous_range(response_ptr, ExecutionInfo::cairo_size()).unwrap();
+        assert_eq!(response_execution_info[ExecutionInfo::block_info_offset()], block_info_ptr.into());
+        assert_eq!(response_execution_info[ExecutionInfo::caller_address_offset()..], execution_info[2..]);
+
+        let response_tx_info_ptr =
+            vm.get_relocatable((response_ptr + ExecutionInfo::tx_info_offset()).unwrap()).unwrap();
+        let response_tx_info = vm.get_integer_range(response_tx_info_ptr, tx_info_len).unwrap();
+        assert_eq!(
+            response_tx_info.iter().map(|felt| felt.clone().into_owned()).collect::<Vec<_>>(),
+            tx_info[..tx_info_len]
+        );
+        assert!(vm.get_maybe(&(response_tx_info_ptr + tx_info_len).unwrap()).is_none());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_execution_info_outside_of_call(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+        vm.insert_value(syscall_ptr, Felt252::from_bytes_be_slice(b"GetExecutionInfo")).unwrap();
+        vm.insert_value((syscall_ptr + 1usize).unwrap(), Felt252::from(1_000_000)).unwrap();
+
+        let result = get_execution_info::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_tx_info(block_context: BlockContext, mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let signature = vec![Felt252::from(0x5151), Felt252::from(0x5252)];
+        let paymaster_data = vec![Felt252::from(0x77)];
+        let tx_info = TransactionInfo::Current(CurrentTransactionInfo {
+            common_fields: CommonAccountFields {
+                transaction_hash: TransactionHash(Felt252::from(0xabcdef)),
+                version: TransactionVersion::THREE,
+                signature: TransactionSignature(signature.clone()),
+                nonce: Nonce(Felt252::from(7)),
+                sender_address: contract_address(Felt252::from(0x2000)),
+                only_query: false,
+            },
+            resource_bounds: ResourceBoundsMapping(BTreeMap::from([
+                (
+                    Resource::L1Gas,
+                    starknet_api::transaction::ResourceBounds { max_amount: 5_000, max_price_per_unit: 10 },
+                ),
+                (Resource::L2Gas, starknet_api::transaction::ResourceBounds { max_amount: 300, max_price_per_unit: 2 }),
+            ])),
+            tip: Tip(42),
+            nonce_data_availability_mode: DataAvailabilityMode::L1,
+            fee_data_availability_mode: DataAvailabilityMode::L2,
+            paymaster_data: PaymasterData(paymaster_data.clone()),
+            account_deployment_data: AccountDeploymentData(vec![]),
+        });
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.set_current_tx_info(tx_info).await;
+
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_tx_info::<PCS>, b"GetTxInfo", &[], 2);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        let tx_info = match response[1] {
+            MaybeRelocatable::RelocatableValue(tx_info) => tx_info,
+            _ => panic!("expected a pointer to the tx info, got {:?}", response[1]),
+        };
+
+        let get_felt = |vm: &VirtualMachine, ptr: Relocatable, offset: usize| {
+            vm.get_integer((ptr + offset).unwrap()).unwrap().into_owned()
+        };
+        let get_ptr =
+            |vm: &VirtualMachine, ptr: Relocatable, offset: usize| vm.get_relocatable((ptr + offset).unwrap()).unwrap();
+        let get_array = |vm: &VirtualMachine, start_offset: usize, end_offset: usize| {
+            let start = get_ptr(vm, tx_info, start_offset);
+            let end = get_ptr(vm, tx_info, end_offset);
+            let values = vm.get_integer_range(start, (end - start).unwrap()).unwrap();
+            values.into_iter().map(|felt| felt.into_owned()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::version_offset()), Felt252::THREE);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::account_contract_address_offset()), Felt252::from(0x2000));
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::max_fee_offset()), Felt252::ZERO);
+        assert_eq!(get_array(&vm, TxInfo::signature_start_offset(), TxInfo::signature_end_offset()), signature);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::transaction_hash_offset()), Felt252::from(0xabcdef));
+        assert_eq!(
+            get_felt(&vm, tx_info, TxInfo::chain_id_offset()),
+            chain_id_to_felt(&block_context.chain_info().chain_id)
+        );
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::nonce_offset()), Felt252::from(7));
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::tip_offset()), Felt252::from(42));
+        assert_eq!(
+            get_array(&vm, TxInfo::paymaster_data_start_offset(), TxInfo::paymaster_data_end_offset()),
+            paymaster_data
+        );
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::nonce_data_availability_mode_offset()), Felt252::ZERO);
+        assert_eq!(get_felt(&vm, tx_info, TxInfo::fee_data_availability_mode_offset()), Felt252::ONE);
+        assert!(
+            get_array(
+                &vm,
+                TxInfo::account_deployment_data_start_offset(),
+                TxInfo::account_deployment_data_end_offset()
+            )
+            .is_empty()
+        );
+
+        let bounds_start = get_ptr(&vm, tx_info, TxInfo::resource_bounds_start_offset());
+        let bounds_end = get_ptr(&vm, tx_info, TxInfo::resource_bounds_end_offset());
+        assert_eq!((bounds_end - bounds_start).unwrap(), 2 * ResourceBounds::cairo_size());
+        let l2_gas = (bounds_start + ResourceBounds::cairo_size()).unwrap();
+        assert_eq!(
+            get_felt(&vm, bounds_start, ResourceBounds::resource_offset()),
+            Felt252::from_bytes_be_slice(b"L1_GAS")
+        );
+        assert_eq!(get_felt(&vm, bounds_start, ResourceBounds::max_amount_offset()), Felt252::from(5_000));
+        assert_eq!(get_felt(&vm, bounds_start, ResourceBounds::max_price_per_unit_offset()), Felt252::from(10));
+        assert_eq!(get_felt(&vm, l2_gas, ResourceBounds::resource_offset()), Felt252::from_bytes_be_slice(b"L2_GAS"));
+        assert_eq!(get_felt(&vm, l2_gas, ResourceBounds::max_amount_offset()), Felt252::from(300));
+        assert_eq!(get_felt(&vm, l2_gas, ResourceBounds::max_price_per_unit_offset()), Felt252::TWO);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_tx_info_without_transaction(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        mut exec_scopes: ExecutionScopes,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // No transaction was started: the execution infos are empty
+        let error = try_run_syscall_hint(&mut vm, &mut exec_scopes, get_tx_info::<PCS>, b"GetTxInfo", &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no active transaction context for get_tx_info"), "{error}");
+        let error = try_run_syscall_hint(&mut vm, &mut exec_scopes, get_tx_signature::<PCS>, b"GetTxSignature", &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no active transaction context for get_tx_signature"), "{error}");
+
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash);
+        let syscall_ptr = vm.add_memory_segment();
+        let syscall_handler =
+            DeprecatedOsSyscallHandlerWrapper::new(exec_helper, syscall_ptr, block_context.block_info().clone());
+        let mut deprecated_exec_scopes = ExecutionScopes::new();
+        deprecated_exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, syscall_handler);
+        let error = try_run_syscall_hint(&mut vm, &mut deprecated_exec_scopes, get_tx_info::<PCS>, b"GetTxInfo", &[])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no active transaction context for get_tx_info"), "{error}");
+        let error =
+            try_run_syscall_hint(&mut vm, &mut deprecated_exec_scopes, get_tx_signature::<PCS>, b"GetTxSignature", &[])
+                .unwrap_err()
+                .to_string();
+        assert!(error.contains("no active transaction context for get_tx_signature"), "{error}");
+    }
+
+    /// Enters (or exits) the syscall `selector` as the OS does around the execution of a syscall.
+    fn log_syscall(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        selector: SyscallSelector,
+        enter: bool,
+    ) -> Result<(), HintError> {
+        let encoded_selector = KNOWN_SYSCALL_SELECTORS
+            .iter()
+            .find_map(|(known_selector, name)| {
+                (*known_selector == selector).then(|| Felt252::from_bytes_be_slice(name))
+            })
+            .unwrap();
+        // No builtin is used by the syscalls, all the pointers stay at the start of the program segment.
+        let builtin_ptrs = Relocatable::from((0, 0));
+        let range_check_ptr = Relocatable::from((0, 0));
+
+        // Each hint gets its own frame: memory cells can only be written once.
+        let fp = (vm.get_fp() + 3usize).unwrap();
+        vm.set_fp(fp.offset);
+        let ids: [MaybeRelocatable; 3] = [encoded_selector.into(), builtin_ptrs.into(), range_check_ptr.into()];
+        vm.load_data((fp - 3usize).unwrap(), &ids.to_vec()).unwrap();
+
+        let ids_data = HashMap::from([
+            (vars::ids::SELECTOR.to_string(), HintReference::new_simple(-3)),
+            (vars::ids::BUILTIN_PTRS.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::RANGE_CHECK_PTR.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let ap_tracking = ApTracking::new();
+        if enter {
+            os_logger_enter_syscall::<PCS>(false, vm, exec_scopes, &ids_data, &ap_tracking)
+        } else {
+            exit_syscall::<PCS>(selector, vm, exec_scopes, &ids_data, &ap_tracking, &HashMap::new())
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_max_call_depth(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_wrapper);
+        syscall_handler.set_max_call_depth(3).await;
+
+        // A contract that calls itself, alternating regular and library calls, and emits an event
+        // at each level.
+        let call_graph = [SyscallSelector::CallContract, SyscallSelector::LibraryCall, SyscallSelector::CallContract];
+        for (depth, selector) in call_graph.into_iter().enumerate() {
+            log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::EmitEvent, true).unwrap();
+            log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::EmitEvent, false).unwrap();
+            log_syscall(&mut vm, &mut exec_scopes, selector, true).unwrap();
+            assert_eq!(syscall_handler.current_call_depth().await, depth + 1);
+        }
+
+        let result = log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::LibraryCall, true);
+        assert!(matches!(result, Err(HintError::CustomHint(msg)) if msg.contains("Maximal call depth")));
+        assert_eq!(syscall_handler.current_call_depth().await, 3);
+
+        for selector in call_graph.into_iter().rev() {
+            log_syscall(&mut vm, &mut exec_scopes, selector, false).unwrap();
+        }
+        assert_eq!(syscall_handler.current_call_depth().await, 0);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_block_number_and_timestamp(block_context: BlockContext, mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        let block_info = block_context.block_info();
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_block_number::<PCS>, b"GetBlockNumber", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::from(block_info.block_number.0).into()]);
+
+        // Overrides, e.g. a block in the future to estimate fees, are reflected in the responses
+        exec_wrapper
+            .set_block_info(blockifier::blockifier::block::BlockInfo {
+                block_number: BlockNumber(1_000_000),
+                block_timestamp: BlockTimestamp(1_704_067_200),
+                ..block_info.clone()
+            })
+            .await;
+
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_block_number::<PCS>, b"GetBlockNumber", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::from(1_000_000).into()]);
+
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_block_timestamp::<PCS>, b"GetBlockTimestamp", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::from(1_704_067_200).into()]);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_syscall_hooks(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let before_syscalls = Rc::new(RefCell::new(vec![]));
+        let n_after_syscalls = Rc::new(RefCell::new(0));
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let recorded_syscalls = before_syscalls.clone();
+        syscall_handler
+            .set_before_syscall(move |selector, syscall_ptr| {
+                recorded_syscalls.borrow_mut().push((selector, syscall_ptr))
+            })
+            .await;
+        let counter = n_after_syscalls.clone();
+        syscall_handler.set_after_syscall(move |_, _| *counter.borrow_mut() += 1).await;
+
+        let selectors = [
+            (SyscallSelector::GetBlockNumber, get_block_number::<PCS> as HintImpl, b"GetBlockNumber".as_slice()),
+            (SyscallSelector::GetSequencerAddress, get_sequencer_address::<PCS>, b"GetSequencerAddress"),
+            (SyscallSelector::GetBlockNumber, get_block_number::<PCS>, b"GetBlockNumber"),
+        ];
+        for (_, hint, selector) in selectors {
+            // The hooks observe the syscalls without changing their responses
+            let response = run_syscall_hint(&mut vm, &mut exec_scopes, hint, selector, &[], 1);
+            assert_eq!(response, vec![Felt252::ZERO.into()]);
+        }
+
+        let hooked_selectors: Vec<_> = before_syscalls.borrow().iter().map(|(selector, _)| *selector).collect();
+        assert_eq!(hooked_selectors, selectors.map(|(selector, _, _)| selector).to_vec());
+        assert_eq!(*n_after_syscalls.borrow(), 3);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_sequencer_address(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        mut exec_scopes: ExecutionScopes,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let default_sequencer_address = *block_context.block_info().sequencer_address.0.key();
+        let sequencer_address = Felt252::from(0x5e9);
+        assert_ne!(sequencer_address, default_sequencer_address);
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_sequencer_address::<PCS>, b"GetSequencerAddress", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), default_sequencer_address.into()]);
+
+        exec_wrapper.set_sequencer_address(sequencer_address).await;
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_sequencer_address::<PCS>, b"GetSequencerAddress", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), sequencer_address.into()]);
+
+        // The deprecated handler honors the override as well
+        let syscall_ptr = vm.add_memory_segment();
+        let exec_helper =
+            EHW::new(ContractStorageMap::default(), vec![], &block_context, None, old_block_number_and_hash);
+        exec_helper.set_sequencer_address(sequencer_address).await;
+        let syscall_handler =
+            DeprecatedOsSyscallHandlerWrapper::new(exec_helper, syscall_ptr, block_context.block_info().clone());
+        syscall_handler.get_sequencer_address(syscall_ptr, &mut vm).await.unwrap();
+        let response_offset =
+            GetSequencerAddress::response_offset() + GetSequencerAddressResponse::sequencer_address_offset();
+        assert_eq!(*vm.get_integer((syscall_ptr + response_offset).unwrap()).unwrap(), sequencer_address);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_caller_address(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // A transaction calls the outer contract, which calls the inner one
+        let outer_address = Felt252::from(0x1000);
+        let inner_address = Felt252::from(0x2000);
+        let mut inner_call_info = CallInfo::default();
+        inner_call_info.call.storage_address = contract_address(inner_address);
+        inner_call_info.call.caller_address = contract_address(outer_address);
+        let mut outer_call_info = CallInfo::default();
+        outer_call_info.call.storage_address = contract_address(outer_address);
+        outer_call_info.inner_calls = vec![inner_call_info.clone()];
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        exec_wrapper.execution_helper.write().await.call_info = Some(outer_call_info);
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_caller_address::<PCS>, b"GetCallerAddress", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::ZERO.into()]);
+
+        exec_wrapper.execution_helper.write().await.call_info = Some(inner_call_info);
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_caller_address::<PCS>, b"GetCallerAddress", &[], 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), outer_address.into()]);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_contract_address(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let address = Felt252::from(0x1000);
+        let mut call_info = CallInfo::default();
+        call_info.call.storage_address = contract_address(address);
+        call_info.call.caller_address = contract_address(Felt252::from(0x2000));
+        // A library call runs the code of another class in the storage of the calling contract
+        let mut library_call_info = CallInfo::default();
+        library_call_info.call.storage_address = contract_address(address);
+        library_call_info.call.caller_address = contract_address(address);
+        library_call_info.call.class_hash = Some(ClassHash(Felt252::from(0x3000)));
+        library_call_info.call.call_type = CallType::Delegate;
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        for call_info in [call_info, library_call_info] {
+            exec_wrapper.execution_helper.write().await.call_info = Some(call_info);
+            let response =
+                run_syscall_hint(&mut vm, &mut exec_scopes, get_contract_address::<PCS>, b"GetContractAddress", &[], 2);
+            assert_eq!(response, vec![Felt252::ZERO.into(), address.into()]);
+        }
+    }
+
+    #[rstest]
+    #[case::empty_signature(vec![])]
+    #[case::two_felts(vec![Felt252::from(0x5151), Felt252::from(0x5252)])]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_tx_signature(mut exec_scopes: ExecutionScopes, #[case] signature: Vec<Felt252>) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let tx_info = TransactionInfo::Deprecated(DeprecatedTransactionInfo {
+            common_fields: CommonAccountFields {
+                signature: TransactionSignature(signature.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.set_current_tx_info(tx_info).await;
+
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, get_tx_signature::<PCS>, b"GetTxSignature", &[], 3);
+        assert_eq!(response[..2], [Felt252::ZERO.into(), Felt252::from(signature.len()).into()]);
+        let signature_ptr = match response[2] {
+            MaybeRelocatable::RelocatableValue(signature_ptr) => signature_ptr,
+            _ => panic!("expected a pointer to the signature, got {:?}", response[2]),
+        };
+        let values = vm.get_integer_range(signature_ptr, signature.len()).unwrap();
+        assert_eq!(values.into_iter().map(|value| value.into_owned()).collect::<Vec<_>>(), signature);
+    }
+
+    const SECP256K1_GENERATOR_X: &str = "0x79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798";
+    const SECP256K1_GENERATOR_Y: &str = "0x483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8";
+    const SECP256K1_PRIME: &str = "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F";
+
+    /// Splits a 256-bit hex value in (low, high) 128-bit limbs.
+    fn to_u256_limbs(hex: &str) -> (Felt252, Felt252) {
+        let high = Felt252::from_hex_unchecked(&hex[..hex.len() - 32]);
+        let low = Felt252::from_hex_unchecked(&format!("0x{}", &hex[hex.len() - 32..]));
+        (low, high)
+    }
+
+    /// Runs a syscall hint over a request made of `selector`, a large gas counter and
+    /// `request`. Returns a pointer to the response, right after the remaining gas.
+    fn try_run_syscall_hint(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint: HintImpl,
+        selector: &[u8],
+        request: &[MaybeRelocatable],
+    ) -> Result<Relocatable, HintError> {
+        let syscall_ptr = vm.add_memory_segment();
+        // Each run gets its own frame: memory cells can only be written once.
+        let fp = (vm.get_fp() + 1usize).unwrap();
+        vm.set_fp(fp.offset);
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, vm, &ids_data, &ap_tracking).unwrap();
+
+        let header: [MaybeRelocatable; 2] =
+            [Felt252::from_bytes_be_slice(selector).into(), Felt252::from(1_000_000).into()];
+        let request_end = vm.load_data(syscall_ptr, &header.to_vec()).unwrap();
+        let request_end = vm.load_data(request_end, &request.to_vec()).unwrap();
+
+        hint(vm, exec_scopes, &ids_data, &ap_tracking, &constants)?;
+
+        // Skip the remaining gas.
+        Ok((request_end + 1usize).unwrap())
+    }
+
+    /// Same as `try_run_syscall_hint` but returns the `response_len` felts that follow the
+    /// remaining gas in the response: the failure flag first, then either the response or the
+    /// error data.
+    fn run_syscall_hint(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint: HintImpl,
+        selector: &[u8],
+        request: &[MaybeRelocatable],
+        response_len: usize,
+    ) -> Vec<MaybeRelocatable> {
+        let response_ptr = try_run_syscall_hint(vm, exec_scopes, hint, selector, request).unwrap();
+        vm.get_continuous_range(response_ptr, response_len).unwrap()
+    }
+
+    /// Runs `secp256k1_new` on (x, y) and returns the failure flag and the optional point.
+    fn run_secp256k1_new(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        x: (Felt252, Felt252),
+        y: (Felt252, Felt252),
+    ) -> [MaybeRelocatable; 3] {
+        let request = [x.0.into(), x.1.into(), y.0.into(), y.1.into()];
+        run_syscall_hint(vm, exec_scopes, secp256k1_new::<PCS>, b"Secp256k1New", &request, 3).try_into().unwrap()
+    }
+
+    /// Returns the handle of a secp256k1 point allocated with `secp256k1_new`.
+    fn new_secp256k1_point(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        x: (Felt252, Felt252),
+        y: (Felt252, Felt252),
+    ) -> Relocatable {
+        let [failure_flag, not_on_curve, point] = run_secp256k1_new(vm, exec_scopes, x, y);
+        assert_eq!((failure_flag, not_on_curve), (Felt252::ZERO.into(), Felt252::ZERO.into()));
+        point.get_relocatable().unwrap()
+    }
+
+    /// Returns the (x, y) coordinates of a secp256k1 point as (low, high) limbs.
+    fn secp256k1_get_xy_limbs(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        point: Relocatable,
+    ) -> Vec<MaybeRelocatable> {
+        let response =
+            run_syscall_hint(vm, exec_scopes, secp256k1_get_xy::<PCS>, b"Secp256k1GetXy", &[point.into()], 5);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        response[1..].to_vec()
+    }
+
+    /// Runs an operation returning a point handle and returns the handle.
+    fn run_secp256k1_op(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        hint: HintImpl,
+        selector: &[u8],
+        request: &[MaybeRelocatable],
+    ) -> Relocatable {
+        let response = run_syscall_hint(vm, exec_scopes, hint, selector, request, 2);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        response[1].get_relocatable().unwrap()
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_syscall_ptr_validation(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        syscall_handler.set_syscall_ptr_validation(true).await;
+
+        let syscall_ptr = vm.add_memory_segment();
+        let request: [MaybeRelocatable; 2] =
+            [Felt252::from_bytes_be_slice(b"GetBlockNumber").into(), Felt252::from(1_000_000).into()];
+        vm.load_data(syscall_ptr, &request.to_vec()).unwrap();
+        syscall_handler.get_block_number(&mut vm, syscall_ptr).await.unwrap();
+
+        // A handler that did not move the pointer runs the same syscall again
+        let error = syscall_handler.get_block_number(&mut vm, syscall_ptr).await.unwrap_err();
+        assert!(
+            matches!(&error, HintError::AssertionFailed(message) if message.contains("did not advance")),
+            "{error}"
+        );
+        assert!(syscall_handler.set_syscall_ptr(syscall_ptr).await.is_err());
+
+        // Request (selector, gas) and response (remaining gas, failure flag, block number)
+        let syscall_end = (syscall_ptr + 5usize).unwrap();
+        syscall_handler.set_syscall_ptr(syscall_end).await.unwrap();
+        // Pointers in other segments, e.g. of another call, are not compared
+        syscall_handler.set_syscall_ptr(vm.add_memory_segment()).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_keccak_syscall_ptr_validation(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        syscall_handler.set_syscall_ptr_validation(true).await;
+
+        let syscall_ptr = prepare_keccak_syscall(&mut vm, &[0u64; 17]);
+        syscall_handler.keccak(&mut vm, syscall_ptr).await.unwrap();
+
+        let error = syscall_handler.keccak(&mut vm, syscall_ptr).await.unwrap_err();
+        assert!(
+            matches!(&error, HintError::AssertionFailed(message) if message.contains("did not advance")),
+            "{error}"
+        );
+
+        // Request (selector, gas, input start, input end) and response (remaining gas, failure
+        // flag, result low, result high)
+        let syscall_end = (syscall_ptr + 8usize).unwrap();
+        assert!(syscall_handler.set_syscall_ptr((syscall_end - 1usize).unwrap()).await.is_err());
+        syscall_handler.set_syscall_ptr(syscall_end).await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_reset_for_new_transaction(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.secp256k1_syscall_processor.max_live_points = Some(10);
+
+        let generator_x = to_u256_limbs(SECP256K1_GENERATOR_X);
+        let generator_y = to_u256_limbs(SECP256K1_GENERATOR_Y);
+        let generator = new_secp256k1_point(&mut vm, &mut exec_scopes, generator_x, generator_y);
+        new_secp256k1_point(&mut vm, &mut exec_scopes, generator_x, generator_y);
+        syscall_handler.enter_contract_call().await.unwrap();
+        syscall_handler.set_syscall_ptr(Relocatable::from((1, 0))).await.unwrap();
+        exec_wrapper.execution_helper.write().await.deployed_contracts.insert(Felt252::from(0x1000), Felt252::ONE);
+
+        syscall_handler.reset_for_new_transaction().await;
+
+        {
+            let eh_ref = exec_wrapper.execution_helper.read().await;
+            assert_eq!(eh_ref.secp256k1_syscall_processor.live_points(), 0);
+            assert_eq!(eh_ref.secp256k1_syscall_processor.max_live_points, Some(10));
+            // Block-wide state is preserved
+            assert_eq!(eh_ref.deployed_contracts.get(&Felt252::from(0x1000)), Some(&Felt252::ONE));
+        }
+        assert_eq!(syscall_handler.current_call_depth().await, 0);
+        assert_eq!(syscall_handler.syscall_ptr().await, None);
+
+        // Points of the previous transaction cannot be used anymore
+        let request = [generator.into(), generator.into()];
+        let response = try_run_syscall_hint(&mut vm, &mut exec_scopes, secp256k1_add::<PCS>, b"Secp256k1Add", &request);
+        assert!(response.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_secp256k1_add_and_mul(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let generator_x = to_u256_limbs(SECP256K1_GENERATOR_X);
+        let generator_y = to_u256_limbs(SECP256K1_GENERATOR_Y);
+        let generator = new_secp256k1_point(&mut vm, &mut exec_scopes, generator_x, generator_y);
+
+        // G + G
+        let request = [generator.into(), generator.into()];
+        let g_plus_g = run_secp256k1_op(&mut vm, &mut exec_scopes, secp256k1_add::<PCS>, b"Secp256k1Add", &request);
+        let g_plus_g_xy = secp256k1_get_xy_limbs(&mut vm, &mut exec_scopes, g_plus_g);
+        // 2G = (0xC6047F9441ED7D6D3045406E95C07CD85C778E4B8CEF3CA7ABAC09B95C709EE5,
+        //       0x1AE168FEA63DC339A3C58419466CEAEEF7F632653266D0E1236431A950CFE52A)
+        let (x_low, x_high) = to_u256_limbs("0xC6047F9441ED7D6D3045406E95C07CD85C778E4B8CEF3CA7ABAC09B95C709EE5");
+        let (y_low, y_high) = to_u256_limbs("0x1AE168FEA63DC339A3C58419466CEAEEF7F632653266D0E1236431A950CFE52A");
+        assert_eq!(g_plus_g_xy, vec![x_low.into(), x_high.into(), y_low.into(), y_high.into()]);
+
+        // 2 * G
+        let request = [generator.into(), Felt252::TWO.into(), Felt252::ZERO.into()];
+        let two_g = run_secp256k1_op(&mut vm, &mut exec_scopes, secp256k1_mul::<PCS>, b"Secp256k1Mul", &request);
+        assert_eq!(secp256k1_get_xy_limbs(&mut vm, &mut exec_scopes, two_g), g_plus_g_xy);
+
+        // (n + 2) * G, the scalar is reduced modulo the curve order n
+        let (n_plus_two_low, n_plus_two_high) =
+            to_u256_limbs("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364143");
+        let request = [generator.into(), n_plus_two_low.into(), n_plus_two_high.into()];
+        let n_plus_two_g = run_secp256k1_op(&mut vm, &mut exec_scopes, secp256k1_mul::<PCS>, b"Secp256k1Mul", &request);
+        assert_eq!(secp256k1_get_xy_limbs(&mut vm, &mut exec_scopes, n_plus_two_g), g_plus_g_xy);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_secp256k1_add_negation_is_infinity(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let generator_x = to_u256_limbs(SECP256K1_GENERATOR_X);
+        let generator_y = to_u256_limbs(SECP256K1_GENERATOR_Y);
+        // -G = (x, p - y)
+        let minus_generator_y = to_u256_limbs("0xB7C52588D95C3B9AA25B0403F1EEF75702E84BB7597AABE663B82F6F04EF2777");
+        let generator = new_secp256k1_point(&mut vm, &mut exec_scopes, generator_x, generator_y);
+        let minus_generator = new_secp256k1_point(&mut vm, &mut exec_scopes, generator_x, minus_generator_y);
+
+        let request = [generator.into(), minus_generator.into()];
+        let infinity = run_secp256k1_op(&mut vm, &mut exec_scopes, secp256k1_add::<PCS>, b"Secp256k1Add", &request);
+
+        // The point at infinity is encoded as (0, 0)
+        assert_eq!(secp256k1_get_xy_limbs(&mut vm, &mut exec_scopes, infinity), vec![Felt252::ZERO.into(); 4]);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_secp256k1_add_invalid_handle(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let generator_x = to_u256_limbs(SECP256K1_GENERATOR_X);
+        let generator_y = to_u256_limbs(SECP256K1_GENERATOR_Y);
+        let generator = new_secp256k1_point(&mut vm, &mut exec_scopes, generator_x, generator_y);
+        let not_a_point = vm.add_memory_segment();
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+        let request: Vec<MaybeRelocatable> = vec![
+            Felt252::from_bytes_be_slice(b"Secp256k1Add").into(),
+            Felt252::from(1_000_000).into(),
+            generator.into(),
+            not_a_point.into(),
+        ];
+        vm.load_data(syscall_ptr, &request).unwrap();
+
+        let result = secp256k1_add::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new());
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_secp256k1_new(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let generator_x = to_u256_limbs(SECP256K1_GENERATOR_X);
+        let generator_y = to_u256_limbs(SECP256K1_GENERATOR_Y);
+
+        // Generator point: Some(id), ids are pointers in the secp256k1 segment
+        let [failure_flag, not_on_curve, generator_id] =
+            run_secp256k1_new(&mut vm, &mut exec_scopes, generator_x, generator_y);
+        assert_eq!(failure_flag, Felt252::ZERO.into());
+        assert_eq!(not_on_curve, Felt252::ZERO.into());
+        let generator_id = generator_id.get_relocatable().expect("point ids are pointers");
+
+        // Off-curve point: None
+        let off_curve_y = (generator_y.0 + Felt252::ONE, generator_y.1);
+        let [failure_flag, not_on_curve, point_id] =
+            run_secp256k1_new(&mut vm, &mut exec_scopes, generator_x, off_curve_y);
+        assert_eq!(failure_flag, Felt252::ZERO.into());
+        assert_eq!(not_on_curve, Felt252::ONE.into());
+        assert_eq!(point_id, Felt252::ZERO.into());
+
+        // (0, 0) encodes the point at infinity, which is allocated after the generator
+        let zero = (Felt252::ZERO, Felt252::ZERO);
+        let [failure_flag, not_on_curve, infinity_id] = run_secp256k1_new(&mut vm, &mut exec_scopes, zero, zero);
+        assert_eq!(failure_flag, Felt252::ZERO.into());
+        assert_eq!(not_on_curve, Felt252::ZERO.into());
+        assert_eq!(infinity_id, (generator_id + EcCoordinate::cairo_size()).unwrap().into());
+
+        // Coordinates must be smaller than the field prime
+        let prime = to_u256_limbs(SECP256K1_PRIME);
+        let [failure_flag, _, _] = run_secp256k1_new(&mut vm, &mut exec_scopes, prime, generator_y);
+        assert_eq!(failure_flag, Felt252::ONE.into());
+    }
+
+    const SECP256R1_GENERATOR_X: &str = "0x6B17D1F2E12C4247F8BCE6E563A440F277037D812DEB33A0F4A13945D898C296";
+    const SECP256R1_GENERATOR_Y: &str = "0x4FE342E2FE1A7F9B8EE7EB4A7C0F9E162BCE33576B315ECECBB6406837BF51F5";
+    // 2G, from the NIST P-256 test vectors.
+    const SECP256R1_DOUBLE_GENERATOR_X: &str = "0x7CF27B188D034F7E8A52380304B51AC3C08969E277F21B35A60B48FC47669978";
+    const SECP256R1_DOUBLE_GENERATOR_Y: &str = "0x07775510DB8ED040293D9AC69F7430DBBA7DADE63CE982299E04B79D227873D1";
+
+    fn u256_limbs_response(x: &str, y: &str) -> Vec<MaybeRelocatable> {
+        let (x_low, x_high) = to_u256_limbs(x);
+        let (y_low, y_high) = to_u256_limbs(y);
+        vec![x_low.into(), x_high.into(), y_low.into(), y_high.into()]
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_secp256r1_syscalls(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let (x_low, x_high) = to_u256_limbs(SECP256R1_GENERATOR_X);
+        let (y_low, y_high) = to_u256_limbs(SECP256R1_GENERATOR_Y);
+
+        // secp256r1_new
+        let request = [x_low.into(), x_high.into(), y_low.into(), y_high.into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, secp256r1_new::<PCS>, b"Secp256r1New", &request, 3);
+        assert_eq!(response[..2], [Felt252::ZERO.into(), Felt252::ZERO.into()]);
+        let generator = response[2].get_relocatable().unwrap();
+
+        // secp256r1_get_point_from_x finds the generator back from x and the parity of y
+        let request = [x_low.into(), x_high.into(), Felt252::ONE.into()];
+        let response = run_syscall_hint(
+            &mut vm,
+            &mut exec_scopes,
+            secp256r1_get_point_from_x::<PCS>,
+            b"Secp256r1GetPointFromX",
+            &request,
+            3,
+        );
+        assert_eq!(response[..2], [Felt252::ZERO.into(), Felt252::ZERO.into()]);
+        let point_from_x = response[2].get_relocatable().unwrap();
+        let response = run_syscall_hint(
+            &mut vm,
+            &mut exec_scopes,
+            secp256r1_get_xy::<PCS>,
+            b"Secp256r1GetXy",
+            &[point_from_x.into()],
+            5,
+        );
+        assert_eq!(response[1..], u256_limbs_response(SECP256R1_GENERATOR_X, SECP256R1_GENERATOR_Y));
+
+        // No point of the curve has x = 1
+        let request = [Felt252::ONE.into(), Felt252::ZERO.into(), Felt252::ZERO.into()];
+        let response = run_syscall_hint(
+            &mut vm,
+            &mut exec_scopes,
+            secp256r1_get_point_from_x::<PCS>,
+            b"Secp256r1GetPointFromX",
+            &request,
+            3,
+        );
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::ONE.into(), Felt252::ZERO.into()]);
+
+        // G + G and 2 * G
+        let request = [generator.into(), generator.into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, secp256r1_add::<PCS>, b"Secp256r1Add", &request, 2);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        let g_plus_g = response[1].get_relocatable().unwrap();
+
+        let request = [generator.into(), Felt252::TWO.into(), Felt252::ZERO.into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, secp256r1_mul::<PCS>, b"Secp256r1Mul", &request, 2);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        let two_g = response[1].get_relocatable().unwrap();
+
+        let expected_xy = u256_limbs_response(SECP256R1_DOUBLE_GENERATOR_X, SECP256R1_DOUBLE_GENERATOR_Y);
+        for point in [g_plus_g, two_g] {
+            let response = run_syscall_hint(
+                &mut vm,
+                &mut exec_scopes,
+                secp256r1_get_xy::<PCS>,
+                b"Secp256r1GetXy",
+                &[point.into()],
+                5,
+            );
+            assert_eq!(response[0], Felt252::ZERO.into());
+            assert_eq!(response[1..], expected_xy);
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_secp256r1_rejects_secp256k1_handles(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let k1_generator = new_secp256k1_point(
+            &mut vm,
+            &mut exec_scopes,
+            to_u256_limbs(SECP256K1_GENERATOR_X),
+            to_u256_limbs(SECP256K1_GENERATOR_Y),
+        );
+        let (x_low, x_high) = to_u256_limbs(SECP256R1_GENERATOR_X);
+        let (y_low, y_high) = to_u256_limbs(SECP256R1_GENERATOR_Y);
+        let request = [x_low.into(), x_high.into(), y_low.into(), y_high.into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, secp256r1_new::<PCS>, b"Secp256r1New", &request, 3);
+        let r1_generator = response[2].get_relocatable().unwrap();
+        assert_ne!(k1_generator.segment_index, r1_generator.segment_index);
+
+        let syscall_ptr = vm.add_memory_segment();
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+        let request: Vec<MaybeRelocatable> = vec![
+            Felt252::from_bytes_be_slice(b"Secp256r1Add").into(),
+            Felt252::from(1_000_000).into(),
+            r1_generator.into(),
+            k1_generator.into(),
+        ];
+        vm.load_data(syscall_ptr, &request).unwrap();
+
+        let result = secp256r1_add::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new());
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_emit_event(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let contract_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, contract_address).await;
+
+        let events = [
+            (vec![Felt252::from(0x1111)], vec![Felt252::ONE, Felt252::TWO]),
+            (vec![Felt252::from(0x2222), Felt252::from(0x3333)], vec![]),
+        ];
+        for (keys, data) in &events {
+            let (keys_start, keys_end) = write_felts(&mut vm, keys);
+            let (data_start, data_end) = write_felts(&mut vm, data);
+            let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+            let response = run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request, 1);
+            assert_eq!(response[0], Felt252::ZERO.into());
+        }
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        let emitted_events = exec_wrapper.emitted_events().await;
+        let expected_events: Vec<_> = events
+            .into_iter()
+            .enumerate()
+            .map(|(order, (keys, data))| OrderedEvent { order, contract_address, selector: None, keys, data })
+            .collect();
+        assert_eq!(emitted_events, expected_events);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_emit_event_selector_split(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let contract_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, contract_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.split_event_selector = true;
+
+        // A Cairo 1 event keyed by the hash of its name, then an event without keys
+        let event_selector = Felt252::from_bytes_be_slice(b"Transfer");
+        for keys in [vec![event_selector, Felt252::from(0x1111), Felt252::from(0x2222)], vec![]] {
+            let (keys_start, keys_end) = write_felts(&mut vm, &keys);
+            let (data_start, data_end) = write_felts(&mut vm, &[Felt252::ONE]);
+            let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+            run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request, 1);
+        }
+
+        assert_eq!(
+            exec_wrapper.emitted_events().await,
+            vec![
+                OrderedEvent {
+                    order: 0,
+                    contract_address,
+                    selector: Some(event_selector),
+                    keys: vec![Felt252::from(0x1111), Felt252::from(0x2222)],
+                    data: vec![Felt252::ONE],
+                },
+                OrderedEvent { order: 1, contract_address, selector: None, keys: vec![], data: vec![Felt252::ONE] },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_duplicate_event_warnings(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        let contract_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, contract_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.warn_duplicate_events = true;
+
+        let transfer = [Felt252::from_bytes_be_slice(b"Transfer")];
+        for (keys, data) in [(transfer, Felt252::ONE), (transfer, Felt252::TWO), (transfer, Felt252::ONE)] {
+            let (keys_start, keys_end) = write_felts(&mut vm, &keys);
+            let (data_start, data_end) = write_felts(&mut vm, &[data]);
+            let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+            run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request, 1);
+        }
+
+        // The duplicate is still emitted, only flagged
+        assert_eq!(exec_wrapper.emitted_events().await.len(), 3);
+        let warnings = exec_wrapper.warnings().await;
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Contract 0x1000 emitted the same event twice"), "{}", warnings[0]);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_flattened_events(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // The called contract emits an event, calls a contract that emits one, then emits another
+        let caller_address = Felt252::from(0x1000);
+        let callee_address = Felt252::from(0x2000);
+        let emitters = [caller_address, callee_address, caller_address];
+        for (index, contract_address) in emitters.into_iter().enumerate() {
+            set_current_contract_address(&mut vm, &exec_scopes, contract_address).await;
+            let (keys_start, keys_end) = write_felts(&mut vm, &[Felt252::from(index)]);
+            let (data_start, data_end) = write_felts(&mut vm, &[]);
+            let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+            run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request, 1);
+        }
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        // The flattening only depends on the order of the events, not on how they are stored
+        exec_wrapper.execution_helper.write().await.emitted_events.reverse();
+
+        let flattened_events = exec_wrapper.flattened_events().await;
+        let expected_events: Vec<_> = emitters
+            .into_iter()
+            .enumerate()
+            .map(|(order, contract_address)| OrderedEvent {
+                order,
+                contract_address,
+                selector: None,
+                keys: vec![Felt252::from(order)],
+                data: vec![],
+            })
+            .collect();
+        assert_eq!(flattened_events, expected_events);
+    }
+
+    #[rstest]
+    #[case::enough_gas(100, false)]
+    #[case::out_of_gas(1_000_000, true)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_syscall_gas_accounting(
+        block_context: BlockContext,
+        old_block_number_and_hash: (Felt252, Felt252),
+        #[case] emit_event_data_word: u64,
+        #[case] out_of_gas: bool,
+    ) {
+        let mut gas_costs = SyscallGasCosts::default().with_syscall_cost(SyscallSelector::EmitEvent, 0);
+        gas_costs.emit_event_data_word = emit_event_data_word;
+        let mut exec_scopes = exec_scopes_with_gas_costs(&block_context, old_block_number_and_hash, gas_costs);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        set_current_contract_address(&mut vm, &exec_scopes, Felt252::from(0x1000)).await;
+
+        let (keys_start, keys_end) = write_felts(&mut vm, &[]);
+        let (data_start, data_end) = write_felts(&mut vm, &[Felt252::ONE, Felt252::TWO, Felt252::THREE]);
+        let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+        let response_ptr =
+            try_run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request).unwrap();
+
+        let remaining_gas = vm.get_integer((response_ptr - 1usize).unwrap()).unwrap().into_owned();
+        let failure_flag = vm.get_integer(response_ptr).unwrap().into_owned();
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        let emitted_events = exec_wrapper.emitted_events().await;
+
+        if out_of_gas {
+            // Nothing is charged and the event is not emitted.
+            assert_eq!(remaining_gas, Felt252::from(1_000_000));
+            assert_eq!(failure_flag, Felt252::ONE);
+            let error_start = vm.get_relocatable((response_ptr + 1usize).unwrap()).unwrap();
+            let error = vm.get_integer(error_start).unwrap().into_owned();
+            assert_eq!(error, Felt252::from_bytes_be_slice(b"Out of gas"));
+            assert!(emitted_events.is_empty());
+        } else {
+            assert_eq!(remaining_gas, Felt252::from(1_000_000 - 3 * emit_event_data_word));
+            assert_eq!(failure_flag, Felt252::ZERO);
+            assert_eq!(emitted_events.len(), 1);
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_run_report(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        set_current_contract_address(&mut vm, &exec_scopes, Felt252::from(0x1000)).await;
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_wrapper.clone());
+
+        let mut charged_gas = 0;
+        for _ in 0..2 {
+            log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::EmitEvent, true).unwrap();
+            let (keys_start, keys_end) = write_felts(&mut vm, &[Felt252::from(0x1111)]);
+            let (data_start, data_end) = write_felts(&mut vm, &[Felt252::ONE]);
+            let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+            let response_ptr =
+                try_run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request).unwrap();
+            let remaining_gas = vm.get_integer((response_ptr - 1usize).unwrap()).unwrap().to_u64().unwrap();
+            charged_gas += 1_000_000 - remaining_gas;
+            log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::EmitEvent, false).unwrap();
+        }
+
+        log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::SendMessageToL1, true).unwrap();
+        let (payload_start, payload_end) = write_felts(&mut vm, &[Felt252::from(10)]);
+        let request = [Felt252::from(0xabcd).into(), payload_start.into(), payload_end.into()];
+        let response_ptr =
+            try_run_syscall_hint(&mut vm, &mut exec_scopes, send_message_to_l1::<PCS>, b"SendMessageToL1", &request)
+                .unwrap();
+        let remaining_gas = vm.get_integer((response_ptr - 1usize).unwrap()).unwrap().to_u64().unwrap();
+        charged_gas += 1_000_000 - remaining_gas;
+        log_syscall(&mut vm, &mut exec_scopes, SyscallSelector::SendMessageToL1, false).unwrap();
+
+        let report = exec_wrapper.run_report().await;
+        assert!(report.gas_consumed > 0);
+        assert_eq!(report.gas_consumed, charged_gas);
+        assert_eq!(report.syscall_profile, exec_wrapper.syscall_profile().await);
+        assert_eq!(report.syscall_profile.0[&SyscallSelector::EmitEvent].n_calls, report.effects.events.len());
+        assert_eq!(
+            report.syscall_profile.0[&SyscallSelector::SendMessageToL1].n_calls,
+            report.effects.l2_to_l1_messages.len()
+        );
+        assert_eq!(report.effects, exec_wrapper.export_effects().await);
+        assert!(report.effects.storage_writes.is_empty());
+        assert_eq!(report.warnings, exec_wrapper.warnings().await);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<RunReport>(&json).unwrap(), report);
+    }
+
+    /// Makes `contract_address` the contract address of the current call, as `enter_call` does.
+    async fn set_current_contract_address(
+        vm: &mut VirtualMachine,
+        exec_scopes: &ExecutionScopes,
+        contract_address: Felt252,
+    ) {
+        let execution_info_ptr = vm.add_memory_segment();
+        vm.insert_value((execution_info_ptr + ExecutionInfo::contract_address_offset()).unwrap(), contract_address)
+            .unwrap();
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.call_execution_info_ptr = Some(execution_info_ptr);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_send_message_to_l1(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let expected_messages = vec![
+            L2ToL1Message {
+                from_address: Felt252::from(0x1000),
+                to_address: Felt252::from(0xabcd),
+                payload: vec![Felt252::from(10), Felt252::from(20)],
+            },
+            L2ToL1Message { from_address: Felt252::from(0x2000), to_address: Felt252::from(0xef01), payload: vec![] },
+        ];
+        for message in &expected_messages {
+            set_current_contract_address(&mut vm, &exec_scopes, message.from_address).await;
+            let (payload_start, payload_end) = write_felts(&mut vm, &message.payload);
+            let request = [message.to_address.into(), payload_start.into(), payload_end.into()];
+            let response =
+                run_syscall_hint(&mut vm, &mut exec_scopes, send_message_to_l1::<PCS>, b"SendMessageToL1", &request, 1);
+            assert_eq!(response[0], Felt252::ZERO.into());
+        }
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        assert_eq!(exec_wrapper.l2_to_l1_messages().await, expected_messages);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_send_message_to_l1_invalid_payload_bounds(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        set_current_contract_address(&mut vm, &exec_scopes, Felt252::from(0x1000)).await;
+        let (payload_start, payload_end) = write_felts(&mut vm, &[Felt252::ONE]);
+        // The end of the payload comes before its start.
+        let request = [Felt252::from(0xabcd).into(), payload_end.into(), payload_start.into()];
+        let result =
+            try_run_syscall_hint(&mut vm, &mut exec_scopes, send_message_to_l1::<PCS>, b"SendMessageToL1", &request);
+        assert!(result.is_err());
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        assert!(exec_wrapper.l2_to_l1_messages().await.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_replace_class(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let contract_address = Felt252::from(0x1000);
+        let old_class_hash = Felt252::from(0xc1a55);
+        let new_class_hash = Felt252::from(0xc1a56);
+        set_current_contract_address(&mut vm, &exec_scopes, contract_address).await;
+
+        for class_hash in [old_class_hash, new_class_hash] {
+            let response = run_syscall_hint(
+                &mut vm,
+                &mut exec_scopes,
+                replace_class::<PCS>,
+                b"ReplaceClass",
+                &[class_hash.into()],
+                1,
+            );
+            assert_eq!(response[0], Felt252::ZERO.into());
+        }
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        assert_eq!(exec_wrapper.class_hash_of(&contract_address).await, Some(new_class_hash));
+        assert_eq!(exec_wrapper.class_replacements().await, HashMap::from([(contract_address, new_class_hash)]));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_syscall_transcript(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        set_current_contract_address(&mut vm, &exec_scopes, Felt252::from(0x1000)).await;
+
+        let response = run_syscall_hint(
+            &mut vm,
+            &mut exec_scopes,
+            replace_class::<PCS>,
+            b"ReplaceClass",
+            &[Felt252::from(0xc1a55).into()],
+            1,
+        );
+        assert_eq!(response[0], Felt252::ZERO.into());
+
+        let (keys_start, keys_end) = write_felts(&mut vm, &[Felt252::from(0x1111)]);
+        let (data_start, data_end) = write_felts(&mut vm, &[Felt252::ONE, Felt252::TWO]);
+        let request = [keys_start.into(), keys_end.into(), data_start.into(), data_end.into()];
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, emit_event::<PCS>, b"EmitEvent", &request, 1);
+        assert_eq!(response[0], Felt252::ZERO.into());
+
+        let (payload_start, payload_end) = write_felts(&mut vm, &[Felt252::from(10)]);
+        let request = [Felt252::from(0xabcd).into(), payload_start.into(), payload_end.into()];
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, send_message_to_l1::<PCS>, b"SendMessageToL1", &request, 1);
+        assert_eq!(response[0], Felt252::ZERO.into());
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        let transcript = exec_wrapper.transcript().await;
+        assert_eq!(
+            transcript.entries(),
+            [
+                SyscallTranscriptEntry {
+                    index: 0,
+                    selector: SyscallSelector::ReplaceClass,
+                    summary: "class_hash=0xc1a55".to_string(),
+                    keccak_rounds: None,
+                    storage_access: None,
+                },
+                SyscallTranscriptEntry {
+                    index: 1,
+                    selector: SyscallSelector::EmitEvent,
+                    summary: "1 keys, 2 data felts".to_string(),
+                    keccak_rounds: None,
+                    storage_access: None,
+                },
+                SyscallTranscriptEntry {
+                    index: 2,
+                    selector: SyscallSelector::SendMessageToL1,
+                    summary: "to_address=0xabcd, 1 payload felts".to_string(),
+                    keccak_rounds: None,
+                    storage_access: None,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_class_hash_at(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let contract_address = Felt252::from(0x1000);
+        let old_class_hash = Felt252::from(0xc1a55);
+        let new_class_hash = Felt252::from(0xc1a56);
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.deployed_contracts.insert(contract_address, old_class_hash);
+
+        let request = [contract_address.into()];
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_class_hash_at::<PCS>, b"GetClassHashAt", &request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), old_class_hash.into()]);
+
+        // The pending replacement takes precedence over the deployed class
+        set_current_contract_address(&mut vm, &exec_scopes, contract_address).await;
+        run_syscall_hint(&mut vm, &mut exec_scopes, replace_class::<PCS>, b"ReplaceClass", &[new_class_hash.into()], 1);
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_class_hash_at::<PCS>, b"GetClassHashAt", &request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), new_class_hash.into()]);
+
+        let request = [Felt252::from(0x2000).into()];
+        let response =
+            run_syscall_hint(&mut vm, &mut exec_scopes, get_class_hash_at::<PCS>, b"GetClassHashAt", &request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), Felt252::ZERO.into()]);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_replace_class_with_zero(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        set_current_contract_address(&mut vm, &exec_scopes, Felt252::from(0x1000)).await;
+
+        let request = [Felt252::ZERO.into()];
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, replace_class::<PCS>, b"ReplaceClass", &request);
+        assert!(result.is_err());
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        assert!(exec_wrapper.class_replacements().await.is_empty());
+    }
+
+    fn contract_address(address: Felt252) -> ContractAddress {
+        ContractAddress(PatriciaKey::try_from(address).unwrap())
+    }
+
+    #[rstest]
+    #[case::caller_storage(Felt252::from(0x1000), true)]
+    #[case::library_storage(Felt252::from(0x2000), false)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_library_call(
+        mut exec_scopes: ExecutionScopes,
+        #[case] replayed_storage_address: Felt252,
+        #[case] is_valid: bool,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let caller_address = Felt252::from(0x1000);
+        let library_class_hash = Felt252::from(0xc1a55);
+        set_current_contract_address(&mut vm, &exec_scopes, caller_address).await;
+
+        // The replayed library call, its storage writes must land on the caller.
+        let mut library_call_info = CallInfo::default();
+        library_call_info.call.class_hash = Some(ClassHash(library_class_hash));
+        library_call_info.call.storage_address = contract_address(replayed_storage_address);
+        library_call_info.execution.retdata = Retdata(vec![Felt252::from(42)]);
+        let mut caller_call_info = CallInfo::default();
+        caller_call_info.call.storage_address = contract_address(caller_address);
+        caller_call_info.inner_calls = vec![library_call_info];
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        {
+            let mut eh_ref = exec_wrapper.execution_helper.write().await;
+            eh_ref.result_iter =
+                vec![CallResult { failed: false, retdata: Retdata(vec![Felt252::from(42)]), gas_consumed: 1 }]
+                    .into_iter();
+            eh_ref.call_info = Some(caller_call_info);
+        }
+
+        let selector = Felt252::from_hex("0x1234").unwrap();
+        let (calldata_start, calldata_end) = write_felts(&mut vm, &[Felt252::ONE]);
+        let request = [library_class_hash.into(), selector.into(), calldata_start.into(), calldata_end.into()];
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, library_call::<PCS>, b"LibraryCall", &request);
+
+        if !is_valid {
+            assert!(result.is_err());
+            return;
+        }
+        let response = vm.get_continuous_range(result.unwrap(), 3).unwrap();
+        assert_eq!(response[0], Felt252::ZERO.into());
+        let retdata_start = response[1].get_relocatable().unwrap();
+        assert_eq!(response[2], (retdata_start + 1usize).unwrap().into());
+        assert_eq!(vm.get_integer(retdata_start).unwrap().into_owned(), Felt252::from(42));
+    }
+
+    /// Computes `h(h(h(h(0, e0), e1), ...), len)` as in `compute_hash_on_elements`.
+    fn pedersen_hash_on_elements(elements: &[Felt252]) -> Felt252 {
+        elements.iter().chain([&Felt252::from(elements.len())]).fold(Felt252::ZERO, |hash, element| {
+            Felt252::from_bytes_be_slice(&PedersenHash::hash(&hash.to_bytes_be(), &element.to_bytes_be()))
+        })
+    }
+
+    /// Adds its inputs and records them, in call order.
+    #[derive(Default)]
+    struct RecordingHasher {
+        calls: RefCell<Vec<(Felt252, Felt252)>>,
+    }
+
+    impl crate::crypto::hasher::Hasher for RecordingHasher {
+        fn hash(&self, a: Felt252, b: Felt252) -> Felt252 {
+            self.calls.borrow_mut().push((a, b));
+            a + b
+        }
+    }
+
+    #[test]
+    fn test_deploy_address_with_hasher() {
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![Felt252::ONE, Felt252::TWO],
+            deploy_from_zero: false,
+        };
+        let deployer_address = Felt252::from(0x1000);
+        let prefix = Felt252::from_bytes_be_slice(b"STARKNET_CONTRACT_ADDRESS");
+
+        let hasher = RecordingHasher::default();
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &hasher);
+
+        // The constructor calldata is hashed first, then the address preimage, each followed by
+        // its length
+        let calldata_hash = Felt252::from(1 + 2 + 2);
+        let preimage = [prefix, deployer_address, request.contract_address_salt, request.class_hash, calldata_hash];
+        let mut expected_calls =
+            vec![(Felt252::ZERO, Felt252::ONE), (Felt252::ONE, Felt252::TWO), (Felt252::THREE, Felt252::TWO)];
+        let mut hash = Felt252::ZERO;
+        for element in preimage.iter().chain([&Felt252::from(preimage.len())]) {
+            expected_calls.push((hash, *element));
+            hash += *element;
+        }
+        assert_eq!(*hasher.calls.borrow(), expected_calls);
+        // The sum is below the address bound
+        assert_eq!(contract_address, hash);
+    }
+
+    #[rstest]
+    #[case::from_deployer(false, Felt252::from(0x1000))]
+    #[case::from_zero(true, Felt252::ZERO)]
+    fn test_deploy_address_deployer(#[case] deploy_from_zero: bool, #[case] expected_deployer: Felt252) {
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![Felt252::ONE, Felt252::TWO],
+            deploy_from_zero,
+        };
+        let prefix = Felt252::from_bytes_be_slice(b"STARKNET_CONTRACT_ADDRESS");
+
+        // With a summing hasher, the address is the sum of the preimage and of its length, the
+        // calldata hash being 1 + 2 + 2
+        let contract_address =
+            calculate_deployed_contract_address(&request, Felt252::from(0x1000), &RecordingHasher::default());
+        assert_eq!(
+            contract_address,
+            prefix + expected_deployer + Felt252::from(0x5a17) + Felt252::from(0xc1a55) + Felt252::from(5 + 5)
+        );
+    }
+
+    /// Writes a deploy request and the replayed results of the deployment, returns the request.
+    async fn prepare_deploy(
+        vm: &mut VirtualMachine,
+        exec_scopes: &ExecutionScopes,
+        request: &DeployRequest,
+        contract_address: Felt252,
+    ) -> Vec<MaybeRelocatable> {
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        let mut eh_ref = exec_wrapper.execution_helper.write().await;
+        eh_ref.result_iter =
+            vec![CallResult { failed: false, retdata: Retdata(vec![Felt252::from(7)]), gas_consumed: 1 }].into_iter();
+        eh_ref.deployed_contracts_iter = vec![contract_address].into_iter();
+
+        let (calldata_start, calldata_end) = write_felts(vm, &request.constructor_calldata);
+        vec![
+            request.class_hash.into(),
+            request.contract_address_salt.into(),
+            calldata_start.into(),
+            calldata_end.into(),
+            Felt252::from(request.deploy_from_zero as u8).into(),
+        ]
+    }
+
+    #[rstest]
+    #[case::from_deployer(false)]
+    #[case::from_zero(true)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy(mut exec_scopes: ExecutionScopes, #[case] deploy_from_zero: bool) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![Felt252::ONE, Felt252::TWO],
+            deploy_from_zero,
+        };
+
+        // Address formula: h(PREFIX, deployer_address, salt, class_hash, h(constructor_calldata)) mod 2**251 - 256.
+        let hash = pedersen_hash_on_elements(&[
+            Felt252::from_bytes_be_slice(b"STARKNET_CONTRACT_ADDRESS"),
+            if deploy_from_zero { Felt252::ZERO } else { deployer_address },
+            request.contract_address_salt,
+            request.class_hash,
+            pedersen_hash_on_elements(&request.constructor_calldata),
+        ]);
+        let address_bound = (BigUint::from(1u8) << 251) - BigUint::from(256u32);
+        let expected_address = Felt252::from_bytes_be_slice(&(hash.to_biguint() % address_bound).to_bytes_be());
+        assert_eq!(calculate_deployed_contract_address(&request, deployer_address, &PedersenHash), expected_address);
+
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, expected_address).await;
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request, 4);
+        assert_eq!(response[0], Felt252::ZERO.into());
+        assert_eq!(response[1], expected_address.into());
+        let retdata_start = response[2].get_relocatable().unwrap();
+        assert_eq!(vm.get_integer(retdata_start).unwrap().into_owned(), Felt252::from(7));
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        assert_eq!(exec_wrapper.class_hash_of(&expected_address).await, Some(request.class_hash));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_invalid_deploy_from_zero_flag(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+        set_current_contract_address(&mut vm, &exec_scopes, Felt252::from(0x1000)).await;
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![],
+            deploy_from_zero: false,
+        };
+        let mut syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, Felt252::ZERO).await;
+        syscall_request[4] = Felt252::TWO.into();
+
+        let error = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("The deploy_from_zero flag must be 0 or 1"), "{error}");
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_twice_to_the_same_address(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![],
+            deploy_from_zero: false,
+        };
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &PedersenHash);
+
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request, 2);
+        assert_eq!(response, vec![Felt252::ZERO.into(), contract_address.into()]);
+
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case::no_constructor(0)]
+    #[case::at_the_limit(3)]
+    #[case::over_the_limit(4)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_constructor_calldata_len(mut exec_scopes: ExecutionScopes, #[case] calldata_len: usize) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        exec_wrapper.execution_helper.write().await.max_constructor_calldata_len = 3;
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: (0..calldata_len).map(Felt252::from).collect(),
+            deploy_from_zero: false,
+        };
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &PedersenHash);
+
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request);
+        if calldata_len > 3 {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("Constructor calldata of 4 elements exceeds the limit of 3"), "{error}");
+            assert_eq!(exec_wrapper.class_hash_of(&contract_address).await, None);
+        } else {
+            let response = vm.get_continuous_range(result.unwrap(), 2).unwrap();
+            assert_eq!(response, vec![Felt252::ZERO.into(), contract_address.into()]);
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_address_cache(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![Felt252::ONE],
+            deploy_from_zero: false,
+        };
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &PedersenHash);
+
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request, 4);
+        assert_eq!(response[1], contract_address.into());
+        assert_eq!(exec_wrapper.execution_helper.read().await.deployed_address_cache.hits(), 0);
+
+        // The address comes from the cache, and is still rejected as already deployed
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request);
+        assert!(result.is_err());
+        assert_eq!(exec_wrapper.execution_helper.read().await.deployed_address_cache.hits(), 1);
+
+        let other_request = DeployRequest { contract_address_salt: Felt252::from(0x5a18), ..request };
+        let other_address = calculate_deployed_contract_address(&other_request, deployer_address, &PedersenHash);
+        assert_ne!(other_address, contract_address);
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &other_request, other_address).await;
+        let response = run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request, 4);
+        assert_eq!(response[1], other_address.into());
+        assert_eq!(exec_wrapper.execution_helper.read().await.deployed_address_cache.hits(), 1);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_unavailable_address(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![Felt252::ONE],
+            deploy_from_zero: false,
+        };
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &PedersenHash);
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let other_request = DeployRequest { contract_address_salt: Felt252::from(0x5a18), ..request };
+        let other_address = calculate_deployed_contract_address(&other_request, deployer_address, &PedersenHash);
+        let other_syscall_request = prepare_deploy(&mut vm, &exec_scopes, &other_request, other_address).await;
+
+        // The replayed run deploys the contract, fails to deploy it again, then deploys another one
+        {
+            let mut eh_ref = exec_wrapper.execution_helper.write().await;
+            eh_ref.result_iter = [1, 2, 3]
+                .map(|gas_consumed| CallResult { failed: false, retdata: Retdata(vec![]), gas_consumed })
+                .to_vec()
+                .into_iter();
+            eh_ref.deployed_contracts_iter = vec![contract_address, contract_address, other_address].into_iter();
+        }
+
+        let response_ptr =
+            try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request).unwrap();
+        let first_remaining_gas = vm.get_integer((response_ptr - 1usize).unwrap()).unwrap().into_owned();
+        let response = vm.get_continuous_range(response_ptr, 2).unwrap();
+        assert_eq!(response, vec![Felt252::ZERO.into(), contract_address.into()]);
+        let error = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("is unavailable for deployment"), "{error}");
+
+        // The failed deploy consumed its replayed result and address
+        let response_ptr =
+            try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &other_syscall_request).unwrap();
+        let remaining_gas = vm.get_integer((response_ptr - 1usize).unwrap()).unwrap().into_owned();
+        let response = vm.get_continuous_range(response_ptr, 2).unwrap();
+        assert_eq!(response, vec![Felt252::ZERO.into(), other_address.into()]);
+        // The replayed deploys consumed 1 and 3 gas
+        assert_eq!(first_remaining_gas - remaining_gas, Felt252::TWO);
+    }
+
+    #[rstest]
+    #[case::mismatch(Some(Felt252::from(0xdead)), "Deployed contract address mismatch")]
+    #[case::exhausted(None, "No more deployed contracts available to replay")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_replayed_address(
+        mut exec_scopes: ExecutionScopes,
+        #[case] replayed_address: Option<Felt252>,
+        #[case] expected_error: &str,
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        let request = DeployRequest {
+            class_hash: Felt252::from(0xc1a55),
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![Felt252::ONE],
+            deploy_from_zero: false,
+        };
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &PedersenHash);
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        exec_wrapper.execution_helper.write().await.deployed_contracts_iter =
+            replayed_address.into_iter().collect::<Vec<_>>().into_iter();
+
+        let error = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Internal Error"), "{error}");
+        assert!(error.contains(expected_error), "{error}");
+        assert_eq!(exec_wrapper.class_hash_of(&contract_address).await, None);
+    }
+
+    /// Returns the test contract class, which has a constructor, and the same class without it.
+    fn test_contract_classes() -> (ContractClass, ContractClass) {
+        let casm_bytes = include_bytes!(
+            "../../../../tests/integration/contracts/blockifier_contracts/feature_contracts/cairo1/compiled/\
+             test_contract.casm.json"
+        );
+        let mut casm: serde_json::Value = serde_json::from_slice(casm_bytes).unwrap();
+        casm["entry_points_by_type"]["CONSTRUCTOR"] = serde_json::json!([]);
+        let to_contract_class = |casm_bytes: Vec<u8>| -> ContractClass {
+            GenericCasmContractClass::from_bytes(casm_bytes).to_blockifier_contract_class().unwrap().into()
+        };
+        (to_contract_class(casm_bytes.to_vec()), to_contract_class(serde_json::to_vec(&casm).unwrap()))
+    }
+
+    #[rstest]
+    #[case::registered(false)]
+    #[case::verified_mismatch(true)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_deploy_registered_class_hash(mut exec_scopes: ExecutionScopes, #[case] verify: bool) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let deployer_address = Felt252::from(0x1000);
+        set_current_contract_address(&mut vm, &exec_scopes, deployer_address).await;
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+
+        let class_hash = Felt252::from(0xc1a55);
+        let (contract_class, contract_class_without_constructor) = test_contract_classes();
+        exec_wrapper.register_class_hash(contract_class_without_constructor, class_hash).await;
+        if verify {
+            let mut eh_ref = exec_wrapper.execution_helper.write().await;
+            eh_ref.verify_registered_classes = true;
+            eh_ref.class_provider = Some(Box::new(move |_| Some(contract_class.clone())));
+        }
+
+        let request = DeployRequest {
+            class_hash,
+            contract_address_salt: Felt252::from(0x5a17),
+            constructor_calldata: vec![],
+            deploy_from_zero: false,
+        };
+        let contract_address = calculate_deployed_contract_address(&request, deployer_address, &PedersenHash);
+        let syscall_request = prepare_deploy(&mut vm, &exec_scopes, &request, contract_address).await;
+        let result = try_run_syscall_hint(&mut vm, &mut exec_scopes, deploy::<PCS>, b"Deploy", &syscall_request);
+        if verify {
+            let error = result.unwrap_err().to_string();
+            assert!(error.contains("Class hash 0xc1a55 was registered for another class"), "{error}");
+        } else {
+            // The registered class has no constructor, so the retdata is written as `cast(0, felt*)`
+            let response = vm.get_continuous_range(result.unwrap(), 4).unwrap();
+            assert_eq!(
+                response,
+                vec![Felt252::ZERO.into(), contract_address.into(), Felt252::ZERO.into(), Felt252::ZERO.into()]
+            );
+        }
+    }
+
+    /// Writes a keccak syscall request over `input` and returns the syscall pointer.
+    fn prepare_keccak_syscall(vm: &mut VirtualMachine, input: &[u64]) -> Relocatable {
+        let syscall_ptr = vm.add_memory_segment();
+        let input_start = vm.add_memory_segment();
+        for (i, word) in input.iter().enumerate() {
+            vm.insert_value((input_start + i).unwrap(), Felt252::from(*word)).unwrap();
+        }
+
+        // Request: selector, gas, input start, input end.
+        vm.insert_value(syscall_ptr, Felt252::from_bytes_be_slice(b"Keccak")).unwrap();
+        vm.insert_value((syscall_ptr + 1usize).unwrap(), Felt252::from(1_000_000)).unwrap();
+        vm.insert_value((syscall_ptr + 2usize).unwrap(), input_start).unwrap();
+        vm.insert_value((syscall_ptr + 3usize).unwrap(), (input_start + input.len()).unwrap()).unwrap();
+
+        syscall_ptr
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_keccak(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        // Keccak-padded empty message: keccak("") = 0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470.
+        let mut input = [0u64; 17];
+        input[0] = 1;
+        input[16] = 0x8000000000000000;
+        let syscall_ptr = prepare_keccak_syscall(&mut vm, &input);
+
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        keccak::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+
+        // Response: remaining gas, failure flag, result low, result high.
+        let failure_flag = vm.get_integer((syscall_ptr + 5usize).unwrap()).unwrap().into_owned();
+        assert_eq!(failure_flag, Felt252::ZERO);
+        let result_low = vm.get_integer((syscall_ptr + 6usize).unwrap()).unwrap().into_owned();
+        let result_high = vm.get_integer((syscall_ptr + 7usize).unwrap()).unwrap().into_owned();
+        assert_eq!(result_low, Felt252::from_hex_unchecked("0xc003c7dcb27d7e923c23f7860146d2c5"));
+        assert_eq!(result_high, Felt252::from_hex_unchecked("0x70a4855d04d8fa7b3b2782ca53b600e5"));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_keccak_rounds(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_ptr = prepare_keccak_syscall(&mut vm, &[0u64; 34]);
+
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        keccak::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        let failure_flag = vm.get_integer((syscall_ptr + 5usize).unwrap()).unwrap().into_owned();
+        assert_eq!(failure_flag, Felt252::ZERO);
+
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let exec_wrapper = syscall_handler.syscall_handler.read().await.exec_wrapper.clone();
+        let transcript = exec_wrapper.transcript().await;
+        assert_eq!(transcript.entries().len(), 1);
+        assert_eq!(transcript.entries()[0].selector, SyscallSelector::Keccak);
+        assert_eq!(transcript.entries()[0].keccak_rounds, Some(2));
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_keccak_invalid_input_length(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let syscall_ptr = prepare_keccak_syscall(&mut vm, &[1u64; 16]);
+
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+
+        let result = keccak::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants);
+        assert!(matches!(result, Err(HintError::CustomHint(_))));
+        // No response must have been written.
+        assert!(vm.get_maybe(&(syscall_ptr + 4usize).unwrap()).is_none());
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_keccak_syscall_hooks(mut exec_scopes: ExecutionScopes) {
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(1);
+
+        let hooked_syscalls = Rc::new(RefCell::new(vec![]));
+        let syscall_handler = exec_scopes.get::<OsSyscallHandlerWrapper<PCS>>(vars::scopes::SYSCALL_HANDLER).unwrap();
+        let before_syscalls = hooked_syscalls.clone();
+        syscall_handler
+            .set_before_syscall(move |selector, syscall_ptr| before_syscalls.borrow_mut().push((selector, syscall_ptr)))
+            .await;
+        let after_syscalls = hooked_syscalls.clone();
+        syscall_handler
+            .set_after_syscall(move |selector, syscall_ptr| after_syscalls.borrow_mut().push((selector, syscall_ptr)))
+            .await;
+
+        let syscall_ptr = prepare_keccak_syscall(&mut vm, &[0u64; 17]);
+        let ids_data = HashMap::from([(vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1))]);
+        let ap_tracking = ApTracking::new();
+        insert_value_from_var_name(vars::ids::SYSCALL_PTR, syscall_ptr, &mut vm, &ids_data, &ap_tracking).unwrap();
+        keccak::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &HashMap::new()).unwrap();
+
+        // Both hooks fire once, like for the other syscalls
+        assert_eq!(
+            *hooked_syscalls.borrow(),
+            vec![(SyscallSelector::Keccak, syscall_ptr), (SyscallSelector::Keccak, syscall_ptr)]
+        );
+    }
+
+    /// Syscall handler backend that records the calls it receives.
+    #[derive(Default)]
+    struct MockSyscallHandler {
+        syscall_ptr: RefCell<Option<Relocatable>>,
+        executed_syscalls: RefCell<Vec<(SyscallSelector, Relocatable)>>,
+    }
+
+    impl SyscallHandlerBackend for MockSyscallHandler {
+        fn set_syscall_ptr(&self, syscall_ptr: Relocatable) -> Result<(), HintError> {
+            *self.syscall_ptr.borrow_mut() = Some(syscall_ptr);
+            Ok(())
+        }
+
+        fn syscall_ptr(&self) -> Result<Option<Relocatable>, HintError> {
+            Ok(*self.syscall_ptr.borrow())
+        }
+
+        fn execute(
+            &self,
+            selector: SyscallSelector,
+            syscall_ptr: Relocatable,
+            _vm: &mut VirtualMachine,
+        ) -> Result<(), HintError> {
+            self.executed_syscalls.borrow_mut().push((selector, syscall_ptr));
+            Ok(())
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_hints_dispatch_through_syscall_handler_backend() {
+        let mock = Rc::new(MockSyscallHandler::default());
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, mock.clone() as Rc<dyn SyscallHandlerBackend>);
+
+        let mut vm = VirtualMachine::new(false);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_fp(2);
+
+        let ids_data = HashMap::from([
+            (vars::ids::OS_CONTEXT.to_string(), HintReference::new_simple(-2)),
+            (vars::ids::SYSCALL_PTR.to_string(), HintReference::new_simple(-1)),
+        ]);
+        let ap_tracking = ApTracking::new();
+        let constants = HashMap::new();
+
+        set_syscall_ptr::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &constants).unwrap();
+        let syscall_ptr = get_ptr_from_var_name(vars::ids::SYSCALL_PTR, &vm, &ids_data, &ap_tracking).unwrap();
+        assert_eq!(mock.syscall_ptr().unwrap(), Some(syscall_ptr));
+
+        vm.insert_value(syscall_ptr, Felt252::from_bytes_be_slice(b"StorageRead")).unwrap();
+        execute_syscall::<PCS>(&mut vm, &mut exec_scopes, syscall_ptr).unwrap();
+        assert_eq!(*mock.executed_syscalls.borrow(), vec![(SyscallSelector::StorageRead, syscall_ptr)]);
+    }
 }
@@ -13,9 +13,11 @@ pub mod tests {
     use crate::config::STORED_BLOCK_HASH_BUFFER;
     use crate::crypto::pedersen::PedersenHash;
     use crate::execution::helper::ContractStorageMap;
+    use crate::execution::syscall_handler::OsSyscallHandlerWrapper;
     use crate::hints::execute_transactions::fill_holes_in_rc96_segment;
     use crate::hints::find_element::search_sorted_optimistic;
     use crate::hints::*;
+    use crate::io::InternalTransaction;
     use crate::starknet::starknet_storage::OsSingleStarknetStorage;
     use crate::storage::dict_storage::DictStorage;
     use crate::utils::set_variable_in_root_exec_scope;
@@ -167,16 +169,60 @@ pub mod tests {
         let execution_infos = vec![transaction_execution_info];
         let exec_helper =
             EHW::new(ContractStorageMap::default(), execution_infos, &block_context, None, old_block_number_and_hash);
+        let syscall_handler = OsSyscallHandlerWrapper::new(exec_helper.clone());
         let exec_helper_box = Box::new(exec_helper);
         exec_scopes.insert_box(vars::scopes::EXECUTION_HELPER, exec_helper_box.clone());
+        exec_scopes.insert_value(vars::scopes::SYSCALL_HANDLER, syscall_handler.clone());
 
         // before starting tx, tx_execution_info should be none
         assert!(exec_helper_box.execution_helper.read().await.tx_execution_info.is_none());
+        // state left behind by a previous transaction
+        syscall_handler.enter_contract_call().await.unwrap();
 
         start_tx::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default()).expect("start_tx");
 
-        // after starting tx, tx_execution_info should be some
+        // after starting tx, tx_execution_info should be some and the syscall handler reset
         assert!(exec_helper_box.execution_helper.read().await.tx_execution_info.is_some());
+        assert_eq!(syscall_handler.current_call_depth().await, 0);
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_start_tx_increments_deploy_account_nonce(
+        block_context: BlockContext,
+        transaction_execution_info: TransactionExecutionInfo,
+        old_block_number_and_hash: (Felt252, Felt252),
+    ) {
+        let mut vm = VirtualMachine::new(false);
+        vm.set_fp(1);
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+
+        let ids_data = ids_data![vars::ids::DEPRECATED_TX_INFO];
+        let ap_tracking = ApTracking::default();
+
+        let mut exec_scopes = ExecutionScopes::new();
+        let exec_helper = EHW::new(
+            ContractStorageMap::default(),
+            vec![transaction_execution_info],
+            &block_context,
+            None,
+            old_block_number_and_hash,
+        );
+        exec_scopes.insert_value(vars::scopes::EXECUTION_HELPER, exec_helper.clone());
+        let account = Felt252::from(0x1000);
+        let tx = InternalTransaction {
+            r#type: "DEPLOY_ACCOUNT".to_string(),
+            contract_address: Some(account),
+            nonce: Some(Felt252::ZERO),
+            ..Default::default()
+        };
+        exec_scopes.insert_value(vars::scopes::TX, tx);
+
+        start_tx::<PCS>(&mut vm, &mut exec_scopes, &ids_data, &ap_tracking, &Default::default()).expect("start_tx");
+
+        // the deployed account is the sender of the transaction
+        assert_eq!(exec_helper.nonce_at(account).await, Felt252::ONE);
     }
 
     #[rstest]
@@ -22,6 +22,9 @@ pub mod scopes {
     pub const N_SELECTED_BUILTINS: &str = "n_selected_builtins";
     pub const NODE: &str = "node";
     pub const LEFT_CHILD: &str = "left_child";
+    pub const MAX_RETDATA_SIZE: &str = "__max_retdata_size";
+    pub const MEMORY_RANGE_DUMPS: &str = "__memory_range_dumps";
+    pub const MISMATCH_COLLECTOR: &str = "__mismatch_collector";
     pub const OS_INPUT: &str = "os_input";
     pub const PATRICIA_SKIP_VALIDATION_RUNNER: &str = "__patricia_skip_validation_runner";
     pub const PATRICIA_TREE_MODE: &str = "patricia_tree_mode";
@@ -115,6 +118,7 @@ pub mod ids {
     pub const PATH: &str = "path";
     pub const PREV_ROOT: &str = "prev_root";
     pub const PREV_VALUE: &str = "prev_value";
+    pub const RANGE_CHECK_PTR: &str = "range_check_ptr";
     pub const RANGE_CHECK96_PTR: &str = "range_check96_ptr";
     pub const REQUEST: &str = "request";
     pub const REQUEST_BLOCK_NUMBER: &str = "request_block_number";
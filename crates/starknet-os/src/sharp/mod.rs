@@ -1,14 +1,12 @@
+#[cfg(feature = "sharp")]
+mod client;
 pub mod pie;
 
-use std::path::PathBuf;
-
-use cairo_vm::vm::runners::cairo_pie::CairoPie;
-use reqwest::blocking::Client;
 use serde::Deserialize;
-use serde_json::json;
 use uuid::Uuid;
 
-use crate::error::SnOsError;
+#[cfg(feature = "sharp")]
+pub use self::client::{SharpClient, SharpPie};
 
 #[allow(dead_code)]
 pub const DEFUALT_SHARP_URL: &str = "https://testnet.provingservice.io";
@@ -66,69 +64,3 @@ pub struct CairoJobResponse {
     #[serde(rename = "stackTrace")]
     pub stack_trace: Option<Vec<String>>,
 }
-
-pub struct SharpClient {
-    client: Client,
-    sharp_addr: String,
-    pie_path: Option<PathBuf>,
-}
-
-pub enum SharpPie {
-    EncodedPie(String),
-    PieObject(Box<CairoPie>),
-}
-
-impl SharpClient {
-    pub fn submit_pie(&self, pie: SharpPie) -> Result<CairoJobResponse, SnOsError> {
-        let pie_enc = match pie {
-            SharpPie::EncodedPie(encoded_pie) => encoded_pie,
-            SharpPie::PieObject(pie_object) => match &self.pie_path {
-                Some(pp) => pie::encode_pie(*pie_object, pp.as_path())?,
-                None => pie::encode_pie_mem(*pie_object)?,
-            },
-        };
-
-        let data = json!({ "action": "add_job", "request": { "cairo_pie": pie_enc } });
-
-        // CAREFUL NOT TO OVERWHELM SHARP DUE TO SHORT BLOCK TIMES
-        let resp = self
-            .client
-            .post(&self.sharp_addr)
-            .json(&data)
-            .send()
-            .map_err(|e| SnOsError::SharpRequest(format!("{e}")))?;
-
-        match resp.status() {
-            reqwest::StatusCode::OK => resp.json().map_err(|e| SnOsError::SharpRequest(format!("{e}"))),
-            _ => Err(SnOsError::SharpRequest("could not submit pie".to_string())),
-        }
-    }
-
-    pub fn get_status(&self, job_key: &Uuid) -> Result<CairoStatusResponse, SnOsError> {
-        let data = serde_json::json!({ "action": "get_status", "request": { "cairo_job_key": job_key } });
-
-        let resp = self
-            .client
-            .post(&self.sharp_addr)
-            .json(&data)
-            .send()
-            .map_err(|e| SnOsError::SharpRequest(format!("{e}")))?;
-
-        match resp.status() {
-            reqwest::StatusCode::OK => resp.json().map_err(|e| SnOsError::SharpRequest(format!("{e}"))),
-            _ => Err(SnOsError::SharpRequest("could not get job status".to_string())),
-        }
-    }
-    pub fn with_sharp_addr(sharp_addr: &str) -> Self {
-        Self { sharp_addr: sharp_addr.to_string(), ..Self::default() }
-    }
-    pub fn with_pie_path(pie_path: &str) -> Self {
-        Self { pie_path: Some(PathBuf::from(pie_path)), ..Self::default() }
-    }
-}
-
-impl Default for SharpClient {
-    fn default() -> Self {
-        Self { client: Client::new(), sharp_addr: DEFUALT_SHARP_URL.to_string(), pie_path: None }
-    }
-}
@@ -1,3 +1,4 @@
 pub mod contract_class;
 pub mod kzg_manager;
+pub mod os_logger;
 pub mod transaction_hash;
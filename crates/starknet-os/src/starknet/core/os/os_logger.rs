@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use cairo_vm::types::relocatable::Relocatable;
+use cairo_vm::vm::errors::hint_errors::HintError;
+use serde::{Deserialize, Serialize};
+
+use crate::execution::syscall_handler_utils::SyscallSelector;
+
+/// `tracing` target of the events emitted when entering and exiting syscalls.
+pub const SYSCALLS_LOG_TARGET: &str = "snos::syscalls";
+
+/// Resources consumed by syscalls, accumulated per selector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallUsage {
+    /// Number of times the syscall was executed.
+    pub n_calls: usize,
+    /// Total number of Cairo steps spent executing the syscall.
+    pub n_steps: usize,
+    /// Total number of range check cells consumed by the syscall.
+    pub range_check_usage: usize,
+    /// Total number of keccak-f permutations performed by the syscall.
+    #[serde(default)]
+    pub keccak_rounds: usize,
+}
+
+/// Resources consumed by each kind of syscall over a run, e.g. to find out which syscalls are
+/// expensive. Serializes to a JSON object keyed by selector name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SyscallProfile(pub HashMap<SyscallSelector, SyscallUsage>);
+
+impl SyscallProfile {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Builtin instances consumed by a syscall, as computed by its handler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuiltinUsage {
+    /// Name of the builtin, for error messages.
+    pub name: &'static str,
+    /// Segment of the builtin, used to find its pointer in the builtin pointers of the OS.
+    pub segment_index: isize,
+    /// Number of cells by which the OS is expected to advance the builtin pointer.
+    pub n_cells: usize,
+}
+
+#[derive(Debug)]
+struct SyscallEntry {
+    selector: SyscallSelector,
+    deprecated: bool,
+    n_steps: usize,
+    builtin_ptrs: Relocatable,
+    range_check_ptr: Relocatable,
+    builtin_usage: Vec<BuiltinUsage>,
+    keccak_rounds: usize,
+}
+
+/// Tracks the resources consumed by the syscalls executed by the OS.
+///
+/// Mirrors the `OsLogger` of the Python implementation: every `enter_syscall` pushes an entry
+/// on a stack and the matching `exit_syscall` pops it and accounts for the steps and builtins
+/// consumed in between.
+#[derive(Debug, Default)]
+pub struct OsLogger {
+    syscall_stack: Vec<SyscallEntry>,
+    syscall_usage: HashMap<SyscallSelector, SyscallUsage>,
+    // Step counter of the VM when the last syscall was entered
+    steps_consumed: usize,
+    max_total_steps: Option<usize>,
+}
+
+impl OsLogger {
+    /// Makes `enter_syscall` fail once the step counter of the VM exceeds `max_total_steps`, e.g.
+    /// to stop runaway contracts in environments that do not model gas.
+    pub fn set_max_total_steps(&mut self, max_total_steps: usize) {
+        self.max_total_steps = Some(max_total_steps);
+    }
+
+    /// Steps executed by the VM when the last syscall was entered.
+    pub fn steps_consumed(&self) -> usize {
+        self.steps_consumed
+    }
+
+    /// Pushes the syscall `selector` entered at step `n_steps`. Fails if `n_steps` exceeds the
+    /// step budget, see `set_max_total_steps`.
+    pub fn enter_syscall(
+        &mut self,
+        selector: SyscallSelector,
+        deprecated: bool,
+        n_steps: usize,
+        builtin_ptrs: Relocatable,
+        range_check_ptr: Relocatable,
+    ) -> Result<(), HintError> {
+        tracing::trace!(target: SYSCALLS_LOG_TARGET, selector = ?selector, deprecated, n_steps, "entering syscall");
+        self.steps_consumed = n_steps;
+        if self.max_total_steps.is_some_and(|max_total_steps| n_steps > max_total_steps) {
+            return Err(HintError::CustomHint("step budget exceeded".into()));
+        }
+        self.syscall_stack.push(SyscallEntry {
+            selector,
+            deprecated,
+            n_steps,
+            builtin_ptrs,
+            range_check_ptr,
+            builtin_usage: vec![],
+            keccak_rounds: 0,
+        });
+        Ok(())
+    }
+
+    /// Records builtin instances consumed by the handler of `selector`, to be checked against the
+    /// builtin pointers when the syscall exits. Usage recorded while `selector` is not the syscall
+    /// entered last cannot be attributed and is ignored.
+    pub fn record_builtin_usage(&mut self, selector: SyscallSelector, usage: BuiltinUsage) {
+        match self.syscall_stack.last_mut() {
+            Some(entry) if entry.selector == selector => entry.builtin_usage.push(usage),
+            _ => log::debug!("Ignoring {} usage of syscall {:?}: it was not entered", usage.name, selector),
+        }
+    }
+
+    /// Records keccak-f permutations performed by the handler of `selector`, attributed like
+    /// `record_builtin_usage`.
+    pub fn record_keccak_rounds(&mut self, selector: SyscallSelector, n_rounds: usize) {
+        match self.syscall_stack.last_mut() {
+            Some(entry) if entry.selector == selector => entry.keccak_rounds += n_rounds,
+            _ => log::debug!("Ignoring {} keccak rounds of syscall {:?}: it was not entered", n_rounds, selector),
+        }
+    }
+
+    /// Builtin pointers of the syscall entered last.
+    pub fn entered_builtin_ptrs(&self) -> Option<Relocatable> {
+        self.syscall_stack.last().map(|entry| entry.builtin_ptrs)
+    }
+
+    /// Pops the syscall entered last and returns the resources it consumed.
+    ///
+    /// `builtin_deltas` maps the segment of each builtin to the number of cells its pointer
+    /// advanced during the syscall. It must agree with the usage recorded by the handler through
+    /// `record_builtin_usage`; builtins the handler does not account for are not checked.
+    pub fn exit_syscall(
+        &mut self,
+        selector: SyscallSelector,
+        n_steps: usize,
+        builtin_ptrs: Relocatable,
+        range_check_ptr: Relocatable,
+        builtin_deltas: &HashMap<isize, usize>,
+    ) -> Result<SyscallUsage, HintError> {
+        let entry = self.syscall_stack.pop().ok_or(HintError::AssertionFailed(
+            format!("Exiting syscall {:?} without entering it.", selector).into_boxed_str(),
+        ))?;
+        if entry.selector != selector {
+            return Err(HintError::AssertionFailed(
+                format!("Exiting syscall {:?}, expected {:?}.", selector, entry.selector).into_boxed_str(),
+            ));
+        }
+
+        let n_steps = n_steps.checked_sub(entry.n_steps).ok_or(HintError::AssertionFailed(
+            format!("Step counter went backwards while executing syscall {:?}.", selector).into_boxed_str(),
+        ))?;
+        let range_check_usage = (range_check_ptr - entry.range_check_ptr)?;
+        for builtin_usage in &entry.builtin_usage {
+            let delta = builtin_deltas.get(&builtin_usage.segment_index).copied().unwrap_or_default();
+            if delta != builtin_usage.n_cells {
+                return Err(HintError::AssertionFailed(
+                    format!(
+                        "Syscall {:?} consumed {} cells of the {} builtin, but its pointer advanced by {}.",
+                        selector, builtin_usage.n_cells, builtin_usage.name, delta
+                    )
+                    .into_boxed_str(),
+                ));
+            }
+        }
+        let usage = SyscallUsage { n_calls: 1, n_steps, range_check_usage, keccak_rounds: entry.keccak_rounds };
+
+        tracing::trace!(
+            target: SYSCALLS_LOG_TARGET,
+            selector = ?selector,
+            deprecated = entry.deprecated,
+            n_steps,
+            range_check_usage,
+            builtin_ptrs = %builtin_ptrs,
+            prev_builtin_ptrs = %entry.builtin_ptrs,
+            "exiting syscall"
+        );
+
+        let total = self.syscall_usage.entry(selector).or_default();
+        total.n_calls += usage.n_calls;
+        total.n_steps += usage.n_steps;
+        total.range_check_usage += usage.range_check_usage;
+        total.keccak_rounds += usage.keccak_rounds;
+
+        Ok(usage)
+    }
+
+    /// Resources accumulated so far, per syscall.
+    pub fn syscall_usage(&self) -> &HashMap<SyscallSelector, SyscallUsage> {
+        &self.syscall_usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct CapturedEvent {
+        target: String,
+        level: Level,
+        fields: HashMap<String, String>,
+    }
+
+    impl Visit for CapturedEvent {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.fields.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    /// Minimal subscriber that records every event it receives.
+    #[derive(Clone, Default)]
+    struct EventCollector {
+        events: Arc<Mutex<Vec<CapturedEvent>>>,
+    }
+
+    impl Subscriber for EventCollector {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let metadata = event.metadata();
+            let mut captured_event = CapturedEvent {
+                target: metadata.target().to_string(),
+                level: *metadata.level(),
+                fields: HashMap::new(),
+            };
+            event.record(&mut captured_event);
+            self.events.lock().unwrap().push(captured_event);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_enter_exit_syscall() {
+        let mut os_logger = OsLogger::default();
+        let builtin_ptrs = Relocatable::from((1, 0));
+
+        os_logger
+            .enter_syscall(SyscallSelector::CallContract, false, 100, builtin_ptrs, Relocatable::from((2, 0)))
+            .unwrap();
+        os_logger
+            .enter_syscall(SyscallSelector::StorageRead, false, 120, builtin_ptrs, Relocatable::from((2, 3)))
+            .unwrap();
+        let usage = os_logger
+            .exit_syscall(SyscallSelector::StorageRead, 150, builtin_ptrs, (2, 5).into(), &HashMap::new())
+            .unwrap();
+        assert_eq!(usage, SyscallUsage { n_calls: 1, n_steps: 30, range_check_usage: 2, keccak_rounds: 0 });
+        os_logger
+            .exit_syscall(SyscallSelector::CallContract, 200, builtin_ptrs, (2, 10).into(), &HashMap::new())
+            .unwrap();
+
+        os_logger
+            .enter_syscall(SyscallSelector::StorageRead, true, 300, builtin_ptrs, Relocatable::from((2, 10)))
+            .unwrap();
+        os_logger
+            .exit_syscall(SyscallSelector::StorageRead, 310, builtin_ptrs, (2, 11).into(), &HashMap::new())
+            .unwrap();
+
+        let syscall_usage = os_logger.syscall_usage();
+        assert_eq!(
+            syscall_usage[&SyscallSelector::CallContract],
+            SyscallUsage { n_calls: 1, n_steps: 100, range_check_usage: 10, keccak_rounds: 0 }
+        );
+        assert_eq!(
+            syscall_usage[&SyscallSelector::StorageRead],
+            SyscallUsage { n_calls: 2, n_steps: 40, range_check_usage: 3, keccak_rounds: 0 }
+        );
+    }
+
+    #[test]
+    fn test_max_total_steps() {
+        let mut os_logger = OsLogger::default();
+        os_logger.set_max_total_steps(100);
+        let builtin_ptrs = Relocatable::from((1, 0));
+
+        os_logger.enter_syscall(SyscallSelector::StorageRead, false, 40, builtin_ptrs, (2, 0).into()).unwrap();
+        os_logger.exit_syscall(SyscallSelector::StorageRead, 60, builtin_ptrs, (2, 1).into(), &HashMap::new()).unwrap();
+        // The budget is inclusive
+        os_logger.enter_syscall(SyscallSelector::StorageRead, false, 100, builtin_ptrs, (2, 1).into()).unwrap();
+        os_logger
+            .exit_syscall(SyscallSelector::StorageRead, 101, builtin_ptrs, (2, 2).into(), &HashMap::new())
+            .unwrap();
+        assert_eq!(os_logger.steps_consumed(), 100);
+
+        let result = os_logger.enter_syscall(SyscallSelector::StorageRead, false, 101, builtin_ptrs, (2, 2).into());
+        assert!(matches!(result, Err(HintError::CustomHint(msg)) if msg.as_ref() == "step budget exceeded"));
+        assert_eq!(os_logger.steps_consumed(), 101);
+    }
+
+    #[test]
+    fn test_exit_syscall_selector_mismatch() {
+        let mut os_logger = OsLogger::default();
+        let builtin_ptrs = Relocatable::from((1, 0));
+
+        assert!(
+            os_logger.exit_syscall(SyscallSelector::Deploy, 10, builtin_ptrs, (2, 0).into(), &HashMap::new()).is_err()
+        );
+
+        os_logger.enter_syscall(SyscallSelector::Deploy, false, 0, builtin_ptrs, Relocatable::from((2, 0))).unwrap();
+        assert!(
+            os_logger
+                .exit_syscall(SyscallSelector::EmitEvent, 10, builtin_ptrs, (2, 0).into(), &HashMap::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_exit_syscall_builtin_usage() {
+        let mut os_logger = OsLogger::default();
+        let builtin_ptrs = Relocatable::from((1, 0));
+        let keccak_usage = BuiltinUsage { name: "keccak", segment_index: 5, n_cells: 32 };
+
+        os_logger.enter_syscall(SyscallSelector::Keccak, false, 0, builtin_ptrs, Relocatable::from((2, 0))).unwrap();
+        os_logger.record_builtin_usage(SyscallSelector::Keccak, keccak_usage);
+        os_logger.record_keccak_rounds(SyscallSelector::Keccak, 2);
+        let usage = os_logger
+            .exit_syscall(SyscallSelector::Keccak, 10, builtin_ptrs, (2, 0).into(), &HashMap::from([(5, 32)]))
+            .unwrap();
+        assert_eq!(usage.keccak_rounds, 2);
+
+        // The exit hint claims that the keccak pointer advanced by a single instance
+        os_logger.enter_syscall(SyscallSelector::Keccak, false, 20, builtin_ptrs, Relocatable::from((2, 0))).unwrap();
+        os_logger.record_builtin_usage(SyscallSelector::Keccak, keccak_usage);
+        let result =
+            os_logger.exit_syscall(SyscallSelector::Keccak, 30, builtin_ptrs, (2, 0).into(), &HashMap::from([(5, 16)]));
+        match result {
+            Err(HintError::AssertionFailed(message)) => assert!(message.contains("keccak builtin"), "{message}"),
+            other => panic!("expected a builtin usage mismatch, got {:?}", other),
+        }
+
+        // Usage recorded for a syscall that was not entered is not checked
+        os_logger
+            .enter_syscall(SyscallSelector::StorageRead, false, 40, builtin_ptrs, Relocatable::from((2, 0)))
+            .unwrap();
+        os_logger.record_builtin_usage(SyscallSelector::Keccak, keccak_usage);
+        os_logger.exit_syscall(SyscallSelector::StorageRead, 50, builtin_ptrs, (2, 0).into(), &HashMap::new()).unwrap();
+    }
+
+    #[test]
+    fn test_exit_syscall_emits_trace_event() {
+        let mut os_logger = OsLogger::default();
+        let builtin_ptrs = Relocatable::from((1, 0));
+        os_logger
+            .enter_syscall(SyscallSelector::StorageRead, false, 0, builtin_ptrs, Relocatable::from((2, 0)))
+            .unwrap();
+
+        let collector = EventCollector::default();
+        tracing::subscriber::with_default(collector.clone(), || {
+            os_logger
+                .exit_syscall(SyscallSelector::StorageRead, 10, builtin_ptrs, (2, 1).into(), &HashMap::new())
+                .unwrap();
+        });
+
+        let events = collector.events.lock().unwrap();
+        let syscall_events: Vec<_> = events.iter().filter(|event| event.target == SYSCALLS_LOG_TARGET).collect();
+        assert_eq!(syscall_events.len(), 1);
+        assert_eq!(syscall_events[0].level, Level::TRACE);
+        assert_eq!(syscall_events[0].fields["selector"], "StorageRead");
+    }
+}
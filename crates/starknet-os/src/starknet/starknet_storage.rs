@@ -15,7 +15,7 @@ use crate::starkware_utils::commitment_tree::errors::TreeError;
 use crate::starkware_utils::commitment_tree::leaf_fact::LeafFact;
 use crate::starkware_utils::commitment_tree::patricia_tree::patricia_tree::PatriciaTree;
 use crate::starkware_utils::serializable::{DeserializeError, Serializable, SerializationPrefix, SerializeError};
-use crate::storage::storage::{DbObject, Fact, FactFetchingContext, HashFunctionType, Storage};
+use crate::storage::storage::{DbObject, Fact, FactFetchingContext, HashFunctionType, Storage, StorageError};
 use crate::utils::{Felt252Num, Felt252Str};
 
 #[derive(Clone, Debug, PartialEq)]
@@ -178,6 +178,11 @@ impl CommitmentInfo {
 pub trait PerContractStorage {
     async fn compute_commitment(&mut self) -> Result<CommitmentInfo, CommitmentInfoError>;
     async fn read(&mut self, key: TreeIndex) -> Option<Felt252>;
+    /// Like `read`, but reports the failures of the storage backend instead of panicking.
+    /// `Ok(None)` means that the key is absent.
+    async fn try_read(&mut self, key: TreeIndex) -> Result<Option<Felt252>, StorageError> {
+        Ok(self.read(key).await)
+    }
     fn write(&mut self, key: TreeIndex, value: Felt252);
 }
 
@@ -1,6 +1,8 @@
 pub mod cached_storage;
 pub mod composite_storage;
 pub mod dict_storage;
+#[cfg(feature = "rpc")]
+pub mod rpc_storage;
 #[allow(clippy::module_inception)] // Use the same name as the parent module
 pub mod storage;
 pub mod storage_utils;
@@ -0,0 +1,256 @@
+use std::rc::Rc;
+
+use cairo_vm::Felt252;
+use futures::FutureExt;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::execution::helper::StorageFallback;
+use crate::storage::storage::StorageError;
+use crate::utils::execute_coroutine;
+
+/// `CONTRACT_NOT_FOUND` error code of the Starknet JSON-RPC specification.
+const CONTRACT_NOT_FOUND_ERROR_CODE: i64 = 20;
+
+/// The block whose state `RpcStorageBackend` reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcBlockId {
+    Number(u64),
+    Hash(Felt252),
+    Latest,
+    Pending,
+}
+
+impl RpcBlockId {
+    fn to_json(self) -> serde_json::Value {
+        match self {
+            Self::Number(block_number) => json!({ "block_number": block_number }),
+            Self::Hash(block_hash) => json!({ "block_hash": block_hash.to_hex_string() }),
+            Self::Latest => json!("latest"),
+            Self::Pending => json!("pending"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: Option<Felt252>,
+    error: Option<RpcError>,
+}
+
+/// A request that failed, and whether sending it again may succeed.
+#[derive(Debug)]
+struct SendError {
+    error: StorageError,
+    transient: bool,
+}
+
+impl SendError {
+    fn from_reqwest(error: reqwest::Error) -> Self {
+        // Transport errors, timeouts, server errors and rate limiting are worth retrying
+        let transient = error.is_connect()
+            || error.is_timeout()
+            || error.is_request()
+            || error
+                .status()
+                .is_some_and(|status| status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS);
+        Self { error: StorageError::Backend(error.to_string()), transient }
+    }
+}
+
+/// Reads contract storage from a Starknet node through `starknet_getStorageAt`.
+///
+/// Plug it into the execution helper with `ExecutionHelperWrapper::with_storage_fallback` and
+/// `into_storage_fallback`, or call `get_storage_at_blocking` from synchronous code.
+#[derive(Clone, Debug)]
+pub struct RpcStorageBackend {
+    client: reqwest::Client,
+    url: String,
+    block_id: RpcBlockId,
+    max_retries: usize,
+}
+
+impl RpcStorageBackend {
+    pub fn new(url: impl Into<String>, block_id: RpcBlockId) -> Self {
+        Self { client: reqwest::Client::new(), url: url.into(), block_id, max_retries: 3 }
+    }
+
+    /// Number of times a request that failed because of the transport, a timeout, a server error
+    /// or rate limiting is sent again before giving up. Other failures are not retried.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Returns the value of `key` in the storage of `contract_address`, or `None` if the node does
+    /// not know the contract.
+    pub async fn get_storage_at(
+        &self,
+        contract_address: Felt252,
+        key: Felt252,
+    ) -> Result<Option<Felt252>, StorageError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_getStorageAt",
+            "params": {
+                "contract_address": contract_address.to_hex_string(),
+                "key": key.to_hex_string(),
+                "block_id": self.block_id.to_json(),
+            },
+        });
+
+        let mut attempt = 0;
+        let response = loop {
+            match self.send(&request).await {
+                Ok(response) => break response,
+                Err(e) if !e.transient || attempt >= self.max_retries => return Err(e.error),
+                Err(e) => {
+                    log::warn!("starknet_getStorageAt failed (attempt {}): {}", attempt + 1, e.error);
+                    attempt += 1;
+                }
+            }
+        };
+
+        match (response.result, response.error) {
+            (Some(value), _) => Ok(Some(value)),
+            (None, Some(error)) if error.code == CONTRACT_NOT_FOUND_ERROR_CODE => Ok(None),
+            (None, Some(error)) => {
+                Err(StorageError::Backend(format!("starknet_getStorageAt error {}: {}", error.code, error.message)))
+            }
+            (None, None) => Err(StorageError::Backend("starknet_getStorageAt returned no result".to_string())),
+        }
+    }
+
+    async fn send(&self, request: &serde_json::Value) -> Result<RpcResponse, SendError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(SendError::from_reqwest)?;
+        // The body is complete once received: failing to decode it will fail again
+        response.json().await.map_err(|e| SendError { error: StorageError::Backend(e.to_string()), transient: false })
+    }
+
+    /// Like `get_storage_at`, for synchronous code running on a Tokio runtime, e.g. hints.
+    pub fn get_storage_at_blocking(
+        &self,
+        contract_address: Felt252,
+        key: Felt252,
+    ) -> Result<Option<Felt252>, StorageError> {
+        execute_coroutine(self.get_storage_at(contract_address, key))
+            .map_err(|e| StorageError::Backend(e.to_string()))?
+    }
+
+    pub fn into_storage_fallback(self) -> StorageFallback {
+        let backend = Rc::new(self);
+        Box::new(move |contract_address, key| {
+            let backend = backend.clone();
+            async move { backend.get_storage_at(contract_address, key).await }.boxed_local()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    use super::*;
+
+    /// Serves `responses` in order, one per connection, and returns the bodies of the requests.
+    fn mock_rpc_server(responses: Vec<(u16, String)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut request = vec![0; content_length];
+                reader.read_exact(&mut request).unwrap();
+                requests.push(String::from_utf8(request).unwrap());
+
+                let response = format!(
+                    "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+                     close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_storage_at() {
+        let (url, server) = mock_rpc_server(vec![
+            (500, String::new()),
+            (200, r#"{"jsonrpc":"2.0","id":1,"result":"0x1f40"}"#.to_string()),
+            (200, r#"{"jsonrpc":"2.0","id":1,"error":{"code":20,"message":"Contract not found"}}"#.to_string()),
+        ]);
+        let backend = RpcStorageBackend::new(url, RpcBlockId::Number(1000)).with_max_retries(1);
+
+        // The first request fails and is retried
+        let value = backend.get_storage_at(Felt252::from(0x100), Felt252::from(42)).await.unwrap();
+        assert_eq!(value, Some(Felt252::from(8000)));
+        let value = backend.get_storage_at(Felt252::from(0x200), Felt252::from(42)).await.unwrap();
+        assert_eq!(value, None);
+
+        let requests = server.join().unwrap();
+        let request: serde_json::Value = serde_json::from_str(&requests[1]).unwrap();
+        assert_eq!(request["method"], "starknet_getStorageAt");
+        assert_eq!(request["params"]["contract_address"], "0x100");
+        assert_eq!(request["params"]["key"], "0x2a");
+        assert_eq!(request["params"]["block_id"], json!({ "block_number": 1000 }));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_storage_at_errors_are_not_retried() {
+        let (url, server) = mock_rpc_server(vec![
+            (200, r#"{"jsonrpc":"2.0","id":1,"error":{"code":24,"message":"Block not found"}}"#.to_string()),
+            (400, String::new()),
+        ]);
+        let backend = RpcStorageBackend::new(url, RpcBlockId::Number(1000)).with_max_retries(3);
+
+        let error = backend.get_storage_at(Felt252::from(0x100), Felt252::ONE).await.unwrap_err().to_string();
+        assert!(error.contains("starknet_getStorageAt error 24: Block not found"), "{error}");
+        let error = backend.get_storage_at(Felt252::from(0x100), Felt252::ONE).await.unwrap_err().to_string();
+        assert!(error.contains("400"), "{error}");
+
+        // Each request was sent once
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_get_storage_at_blocking() {
+        let (url, server) = mock_rpc_server(vec![(200, r#"{"jsonrpc":"2.0","id":1,"result":"0x7"}"#.to_string())]);
+        let backend = RpcStorageBackend::new(url, RpcBlockId::Latest);
+
+        let value = backend.get_storage_at_blocking(Felt252::from(0x100), Felt252::ONE).unwrap();
+        assert_eq!(value, Some(Felt252::from(7)));
+        server.join().unwrap();
+    }
+}
@@ -8,6 +8,7 @@ use starknet_os_types::hash::Hash;
 use tokio::sync::Mutex;
 
 use crate::starkware_utils::serializable::{DeserializeError, Serializable, SerializeError};
+use crate::utils::display_felt;
 
 pub const HASH_BYTES: usize = 32;
 
@@ -21,6 +22,19 @@ pub enum StorageError {
 
     #[error(transparent)]
     Serialize(#[from] SerializeError),
+
+    #[error("Storage backend failure: {0}")]
+    Backend(String),
+
+    #[error(
+        "Storage of contract {} accessed during a call to contract {}",
+        display_felt(.actual),
+        display_felt(.expected)
+    )]
+    UnexpectedContractAddress { expected: Felt252, actual: Felt252 },
+
+    #[error("Storage key {} of contract {} is reserved", display_felt(.key), display_felt(.contract_address))]
+    ReservedStorageKey { contract_address: Felt252, key: Felt252 },
 }
 
 impl From<StorageError> for StateError {
@@ -1,7 +1,10 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use cairo_vm::types::exec_scope::ExecutionScopes;
+use cairo_vm::types::relocatable::Relocatable;
 use cairo_vm::vm::errors::hint_errors::HintError;
+use cairo_vm::vm::vm_core::VirtualMachine;
 use cairo_vm::{any_box, Felt252};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Number;
@@ -158,17 +161,117 @@ where
     exec_scopes.data[0].insert(name.to_string(), any_box!(value));
 }
 
+/// Fetches the syscall handler stored under `name` in the current execution scope.
+///
+/// Unlike a plain `exec_scopes.get`, a missing handler is reported explicitly since it usually
+/// means that the OS did not go through the hints that install it.
+pub(crate) fn get_syscall_handler<T>(exec_scopes: &ExecutionScopes, name: &str) -> Result<T, HintError>
+where
+    T: Clone + 'static,
+{
+    exec_scopes.get::<T>(name).map_err(|_| {
+        custom_hint_error(
+            "syscall handler not installed in execution scopes; call set_syscall_ptr / initialize handler first",
+        )
+    })
+}
+
+/// How felts are rendered in error messages, see `set_felt_format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FeltFormat {
+    Decimal,
+    /// Matches block explorers and most Starknet tooling.
+    #[default]
+    Hex,
+}
+
+static FELT_FORMAT_IS_DECIMAL: AtomicBool = AtomicBool::new(false);
+
+/// Sets the format of the felts, e.g. addresses and storage keys, in the error messages of the
+/// crate. Counts and lengths are always rendered in decimal.
+pub fn set_felt_format(format: FeltFormat) {
+    FELT_FORMAT_IS_DECIMAL.store(format == FeltFormat::Decimal, Ordering::Relaxed);
+}
+
+pub fn felt_format() -> FeltFormat {
+    if FELT_FORMAT_IS_DECIMAL.load(Ordering::Relaxed) { FeltFormat::Decimal } else { FeltFormat::Hex }
+}
+
+pub fn fmt_felt(felt: &Felt252, format: FeltFormat) -> String {
+    match format {
+        FeltFormat::Decimal => felt.to_string(),
+        FeltFormat::Hex => felt.to_hex_string(),
+    }
+}
+
+/// Renders `felt` in the format set with `set_felt_format`, for error messages.
+pub(crate) fn display_felt(felt: &Felt252) -> String {
+    fmt_felt(felt, felt_format())
+}
+
 /// Builds a custom hint error
 pub(crate) fn custom_hint_error<S: Into<String>>(error: S) -> HintError {
     HintError::CustomHint(error.into().into_boxed_str())
 }
 
+/// Reads the felts in `[start, end)`, e.g. an array passed to a syscall as a pair of pointers.
+pub fn read_felt_array(vm: &VirtualMachine, start: Relocatable, end: Relocatable) -> Result<Vec<Felt252>, HintError> {
+    if start.segment_index != end.segment_index || end.offset < start.offset {
+        return Err(custom_hint_error(format!("Invalid felt array: end {end} is not after start {start}")));
+    }
+    read_felt_span(vm, start, end.offset - start.offset)
+}
+
+/// Reads `len` felts starting at `ptr`, e.g. an array passed to a syscall as a pointer and a size.
+pub fn read_felt_span(vm: &VirtualMachine, ptr: Relocatable, len: usize) -> Result<Vec<Felt252>, HintError> {
+    let values = vm
+        .get_integer_range(ptr, len)
+        .map_err(|e| custom_hint_error(format!("Invalid felt array of {len} elements at {ptr}: {e}")))?;
+    Ok(values.into_iter().map(|felt| felt.into_owned()).collect())
+}
+
+/// Checks that two memory ranges hold the same values, e.g. the expected and actual retdata
+/// of a call.
+pub fn assert_memory_ranges_equal(
+    vm: &VirtualMachine,
+    expected_ptr: Relocatable,
+    expected_size: usize,
+    actual_ptr: Relocatable,
+    actual_size: usize,
+) -> Result<(), HintError> {
+    if expected_size != actual_size {
+        return Err(HintError::AssertionFailed(
+            format!("Return value length mismatch: expected {expected_size}, got {actual_size}.").into_boxed_str(),
+        ));
+    }
+
+    let expected = vm.get_range(expected_ptr, expected_size);
+    let actual = vm.get_range(actual_ptr, actual_size);
+
+    if expected != actual {
+        return Err(HintError::AssertionFailed(
+            format!("Return value mismatch expected={expected:?}, actual={actual:?}.").into_boxed_str(),
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
     use serde_with::serde_as;
 
     use super::*;
 
+    #[test]
+    fn test_fmt_felt() {
+        let felt = Felt252::from(8000);
+        assert_eq!(fmt_felt(&felt, FeltFormat::Hex), "0x1f40");
+        assert_eq!(fmt_felt(&felt, FeltFormat::Decimal), "8000");
+        assert_eq!(FeltFormat::default(), FeltFormat::Hex);
+    }
+
     #[serde_as]
     #[derive(Serialize)]
     struct ChainIdOnly {
@@ -181,4 +284,65 @@ mod tests {
         let c = ChainIdOnly { chain_id: ChainId::Sepolia };
         serde_json::to_string(&c).unwrap();
     }
+
+    fn vm_with_ranges(expected: &[u64], actual: &[u64]) -> (VirtualMachine, Relocatable, Relocatable) {
+        let mut vm = VirtualMachine::new(false);
+        let expected_ptr = vm.add_memory_segment();
+        let actual_ptr = vm.add_memory_segment();
+        for (ptr, values) in [(expected_ptr, expected), (actual_ptr, actual)] {
+            for (i, value) in values.iter().enumerate() {
+                vm.insert_value((ptr + i).unwrap(), Felt252::from(*value)).unwrap();
+            }
+        }
+        (vm, expected_ptr, actual_ptr)
+    }
+
+    #[test]
+    fn test_read_felt_array() {
+        let (vm, ptr, _) = vm_with_ranges(&[1, 2, 3], &[]);
+        let end = (ptr + 3usize).unwrap();
+        let expected: Vec<_> = [1, 2, 3].into_iter().map(Felt252::from).collect();
+        assert_eq!(read_felt_array(&vm, ptr, end).unwrap(), expected);
+        assert_eq!(read_felt_span(&vm, ptr, 3).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_read_felt_array_empty() {
+        let (vm, ptr, empty_ptr) = vm_with_ranges(&[1, 2, 3], &[]);
+        assert_eq!(read_felt_array(&vm, ptr, ptr).unwrap(), vec![]);
+        assert_eq!(read_felt_span(&vm, empty_ptr, 0).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_read_felt_array_invalid_range() {
+        let (vm, ptr, other_ptr) = vm_with_ranges(&[1, 2, 3], &[4]);
+        let end = (ptr + 2usize).unwrap();
+        let error = read_felt_array(&vm, end, ptr).unwrap_err();
+        assert_matches!(error, HintError::CustomHint(message) if message.starts_with("Invalid felt array: end"));
+        assert!(read_felt_array(&vm, ptr, other_ptr).is_err());
+        // The range goes past the written cells of the segment
+        let error = read_felt_span(&vm, ptr, 4).unwrap_err();
+        assert_matches!(error, HintError::CustomHint(message) if message.starts_with("Invalid felt array of 4 elements"));
+    }
+
+    #[test]
+    fn test_assert_memory_ranges_equal() {
+        let (vm, expected_ptr, actual_ptr) = vm_with_ranges(&[1, 2, 3], &[1, 2, 3]);
+        assert_memory_ranges_equal(&vm, expected_ptr, 3, actual_ptr, 3).unwrap();
+    }
+
+    #[test]
+    fn test_assert_memory_ranges_equal_length_mismatch() {
+        let (vm, expected_ptr, actual_ptr) = vm_with_ranges(&[1, 2, 3], &[1, 2]);
+        let error = assert_memory_ranges_equal(&vm, expected_ptr, 3, actual_ptr, 2).unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message)
+            if message.as_ref() == "Return value length mismatch: expected 3, got 2.");
+    }
+
+    #[test]
+    fn test_assert_memory_ranges_equal_content_mismatch() {
+        let (vm, expected_ptr, actual_ptr) = vm_with_ranges(&[1, 2, 3], &[1, 5, 3]);
+        let error = assert_memory_ranges_equal(&vm, expected_ptr, 3, actual_ptr, 3).unwrap_err();
+        assert_matches!(error, HintError::AssertionFailed(message) if message.starts_with("Return value mismatch"));
+    }
 }